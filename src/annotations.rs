@@ -0,0 +1,80 @@
+// User labels for ranges of memory, shown by the debugger. Loaded from a text file with one
+// region per line, `START-END LABEL` or `ADDR LABEL`, in decimal or 0x-prefixed hex. Lines
+// starting with `#` are comments
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, Clone)]
+pub struct MemoryRegion {
+    pub start: u16,
+    pub end: u16, // Inclusive
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAnnotations {
+    regions: Vec<MemoryRegion>,
+}
+
+impl MemoryAnnotations {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path:?}: {e}")))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut regions = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let region =
+                Self::parse_region(line).map_err(|e| format!("line {}: {e}", number + 1))?;
+            regions.push(region);
+        }
+        Ok(Self { regions })
+    }
+
+    fn parse_region(line: &str) -> Result<MemoryRegion, String> {
+        let (range, label) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("'{line}' has no label"))?;
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => (Self::parse_addr(start)?, Self::parse_addr(end)?),
+            None => {
+                let addr = Self::parse_addr(range)?;
+                (addr, addr)
+            }
+        };
+        if end < start {
+            return Err(format!("'{range}' ends before it starts"));
+        }
+        Ok(MemoryRegion {
+            start,
+            end,
+            label: label.trim().to_string(),
+        })
+    }
+
+    fn parse_addr(value: &str) -> Result<u16, String> {
+        let parsed = match value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+        {
+            Some(hex) => u16::from_str_radix(hex, 16),
+            None => value.parse(),
+        };
+        parsed.map_err(|e| format!("'{value}' is not an address: {e}"))
+    }
+
+    // The label of the first region containing `addr`
+    pub fn label_at(&self, addr: u16) -> Option<&str> {
+        self.regions
+            .iter()
+            .find(|region| (region.start..=region.end).contains(&addr))
+            .map(|region| region.label.as_str())
+    }
+}
@@ -0,0 +1,152 @@
+// Sound output. `Hardware` emits an `AudioEvent` whenever the beeper changes, and whichever
+// backend is active turns the events into sound
+
+#[cfg(feature = "tui")]
+use rodio::{
+    buffer::SamplesBuffer,
+    source::SineWave,
+    {OutputStream, OutputStreamBuilder, Sink, Source},
+};
+#[cfg(feature = "tui")]
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioEvent {
+    BeepStart,
+    BeepStop,
+    Pattern(Option<AudioPattern>), // XO-CHIP's F002 or FX3A changed what the beep sounds like
+}
+
+// XO-CHIP's audio buffer: 128 1-bit samples, played in a loop at a rate set by the pitch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioPattern {
+    pub samples: [u8; 16],
+    pub pitch: u8,
+}
+
+impl AudioPattern {
+    // A pitch of 64 plays 4000 samples a second, and each 48 above or below doubles or halves it
+    pub fn sample_rate(&self) -> f32 {
+        4000.0 * 2f32.powf((self.pitch as f32 - 64.0) / 48.0)
+    }
+
+    // The bits, first byte's highest bit first
+    pub fn bits(&self) -> impl Iterator<Item = bool> + '_ {
+        self.samples
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |bit| byte & (1 << bit) != 0))
+    }
+}
+
+// How the beep is played: out loud, as an indicator on screen, both, or not at all
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum BellMode {
+    Visual,
+    Audio,
+    Both,
+    None,
+}
+
+impl std::fmt::Display for BellMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use BellMode::*;
+        write!(
+            f,
+            "{}",
+            match self {
+                Visual => "visual",
+                Audio => "audio",
+                Both => "both",
+                None => "none",
+            }
+        )
+    }
+}
+
+impl BellMode {
+    pub fn is_audible(&self) -> bool {
+        matches!(self, BellMode::Audio | BellMode::Both)
+    }
+
+    pub fn is_visible(&self) -> bool {
+        matches!(self, BellMode::Visual | BellMode::Both)
+    }
+}
+
+pub trait AudioBackend {
+    fn handle(&mut self, event: AudioEvent);
+}
+
+// Plays the beep on the default output device
+#[cfg(feature = "tui")]
+pub struct RodioBackend {
+    _stream: OutputStream, // Sound stops once the stream is dropped
+    sink: Sink,
+    pattern: Option<AudioPattern>, // Played in place of the sine wave once a ROM sets one
+}
+
+#[cfg(feature = "tui")]
+impl RodioBackend {
+    const BEEP_HZ: f32 = 440.0;
+    const BEEP_AMPLITUDE: f32 = 0.1;
+
+    // Returns None when there is no audio device
+    pub fn open() -> Option<Self> {
+        let stream = OutputStreamBuilder::open_default_stream().ok()?;
+        let sink = Sink::connect_new(stream.mixer());
+        Some(Self {
+            _stream: stream,
+            sink,
+            pattern: None,
+        })
+    }
+
+    fn start_beep(&mut self) {
+        match self.pattern {
+            Some(ref pattern) => {
+                let samples = pattern
+                    .bits()
+                    .map(|on| if on { 1.0 } else { -1.0 })
+                    .collect::<Vec<f32>>();
+                let rate = pattern.sample_rate().round() as u32;
+                self.sink.append(
+                    SamplesBuffer::new(1, rate.max(1), samples)
+                        .repeat_infinite()
+                        .amplify(Self::BEEP_AMPLITUDE),
+                );
+            }
+            None => self.sink.append(
+                SineWave::new(Self::BEEP_HZ)
+                    .take_duration(Duration::from_millis(100))
+                    .repeat_infinite()
+                    .amplify(Self::BEEP_AMPLITUDE),
+            ),
+        }
+        self.sink.play();
+    }
+}
+
+#[cfg(feature = "tui")]
+impl AudioBackend for RodioBackend {
+    fn handle(&mut self, event: AudioEvent) {
+        match event {
+            AudioEvent::BeepStart => self.start_beep(),
+            AudioEvent::BeepStop => self.sink.stop(),
+            // A beep already sounding changes to the new pattern straight away
+            AudioEvent::Pattern(pattern) => {
+                self.pattern = pattern;
+                if !self.sink.empty() {
+                    self.sink.stop();
+                    self.start_beep();
+                }
+            }
+        }
+    }
+}
+
+// Discards all sound, so the emulator still runs without an audio device
+pub struct NullBackend;
+
+impl AudioBackend for NullBackend {
+    fn handle(&mut self, _event: AudioEvent) {}
+}
@@ -0,0 +1,93 @@
+// What this build of the emulator supports, for launchers and test harnesses to introspect
+
+use clap::ValueEnum;
+
+use crate::chip8::Chip8Version;
+use crate::hardware::HardwareExecutionConfig;
+use crate::quirks::Quirks;
+
+// Cargo features this build was compiled with
+pub fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "tui") {
+        features.push("tui");
+    }
+    features
+}
+
+pub fn frontends() -> Vec<&'static str> {
+    let mut frontends = vec!["headless"];
+    if cfg!(feature = "tui") {
+        frontends.insert(0, "terminal");
+    }
+    frontends
+}
+
+pub fn audio_backends() -> Vec<&'static str> {
+    let mut backends = vec!["null"];
+    if cfg!(feature = "tui") {
+        backends.insert(0, "rodio");
+    }
+    backends
+}
+
+// The build and its capabilities as a JSON object
+pub fn version_info_json() -> String {
+    let defaults = HardwareExecutionConfig::default();
+    let variants: Vec<String> = Chip8Version::value_variants()
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let mut quirks = vec![
+        ("version", json_string(&defaults.version.to_string())),
+        ("cpu_hz", defaults.cpu_hz.to_string()),
+        ("vip_routines", defaults.vip_routines.to_string()),
+        ("on_halt", json_string(&defaults.on_halt.to_string())),
+    ];
+    let version_quirks = Quirks::for_version(&defaults.version);
+    quirks.extend(
+        version_quirks
+            .iter()
+            .map(|(name, on)| (name, on.to_string())),
+    );
+    let fields = [
+        ("name", json_string(env!("CARGO_PKG_NAME"))),
+        ("version", json_string(env!("CARGO_PKG_VERSION"))),
+        ("features", json_list(&enabled_features())),
+        ("frontends", json_list(&frontends())),
+        ("audio_backends", json_list(&audio_backends())),
+        ("variants", json_list(&variants)),
+        ("quirk_defaults", json_object(&quirks, "  ")),
+    ];
+    json_object(&fields, "")
+}
+
+fn json_object(fields: &[(&str, String)], indent: &str) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{indent}  {}: {value}", json_string(name)))
+        .collect();
+    format!("{{\n{}\n{indent}}}", fields.join(",\n"))
+}
+
+fn json_list(items: &[impl AsRef<str>]) -> String {
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| json_string(item.as_ref()))
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn json_string(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
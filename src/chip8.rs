@@ -1,17 +1,31 @@
-use crate::cpu::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::audio::BellMode;
+use crate::cpu::CPU;
 use crate::decoder::*;
+use crate::error::Chip8Error;
+use crate::frontend::ScreenColor;
 use crate::hardware::Hardware;
-use crate::hardware::HardwareExecutionConfig;
-use crate::input::KeyEventHandler;
+use crate::hardware::{HaltAction, HardwareExecutionConfig, InvalidAction, MemoryProtection};
+use crate::limits::ExecutionLimits;
 use crate::primitive::*;
-use crate::scheduler::*;
-use crate::screen::ScreenColor;
+use crate::quirks::Quirks;
+#[cfg(feature = "tui")]
+use crate::{
+    input::{Chip8Command, KeyEventHandler},
+    keybindings::BindingTarget,
+    scheduler::Chip8Orchaestrator,
+    screen::TerminalRenderer,
+};
 
 #[derive(Clone, Debug, PartialEq, clap::ValueEnum)]
 pub enum Chip8Version {
     Cosmac,
     Chip48,
     Superchip,
+    #[value(name = "xochip")]
+    XoChip, // Octo's extension of SUPER-CHIP, with two drawing planes and 64KB of memory
 }
 
 impl std::fmt::Display for Chip8Version {
@@ -24,24 +38,163 @@ impl std::fmt::Display for Chip8Version {
                 Cosmac => "cosmac",
                 Chip48 => "chip48",
                 Superchip => "superchip",
+                XoChip => "xochip",
             }
         )
     }
 }
 
-#[derive(Clone)]
+impl Chip8Version {
+    // Whether N = 0 draws a 16x16 sprite
+    pub fn has_big_sprites(&self) -> bool {
+        matches!(self, Chip8Version::Superchip | Chip8Version::XoChip)
+    }
+
+    // How much memory programs can address
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Chip8Version::XoChip => CPU::XO_CHIP_MEMORY_SIZE,
+            _ => CPU::MEMORY_SIZE,
+        }
+    }
+}
+
+// What the clock does with ticks it missed because the hardware fell behind
+// (e.g. a slow terminal flush)
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum MissedTickPolicy {
+    Skip,  // Drop the missed ticks, and carry on at the normal rate
+    Burst, // Run every missed tick as fast as possible to catch up
+    Cap,   // Catch up, but drop the backlog once it's too far behind
+}
+
+impl std::fmt::Display for MissedTickPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use MissedTickPolicy::*;
+        write!(
+            f,
+            "{}",
+            match self {
+                Skip => "skip",
+                Burst => "burst",
+                Cap => "cap",
+            }
+        )
+    }
+}
+
+// Presets trading faithfulness to the original hardware for smoother play
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Accuracy {
+    Fast,          // Drops late CPU ticks, and draws never wait
+    Balanced,      // Catches up on late CPU ticks a little, and draws never wait
+    CycleAccurate, // Runs every CPU tick, and draws wait for the display like the COSMAC VIP
+}
+
+impl std::fmt::Display for Accuracy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Accuracy::*;
+        write!(
+            f,
+            "{}",
+            match self {
+                Fast => "fast",
+                Balanced => "balanced",
+                CycleAccurate => "cycle-accurate",
+            }
+        )
+    }
+}
+
+impl Accuracy {
+    pub fn missed_ticks(&self) -> MissedTickPolicy {
+        match self {
+            Accuracy::Fast => MissedTickPolicy::Skip,
+            Accuracy::Balanced => MissedTickPolicy::Cap,
+            Accuracy::CycleAccurate => MissedTickPolicy::Burst,
+        }
+    }
+
+    pub fn display_wait(&self) -> bool {
+        *self == Accuracy::CycleAccurate
+    }
+}
+
+// How `dump_inst` prints words that don't decode to an instruction
+#[derive(Clone, Debug, PartialEq, clap::ValueEnum)]
+pub enum InvalidDataPolicy {
+    Data,    // As a data word, `.dw 0xNNNN`
+    Invalid, // As `INVALID`
+}
+
+impl std::fmt::Display for InvalidDataPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use InvalidDataPolicy::*;
+        write!(
+            f,
+            "{}",
+            match self {
+                Data => "data",
+                Invalid => "invalid",
+            }
+        )
+    }
+}
+
+// Which part of a ROM `dump_inst` disassembles, and how
+#[derive(Clone, Debug)]
+pub struct DumpOptions {
+    pub offset: usize, // Byte offset into the ROM. Odd offsets read misaligned words
+    pub length: Option<usize>, // Number of bytes to dump, or the rest of the ROM
+    pub invalid: InvalidDataPolicy,
+}
+
+#[derive(Clone, Debug)]
 pub struct Chip8Config {
     pub version: Chip8Version,
+    pub cpu_hz: f64, // Instructions run per second
     pub debug: bool,
     pub color: ScreenColor,
+    pub quirks: Quirks,
+    pub missed_ticks: MissedTickPolicy,
+    pub vip_routines: bool,
+    pub on_halt: HaltAction,
+    pub on_invalid: InvalidAction,
+    pub min_beep: Duration,     // Shorter beeps are stretched to this length
+    pub bell: Option<BellMode>, // None plays the beep, or shows it without an audio device
+    pub limits: ExecutionLimits,
+    pub pc_guard: bool,
+    pub memory_protection: Option<MemoryProtection>,
+    pub font_addr: u16,
+    pub settings_path: Option<PathBuf>, // Settings file to reload when it's edited
+}
+
+impl Chip8Config {
+    // The part of the config the hardware runs with
+    pub fn hardware_config(&self) -> HardwareExecutionConfig {
+        HardwareExecutionConfig {
+            version: self.version.clone(),
+            cpu_hz: self.cpu_hz,
+            screen_color: self.color,
+            quirks: Some(self.quirks),
+            vip_routines: self.vip_routines,
+            on_halt: self.on_halt,
+            on_invalid: self.on_invalid,
+            limits: self.limits,
+            pc_guard: self.pc_guard,
+            memory_protection: self.memory_protection,
+            font_addr: self.font_addr,
+        }
+    }
 }
 
 pub struct Chip8<'a> {
     // Config
     pub config: Chip8Config,
-    // CPU & Screen
+    // CPU & display
     pub hardware: Hardware<'a>,
-    // Input,
+    // Input, from the terminal
+    #[cfg(feature = "tui")]
     pub input: KeyEventHandler,
 }
 
@@ -74,46 +227,93 @@ impl<'a> Chip8<'a> {
     ];
     pub const BYTES_PER_FONT: u16 = 5;
 
+    // Where the fonts can be loaded instead, as some interpreters used 0x000. Both have to fit
+    // below the program
+    pub fn check_font_addr(addr: u16) -> Result<u16, String> {
+        let end = addr as usize + Self::FONT.len() + Self::BIG_FONT.len();
+        if end > Self::ENTRY_POINT as usize {
+            return Err(format!(
+                "the fonts don't fit between {addr:#05X} and the program at {:#05X}",
+                Self::ENTRY_POINT
+            ));
+        }
+        Ok(addr)
+    }
+
+    // SUPER-CHIP's 8x10 font, loaded right after the small one. The original only drew 0-9,
+    // so A-F follow the later extended fonts
+    pub const BIG_FONT: [u8; 160] = [
+        0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+        0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+        0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+        0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+        0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+        0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+        0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+        0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+        0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+        0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+        0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+        0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+        0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+    ];
+    pub const BYTES_PER_BIG_FONT: u16 = 10;
+
+    #[cfg(feature = "tui")]
     pub fn new(config: Chip8Config, input_handler: KeyEventHandler) -> Self {
         Self {
             config: config.clone(),
-            hardware: Hardware::new(HardwareExecutionConfig {
-                version: config.version,
-                screen_color: config.color,
-            }),
+            hardware: Hardware::new(config.hardware_config()),
             input: input_handler,
         }
     }
 
     // Loads a program `bytes` into ROM starting at the entry point, and gets CPU ready for
     // execution
-    pub fn load_rom(&mut self, bytes: &'a [u8]) -> Result<(), ()> {
-        self.hardware.load_rom(bytes)?;
-        Ok(())
+    pub fn load_rom(&mut self, bytes: &'a [u8]) -> Result<(), Chip8Error> {
+        self.hardware.load_rom(bytes)
     }
 
-    // Dumps the instructions contained in the bytes to stdio in a readible format
-    pub fn dump_inst(bytes: &[u8]) {
+    // Dumps the instructions contained in the bytes to stdio in a readible format. Errors if the
+    // offset starts past the end of addressable memory
+    pub fn dump_inst(bytes: &[u8], options: &DumpOptions) -> Result<(), String> {
+        let start = options.offset.min(bytes.len());
+        let end = options.length.map_or(bytes.len(), |length| {
+            start.saturating_add(length).min(bytes.len())
+        });
+        // Disassembled addresses are 12 bits, as instructions address memory
+        let base_addr = u16::try_from(Self::ENTRY_POINT as usize + start)
+            .ok()
+            .filter(|&addr| Address::new(addr).is_ok())
+            .ok_or_else(|| {
+                format!("offset {start:#X} starts past the end of addressable memory, at 0xFFF")
+            })?;
         println!("Dumping instruction hex codes:");
-        bytes
-            .chunks_exact(CPU::INSTRUCTION_SIZE_B.into())
-            .map(|chunk| RawInstruction::new(chunk[0], chunk[1]))
-            .enumerate()
-            .for_each(|(index, raw)| {
-                let inst = Decoder::decode(&raw);
-                let addr = Address::new(Self::ENTRY_POINT + index as u16 * 2).unwrap();
-                println!(
-                    "{}: Code {}, {}",
-                    addr,
-                    raw,
-                    inst.unwrap_or(Instruction::Invalid)
-                );
-            });
+        for (addr, raw, inst) in disasm(&bytes[start..end], base_addr) {
+            match (inst, &options.invalid) {
+                (Some(inst), _) => println!("{}: Code {}, {}", addr, raw, inst),
+                (None, InvalidDataPolicy::Data) => println!("{}: Code {}, .dw {}", addr, raw, raw),
+                (None, InvalidDataPolicy::Invalid) => {
+                    println!("{}: Code {}, {}", addr, raw, Instruction::Invalid)
+                }
+            }
+        }
+        Ok(())
     }
 
-    pub async fn run(&mut self) {
+    // Runs the ROM in the terminal, drawn by `screen`, until it exits or the user quits
+    #[cfg(feature = "tui")]
+    pub async fn run(&mut self, screen: &mut TerminalRenderer) {
+        let restart_key = self
+            .input
+            .label(BindingTarget::Command(Chip8Command::Restart));
+        screen.set_restart_key(restart_key);
+        screen.enter_terminal();
         crossterm::terminal::enable_raw_mode().unwrap();
-        Chip8Orchaestrator::run(self).await;
+        Chip8Orchaestrator::run(self, screen).await;
         crossterm::terminal::disable_raw_mode().unwrap();
     }
 }
@@ -1,39 +1,74 @@
+use crate::error::Chip8Error;
+use crate::icache::{BasicBlock, InstructionCache};
 use crate::primitive::*;
+use crate::savestate::{StateReader, StateWriter};
+
+// State of a GetKey instruction that is waiting for input
+#[derive(Clone, Copy, Debug)]
+pub struct KeyWait {
+    pub reg: Register,
+    pub captured_key: Option<u8>, // Key that was pressed, and now has to be released (COSMAC)
+}
 
 #[allow(clippy::upper_case_acronyms)]
+#[derive(Clone)]
 pub struct CPU {
-    memory: [u8; CPU::MEMORY_SIZE],    // This CPU also has memory lol
-    pc_r: u16,                         // Program Counter
-    index_r: u16,                      // Index Register
-    gen_r: [u8; CPU::REGISTER_COUNT],  // General Purpose Registers
-    stack: Vec<u16>,                   // Stack
-    delay_timer: u8,                   // Delay Timer
-    sound_timer: u8,                   // Sound Timer
-    waiting_for_key: Option<Register>, // Track if CPU is waiting for key input
+    memory: Vec<u8>,                  // This CPU also has memory lol
+    pc_r: u16,                        // Program Counter
+    index_r: u16,                     // Index Register
+    gen_r: [u8; CPU::REGISTER_COUNT], // General Purpose Registers
+    stack: Vec<u16>,                  // Stack
+    delay_timer: u8,                  // Delay Timer
+    sound_timer: u8,                  // Sound Timer
+    waiting_for_key: Option<KeyWait>, // Track if CPU is waiting for key input
+    decoded: InstructionCache,        // Instructions already decoded, cleared by writes
+    pc_wrapped: bool,                 // The PC ran off the end of memory, back to 0
+    memory_written: bool,             // Memory changed, for the debug overlay to refresh
+}
+
+impl Default for CPU {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CPU {
     pub const MEMORY_SIZE: usize = 4096; // 4KB memory
+    pub const XO_CHIP_MEMORY_SIZE: usize = 65536;
     pub const REGISTER_COUNT: usize = 16; // 16 General Purpose Registers
     pub const INSTRUCTION_SIZE_B: u16 = 2; // Each instruction is 2 bytes
 
-    const DEFAULT_CPU: Self = Self {
-        memory: [0; Self::MEMORY_SIZE],
-        index_r: 0,
-        gen_r: [0; Self::REGISTER_COUNT],
-        stack: Vec::new(),
-        delay_timer: 0,
-        sound_timer: 0,
-        pc_r: 0,
-        waiting_for_key: None,
-    };
-
     pub fn new() -> Self {
-        Self::DEFAULT_CPU
+        Self::with_memory_size(Self::MEMORY_SIZE)
+    }
+
+    pub fn with_memory_size(memory_size: usize) -> Self {
+        Self {
+            memory: vec![0; memory_size],
+            index_r: 0,
+            gen_r: [0; Self::REGISTER_COUNT],
+            stack: Vec::new(),
+            delay_timer: 0,
+            sound_timer: 0,
+            pc_r: 0,
+            waiting_for_key: None,
+            decoded: InstructionCache::new(memory_size),
+            pc_wrapped: false,
+            memory_written: true,
+        }
     }
 
+    // Clears everything, keeping the amount of memory
     pub fn reset(&mut self) {
-        *self = Self::DEFAULT_CPU;
+        *self = Self::with_memory_size(self.memory_size());
+    }
+
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    pub fn memory_size(&self) -> usize {
+        self.memory.len()
     }
 
     // Return a reference to the value of the VF register
@@ -57,28 +92,63 @@ impl CPU {
     }
 
     // Load value from address in memory
-    pub fn load_from_addr(&self, addr: u16) -> u8 {
-        self.memory[addr as usize]
+    pub fn load_from_addr(&self, addr: u16) -> Result<u8, Chip8Error> {
+        self.check_addr(addr as usize)?;
+        Ok(self.memory[addr as usize])
     }
 
     // Store value in memory at address
-    pub fn store_in_addr(&mut self, addr: u16, value: u8) {
+    pub fn store_in_addr(&mut self, addr: u16, value: u8) -> Result<(), Chip8Error> {
+        self.check_addr(addr as usize)?;
         self.memory[addr as usize] = value;
+        self.decoded.invalidate(addr as usize);
+        self.memory_written = true;
+        Ok(())
+    }
+
+    // `addr`, if it's in memory. Past the end, it wraps around to 0 if `wrap` is set, and is
+    // an error otherwise
+    pub fn resolve_addr(&self, addr: usize, wrap: bool) -> Result<u16, Chip8Error> {
+        if wrap {
+            Ok(addr as u16 & self.pc_mask())
+        } else {
+            self.check_addr(addr).map(|_| addr as u16)
+        }
     }
 
-    pub fn store_memory_slice(&mut self, start: usize, bytes: &[u8]) -> Result<(), ()> {
+    fn check_addr(&self, addr: usize) -> Result<(), Chip8Error> {
+        if addr < self.memory.len() {
+            Ok(())
+        } else {
+            Err(Chip8Error::MemoryOutOfBounds {
+                pc: self.pc_r,
+                addr,
+            })
+        }
+    }
+
+    pub fn store_memory_slice(&mut self, start: usize, bytes: &[u8]) -> Result<(), Chip8Error> {
         let end = start + bytes.len();
         if end > self.memory.len() {
-            Err(())
+            Err(Chip8Error::MemoryOutOfBounds {
+                pc: self.pc_r,
+                addr: self.memory.len(),
+            })
         } else {
             self.memory[start..end].copy_from_slice(bytes);
+            for addr in start..end {
+                self.decoded.invalidate(addr);
+            }
+            self.memory_written = true;
             Ok(())
         }
     }
 
-    // Increment the Program Counter
+    // Increment the Program Counter. Past the end of memory, it wraps around to 0
     pub fn increment_pc(&mut self) {
-        self.pc_r += Self::INSTRUCTION_SIZE_B;
+        let pc = self.pc_r.wrapping_add(Self::INSTRUCTION_SIZE_B) & self.pc_mask();
+        self.pc_wrapped |= pc < self.pc_r;
+        self.pc_r = pc;
     }
 
     pub fn get_pc(&self) -> u16 {
@@ -86,14 +156,54 @@ impl CPU {
     }
 
     pub fn jump_to(&mut self, addr: &Address) {
-        self.pc_r = addr.get();
+        self.set_pc(addr.get());
+    }
+
+    // Jumps anywhere, wrapping addresses past the end of memory
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc_r = pc & self.pc_mask();
+    }
+
+    // Whether the PC wrapped around since this was last called
+    pub fn take_pc_wrapped(&mut self) -> bool {
+        std::mem::take(&mut self.pc_wrapped)
+    }
+
+    // Whether memory was written since this was last called
+    pub fn take_memory_written(&mut self) -> bool {
+        std::mem::take(&mut self.memory_written)
+    }
+
+    // Memory sizes are powers of two, so addresses wrap by masking
+    fn pc_mask(&self) -> u16 {
+        (self.memory.len() - 1) as u16
     }
 
     pub fn fetch_current_instruction(&self) -> RawInstruction {
-        RawInstruction::new(
-            self.memory[self.pc_r as usize],
-            self.memory[self.pc_r as usize + 1],
-        )
+        let next = self.pc_r.wrapping_add(1) & self.pc_mask();
+        RawInstruction::new(self.memory[self.pc_r as usize], self.memory[next as usize])
+    }
+
+    // The instruction at `addr`, if it was decoded since its bytes were last written
+    pub fn decoded_instruction(&self, addr: u16) -> Option<&Instruction> {
+        self.decoded.get(addr)
+    }
+
+    pub fn cache_decoded_instruction(&mut self, addr: u16, inst: Instruction) {
+        self.decoded.insert(addr, inst);
+    }
+
+    pub fn basic_block(&self, addr: u16) -> Option<BasicBlock> {
+        self.decoded.block(addr)
+    }
+
+    pub fn cache_basic_block(&mut self, addr: u16, block: BasicBlock) {
+        self.decoded.insert_block(addr, block);
+    }
+
+    // Whether the program wrote over instructions it ran as part of a basic block
+    pub fn is_self_modifying(&self) -> bool {
+        self.decoded.is_self_modifying()
     }
 
     pub fn get_index(&self) -> u16 {
@@ -142,6 +252,11 @@ impl CPU {
         self.stack.pop()
     }
 
+    // Addresses of the calls to the subroutines currently running, outermost first
+    pub fn call_stack(&self) -> &[u16] {
+        &self.stack
+    }
+
     // Register arithmetic operations
     pub fn add_reg(&mut self, reg: &Register, value: u8) {
         let current = self.register_val(reg);
@@ -149,47 +264,73 @@ impl CPU {
         self.register_set(reg, result);
     }
 
-    pub fn add_index(&mut self, value: u16) {
-        self.index_r += value;
+    // Returns whether I went past the 4KB the original interpreters could address
+    pub fn add_index(&mut self, value: u16) -> bool {
+        self.index_r = self.index_r.wrapping_add(value);
+        self.index_r > 0x0FFF
+    }
+
+    // The address `offset` bytes past I
+    fn index_addr(&self, offset: u16, wrap: bool) -> Result<u16, Chip8Error> {
+        self.resolve_addr(self.index_r as usize + offset as usize, wrap)
     }
 
     // Binary decimal conversion
-    pub fn binary_decimal_conv(&mut self, reg: &Register) {
+    pub fn binary_decimal_conv(&mut self, reg: &Register, wrap: bool) -> Result<(), Chip8Error> {
         let value = self.register_val(reg);
-        let first_digit = value / 100;
-        let second_digit = (value % 100) / 10;
-        let last_digit = value % 10;
-        self.store_in_addr(self.index_r, first_digit);
-        self.store_in_addr(self.index_r + 1, second_digit);
-        self.store_in_addr(self.index_r + 2, last_digit);
+        let digits = [value / 100, (value % 100) / 10, value % 10];
+        // Checked before writing any, so a fault leaves memory as it was
+        let addrs = [
+            self.index_addr(0, wrap)?,
+            self.index_addr(1, wrap)?,
+            self.index_addr(2, wrap)?,
+        ];
+        for (addr, digit) in addrs.into_iter().zip(digits) {
+            self.store_in_addr(addr, digit)?;
+        }
+        Ok(())
     }
 
     // Memory operations for register range
-    pub fn load_registers(&mut self, up_to_reg: &Register) {
+    pub fn load_registers(&mut self, up_to_reg: &Register, wrap: bool) -> Result<(), Chip8Error> {
+        self.index_addr(up_to_reg.get() as u16, wrap)?;
         for i in 0x0..=(up_to_reg.get()) {
-            let value = self.load_from_addr(self.index_r + i as u16);
+            let value = self.load_from_addr(self.index_addr(i as u16, wrap)?)?;
             let reg = Register::new(i).unwrap();
             self.register_set(&reg, value);
         }
+        Ok(())
     }
 
-    pub fn store_registers(&mut self, up_to_reg: &Register) {
+    pub fn store_registers(&mut self, up_to_reg: &Register, wrap: bool) -> Result<(), Chip8Error> {
+        self.index_addr(up_to_reg.get() as u16, wrap)?;
         for i in 0x0..=(up_to_reg.get()) {
             let reg = Register::new(i).unwrap();
             let value = self.register_val(&reg);
-            self.store_in_addr(self.index_r + i as u16, value);
+            self.store_in_addr(self.index_addr(i as u16, wrap)?, value)?;
         }
+        Ok(())
     }
 
     // Version-dependent operations that need to modify index
-    pub fn load_registers_cosmac(&mut self, up_to_reg: &Register) {
-        self.load_registers(up_to_reg);
-        self.index_r += up_to_reg.get() as u16 + 1;
-    }
-
-    pub fn store_registers_cosmac(&mut self, up_to_reg: &Register) {
-        self.store_registers(up_to_reg);
-        self.index_r += up_to_reg.get() as u16 + 1;
+    pub fn load_registers_cosmac(
+        &mut self,
+        up_to_reg: &Register,
+        wrap: bool,
+    ) -> Result<(), Chip8Error> {
+        self.load_registers(up_to_reg, wrap)?;
+        self.index_r = self.index_r.wrapping_add(up_to_reg.get() as u16 + 1);
+        Ok(())
+    }
+
+    pub fn store_registers_cosmac(
+        &mut self,
+        up_to_reg: &Register,
+        wrap: bool,
+    ) -> Result<(), Chip8Error> {
+        self.store_registers(up_to_reg, wrap)?;
+        self.index_r = self.index_r.wrapping_add(up_to_reg.get() as u16 + 1);
+        Ok(())
     }
 
     // Key waiting state management
@@ -197,11 +338,80 @@ impl CPU {
         self.waiting_for_key.is_some()
     }
 
+    pub fn key_wait(&self) -> Option<KeyWait> {
+        self.waiting_for_key
+    }
+
     pub fn start_waiting_for_key(&mut self, reg: Register) {
-        self.waiting_for_key = Some(reg);
+        self.waiting_for_key = Some(KeyWait {
+            reg,
+            captured_key: None,
+        });
+    }
+
+    // Remember which key was pressed while waiting, so only its release ends the wait
+    pub fn capture_key(&mut self, key: u8) {
+        if let Some(ref mut wait) = self.waiting_for_key {
+            wait.captured_key = Some(key);
+        }
     }
 
     pub fn stop_waiting_for_key(&mut self) -> Option<Register> {
-        self.waiting_for_key.take()
+        self.waiting_for_key.take().map(|wait| wait.reg)
+    }
+
+    pub(crate) fn save_state(&self, out: &mut StateWriter) {
+        out.u32(self.memory.len() as u32);
+        out.bytes(&self.memory);
+        out.u16(self.pc_r);
+        out.u16(self.index_r);
+        out.bytes(&self.gen_r);
+        out.u32(self.stack.len() as u32);
+        for &addr in &self.stack {
+            out.u16(addr);
+        }
+        out.u8(self.delay_timer);
+        out.u8(self.sound_timer);
+        out.optional_u8(self.waiting_for_key.map(|wait| wait.reg.get()));
+        out.optional_u8(self.waiting_for_key.and_then(|wait| wait.captured_key));
+    }
+
+    pub(crate) fn load_state(state: &mut StateReader) -> Result<Self, String> {
+        let memory_size = state.u32()? as usize;
+        if memory_size > Self::XO_CHIP_MEMORY_SIZE {
+            return Err(format!("{memory_size} bytes of memory is too much"));
+        }
+        // Addresses wrap by masking
+        if !memory_size.is_power_of_two() {
+            return Err(format!(
+                "{memory_size} bytes of memory is not a power of two"
+            ));
+        }
+        let mut cpu = Self::with_memory_size(memory_size);
+        cpu.memory.copy_from_slice(state.bytes(memory_size)?);
+        cpu.pc_r = state.u16()?;
+        if cpu.pc_r as usize >= memory_size {
+            return Err(format!(
+                "the PC {:#05X} is past the end of memory",
+                cpu.pc_r
+            ));
+        }
+        cpu.index_r = state.u16()?;
+        cpu.gen_r
+            .copy_from_slice(state.bytes(Self::REGISTER_COUNT)?);
+        for _ in 0..state.u32()? {
+            cpu.stack.push(state.u16()?);
+        }
+        cpu.delay_timer = state.u8()?;
+        cpu.sound_timer = state.u8()?;
+        let waiting_reg = state.optional_u8()?;
+        let captured_key = state.optional_u8()?;
+        if let Some(reg) = waiting_reg {
+            cpu.waiting_for_key = Some(KeyWait {
+                reg: Register::new(reg)?,
+                captured_key,
+            });
+        }
+        Ok(cpu)
     }
 }
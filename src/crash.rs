@@ -0,0 +1,153 @@
+// Crash reports, written to the logs directory when the emulator panics, is interrupted, or
+// stops a ROM. The panic hook can't reach the running machine, so the emulator keeps a recent
+// snapshot of it here instead
+
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::exitcode::ExitCode;
+use crate::{paths, util};
+
+struct CrashContext {
+    rom_hash: Option<u64>,
+    config: Option<String>,
+}
+
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    rom_hash: None,
+    config: None,
+});
+
+// The latest snapshot from any thread, for the interrupt handler, which runs on its own
+static LATEST_SNAPSHOT: Mutex<Option<String>> = Mutex::new(None);
+
+thread_local! {
+    // Kept per thread, since the panic hook runs on the thread that panicked, and headless
+    // cores may be running on several at once
+    static SNAPSHOT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+// Replaces the default panic output with a crash report. The panic then unwinds as usual, so
+// whatever it passes through is dropped, and the caller decides how to exit
+pub fn install_hook() {
+    panic::set_hook(Box::new(panic_handler));
+}
+
+// What is being run, included in every report
+pub fn set_run_info(rom_hash: u64, config: &impl std::fmt::Debug) {
+    let mut context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    context.rom_hash = Some(rom_hash);
+    context.config = Some(format!("{config:#?}"));
+}
+
+// The most recent state of the machine, included in the report if it crashes
+pub fn record_snapshot(snapshot: String) {
+    *LATEST_SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()) = Some(snapshot.clone());
+    SNAPSHOT.with(|saved| *saved.borrow_mut() = Some(snapshot));
+}
+
+// Writes a report and exits on SIGINT, e.g. Ctrl+C in the terminal that started a headless
+// run. The TUI's raw mode reads Ctrl+C as a key, so this only sees signals sent to it
+pub fn install_interrupt_handler() {
+    tokio::spawn(async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        restore_terminal();
+        eprintln!("Interrupted");
+        match write_report("interrupt", &build_report("interrupted: SIGINT", None)) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Could not write crash report ({e})"),
+        }
+        ExitCode::Interrupted.exit();
+    });
+}
+
+// Leaves the emulator's screen, so what's printed after stays visible. Only the TUI changes
+// the terminal, and anything else's output may be going to a file
+fn restore_terminal() {
+    if !crossterm::terminal::is_raw_mode_enabled().unwrap_or(false) {
+        return;
+    }
+    let _ = crossterm::execute!(
+        io::stdout(),
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::event::DisableBracketedPaste,
+        crossterm::cursor::Show
+    );
+    let _ = crossterm::terminal::disable_raw_mode();
+}
+
+fn panic_handler(panic_info: &PanicHookInfo) {
+    restore_terminal();
+
+    let report = build_report(
+        &format!("panic: {panic_info}"),
+        Some(Backtrace::force_capture()),
+    );
+    eprintln!("PANIC:\n  {panic_info}\n");
+    match write_report("crash", &report) {
+        Ok(path) => eprintln!("Crash report written to {}", path.display()),
+        Err(e) => eprintln!("Could not write crash report ({e}):\n{report}"),
+    }
+}
+
+// Writes a report for a run the emulator stopped itself, e.g. at an execution limit
+pub fn write_fault_report(reason: &str) -> io::Result<PathBuf> {
+    write_report("fault", &build_report(&format!("fault: {reason}"), None))
+}
+
+fn build_report(reason: &str, backtrace: Option<Backtrace>) -> String {
+    let context = CONTEXT.lock().unwrap_or_else(|e| e.into_inner());
+    let unknown = || "unknown".to_string();
+    let mut report = String::new();
+    let _ = writeln!(report, "time: {}", util::utc_timestamp(SystemTime::now()));
+    let _ = writeln!(report, "version: {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        report,
+        "rom hash: {}",
+        context
+            .rom_hash
+            .map_or_else(unknown, |hash| format!("{hash:016x}"))
+    );
+    let _ = writeln!(report, "{reason}");
+    let sections = [
+        ("config", context.config.clone()),
+        (
+            "machine",
+            SNAPSHOT.with(|saved| saved.borrow().clone()).or_else(|| {
+                LATEST_SNAPSHOT
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone()
+            }),
+        ),
+    ];
+    for (name, section) in sections {
+        let _ = writeln!(report, "\n[{name}]\n{}", section.unwrap_or_else(unknown));
+    }
+    if let Some(backtrace) = backtrace {
+        let _ = writeln!(report, "\n[backtrace]\n{backtrace}");
+    }
+    report
+}
+
+// Writes to `<kind>-<unix time>.txt` in the logs directory
+fn write_report(kind: &str, report: &str) -> io::Result<PathBuf> {
+    let dir = paths::logs_dir();
+    fs::create_dir_all(&dir)?;
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = dir.join(format!("{kind}-{secs}.txt"));
+    fs::File::create(&path)?.write_all(report.as_bytes())?;
+    Ok(path)
+}
@@ -1,72 +1,148 @@
 // Decodes a raw instruction into an instruction enum
 
+use crate::chip8::Chip8Version;
+use crate::cpu::CPU;
+use crate::opcode_table;
 use crate::primitive::*;
 
 pub struct Decoder;
 
+// One row of the opcode table
+#[derive(Debug)]
+pub struct Opcode {
+    pub value: u16,
+    pub mask: u16,
+    pub pattern: &'static str,  // e.g. `DXYN`
+    pub mnemonic: &'static str, // Assembly syntax, e.g. `DRW Vx, Vy, n`
+    pub versions: &'static [Chip8Version],
+}
+
+opcode_table! {
+    // Display/Draw
+    0x00E0 / 0xFFFF, "00E0", "CLS", [Cosmac, Chip48, Superchip, XoChip] => ClearScreen;
+    0x00EE / 0xFFFF, "00EE", "RET", [Cosmac, Chip48, Superchip, XoChip] => Return;
+    0x00FD / 0xFFFF, "00FD", "EXIT", [Superchip, XoChip] => Exit;
+    0x00FE / 0xFFFF, "00FE", "LOW", [Superchip, XoChip] => LowRes;
+    0x00FF / 0xFFFF, "00FF", "HIGH", [Superchip, XoChip] => HighRes;
+    0x00C0 / 0xFFF0, "00CN", "SCD n", [Superchip, XoChip] => ScrollDown(n);
+    0x00FB / 0xFFFF, "00FB", "SCR", [Superchip, XoChip] => ScrollRight;
+    0x00FC / 0xFFFF, "00FC", "SCL", [Superchip, XoChip] => ScrollLeft;
+    0x00D0 / 0xFFF0, "00DN", "SCU n", [XoChip] => ScrollUp(n);
+    0x0000 / 0xF000, "0NNN", "SYS nnn", [Cosmac, Chip48, Superchip, XoChip] => ExecuteMachineLangRoutine(nnn);
+    0xD000 / 0xF000, "DXYN", "DRW Vx, Vy, n", [Cosmac, Chip48, Superchip, XoChip] => Draw(x, y, n);
+    0xF029 / 0xF0FF, "FX29", "LD F, Vx", [Cosmac, Chip48, Superchip, XoChip] => SetFont(x);
+    0xF030 / 0xF0FF, "FX30", "LD HF, Vx", [Superchip, XoChip] => SetBigFont(x);
+    0xF001 / 0xF0FF, "FN01", "PLANE n", [XoChip] => SelectPlanes(p);
+
+    // Subroutine
+    0x1000 / 0xF000, "1NNN", "JP nnn", [Cosmac, Chip48, Superchip, XoChip] => Jump(nnn);
+    0xB000 / 0xF000, "BNNN", "JP V0, nnn", [Cosmac, Chip48, Superchip, XoChip] => JumpWithOffset(nnn);
+    0x2000 / 0xF000, "2NNN", "CALL nnn", [Cosmac, Chip48, Superchip, XoChip] => CallSubroutine(nnn);
+
+    // Control Flow
+    0x3000 / 0xF000, "3XNN", "SE Vx, nn", [Cosmac, Chip48, Superchip, XoChip] => Skip({SkipIf::Eq}, x, nn);
+    0x4000 / 0xF000, "4XNN", "SNE Vx, nn", [Cosmac, Chip48, Superchip, XoChip] => Skip({SkipIf::NotEq}, x, nn);
+    0x5000 / 0xF00F, "5XY0", "SE Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => SkipReg({SkipIf::Eq}, x, y);
+    0x9000 / 0xF00F, "9XY0", "SNE Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => SkipReg({SkipIf::NotEq}, x, y);
+
+    // Keys
+    0xF00A / 0xF0FF, "FX0A", "LD Vx, K", [Cosmac, Chip48, Superchip, XoChip] => GetKey(x);
+    0xE09E / 0xF0FF, "EX9E", "SKP Vx", [Cosmac, Chip48, Superchip, XoChip] => SkipKeyPress({SkipIf::Eq}, x);
+    0xE0A1 / 0xF0FF, "EXA1", "SKNP Vx", [Cosmac, Chip48, Superchip, XoChip] => SkipKeyPress({SkipIf::NotEq}, x);
+
+    // Register Logic
+    0x6000 / 0xF000, "6XNN", "LD Vx, nn", [Cosmac, Chip48, Superchip, XoChip] => SetRegImmediate(x, nn);
+    0x7000 / 0xF000, "7XNN", "ADD Vx, nn", [Cosmac, Chip48, Superchip, XoChip] => AddRegImmediate(x, nn);
+    0x8000 / 0xF00F, "8XY0", "LD Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::Set}, x, y);
+    0x8001 / 0xF00F, "8XY1", "OR Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::Or}, x, y);
+    0x8002 / 0xF00F, "8XY2", "AND Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::And}, x, y);
+    0x8003 / 0xF00F, "8XY3", "XOR Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::Xor}, x, y);
+    0x8004 / 0xF00F, "8XY4", "ADD Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::Add}, x, y);
+    0x8005 / 0xF00F, "8XY5", "SUB Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::Sub}, x, y);
+    0x8007 / 0xF00F, "8XY7", "SUBN Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::SubInv}, x, y);
+    0x8006 / 0xF00F, "8XY6", "SHR Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::ShiftRight}, x, y);
+    0x800E / 0xF00F, "8XYE", "SHL Vx, Vy", [Cosmac, Chip48, Superchip, XoChip] => RegOp({RegOperation::ShiftLeft}, x, y);
+
+    // Store and Load
+    0xF055 / 0xF0FF, "FX55", "LD [I], Vx", [Cosmac, Chip48, Superchip, XoChip] => StoreAddr(x);
+    0xF065 / 0xF0FF, "FX65", "LD Vx, [I]", [Cosmac, Chip48, Superchip, XoChip] => LoadAddr(x);
+    0xF075 / 0xF0FF, "FX75", "LD R, Vx", [Superchip, XoChip] => StoreFlags(x);
+    0xF085 / 0xF0FF, "FX85", "LD Vx, R", [Superchip, XoChip] => LoadFlags(x);
+    0x5002 / 0xF00F, "5XY2", "SAVE Vx - Vy", [XoChip] => SaveRange(x, y);
+    0x5003 / 0xF00F, "5XY3", "LOAD Vx - Vy", [XoChip] => LoadRange(x, y);
+    0xF000 / 0xFFFF, "F000", "LD I, long nnnn", [XoChip] => LoadLongIndex;
+
+    // Timers
+    0xF007 / 0xF0FF, "FX07", "LD Vx, DT", [Cosmac, Chip48, Superchip, XoChip] => GetDelayTimer(x);
+    0xF015 / 0xF0FF, "FX15", "LD DT, Vx", [Cosmac, Chip48, Superchip, XoChip] => SetDelayTimer(x);
+    0xF018 / 0xF0FF, "FX18", "LD ST, Vx", [Cosmac, Chip48, Superchip, XoChip] => SetSoundTimer(x);
+
+    // Audio
+    0xF002 / 0xFFFF, "F002", "AUDIO", [XoChip] => LoadAudioPattern;
+    0xF03A / 0xF0FF, "FX3A", "PITCH Vx", [XoChip] => SetPitch(x);
+
+    // Index
+    0xA000 / 0xF000, "ANNN", "LD I, nnn", [Cosmac, Chip48, Superchip, XoChip] => SetIndex(nnn);
+    0xF01E / 0xF0FF, "FX1E", "ADD I, Vx", [Cosmac, Chip48, Superchip, XoChip] => AddIndex(x);
+
+    // Misc
+    0xC000 / 0xF000, "CXNN", "RND Vx, nn", [Cosmac, Chip48, Superchip, XoChip] => Random(x, nn);
+    0xF033 / 0xF0FF, "FX33", "LD B, Vx", [Cosmac, Chip48, Superchip, XoChip] => BinaryDecimalConv(x);
+}
+
 impl Decoder {
-    pub fn decode(raw: &RawInstruction) -> Option<Instruction> {
-        let (nibble1, nibble2, nibble3, nibble4) = raw.to_nibbles();
-        Some(match (nibble1, nibble2, nibble3, nibble4) {
-            // Display/Draw
-            (0, 0, 0xE, 0) => Instruction::ClearScreen,
-            (0x0, 0x0, 0xE, 0xE) => Instruction::Return,
-            (0, _, _, _) => Instruction::ExecuteMachineLangRoutine,
-            (0xD, _, _, _) => Instruction::Draw(raw.x(), raw.y(), raw.n()),
-            (0xF, _, 0x2, 0x9) => Instruction::SetFont(raw.x()),
-
-            // Subroutine
-            (0x1, _, _, _) => Instruction::Jump(raw.nnn()),
-            (0xB, _, _, _) => Instruction::JumpWithOffset(raw.nnn()),
-            (0x2, _, _, _) => Instruction::CallSubroutine(raw.nnn()),
-
-            // Control Flow
-            (0x3, _, _, _) => Instruction::Skip(SkipIf::Eq, raw.x(), raw.nn()),
-            (0x4, _, _, _) => Instruction::Skip(SkipIf::NotEq, raw.x(), raw.nn()),
-            (0x5, _, _, 0x0) => Instruction::SkipReg(SkipIf::Eq, raw.x(), raw.y()),
-            (0x9, _, _, 0x0) => Instruction::SkipReg(SkipIf::NotEq, raw.x(), raw.y()),
-
-            // Keys
-            (0xF, _, 0x0, 0xA) => Instruction::GetKey(raw.x()),
-            (0xE, _, 0x9, 0xE) => Instruction::SkipKeyPress(SkipIf::Eq, raw.x()),
-            (0xE, _, 0xA, 0x1) => Instruction::SkipKeyPress(SkipIf::NotEq, raw.x()),
-
-            // Register Logic
-            (0x6, _, _, _) => Instruction::SetRegImmediate(raw.x(), raw.nn()),
-            (0x7, _, _, _) => Instruction::AddRegImmediate(raw.x(), raw.nn()),
-            (0x8, _, _, op) => {
-                let reg_op: RegOperation = match op {
-                    0x0 => RegOperation::Set,
-                    0x1 => RegOperation::Or,
-                    0x2 => RegOperation::And,
-                    0x3 => RegOperation::Xor,
-                    0x4 => RegOperation::Add,
-                    0x5 => RegOperation::Sub,
-                    0x7 => RegOperation::SubInv,
-                    0x6 => RegOperation::ShiftRight,
-                    0xE => RegOperation::ShiftLeft,
-                    _ => return None,
-                };
-                Instruction::RegOp(reg_op, raw.x(), raw.y())
+    // Decodes for a specific CHIP-8 version. Instructions that the version doesn't have decode
+    // to `Unsupported`, rather than being run as if they did exist
+    pub fn decode_for(raw: &RawInstruction, version: &Chip8Version) -> Option<Instruction> {
+        let inst = Self::decode(raw)?;
+        match inst.opcode() {
+            Some(opcode) if !opcode.versions.contains(version) => {
+                Some(Instruction::Unsupported(version.clone()))
             }
+            _ => Some(inst),
+        }
+    }
+}
 
-            // Store and Load
-            (0xF, _, 0x5, 0x5) => Instruction::StoreAddr(raw.x()),
-            (0xF, _, 0x6, 0x5) => Instruction::LoadAddr(raw.x()),
+impl Instruction {
+    // The opcode table row this instruction comes from, if it has an encoding
+    pub fn opcode(&self) -> Option<&'static Opcode> {
+        let word = Decoder::encode(self)?.word();
+        OPCODES
+            .iter()
+            .find(|opcode| word & opcode.mask == opcode.value)
+    }
+}
 
-            // Timers
-            (0xF, _, 0x0, 0x7) => Instruction::GetDelayTimer(raw.x()),
-            (0xF, _, 0x1, 0x5) => Instruction::SetDelayTimer(raw.x()),
-            (0xF, _, 0x1, 0x8) => Instruction::SetSoundTimer(raw.x()),
+// Disassembles `bytes`, as if they were loaded into memory at `base_addr`. Words that don't
+// decode to an instruction are returned with `None`. Stops at the end of addressable memory
+pub fn disasm(bytes: &[u8], base_addr: u16) -> Vec<(Address, RawInstruction, Option<Instruction>)> {
+    disasm_with(bytes, base_addr, Decoder::decode)
+}
 
-            // Index
-            (0xA, _, _, _) => Instruction::SetIndex(raw.nnn()),
-            (0xF, _, 0x1, 0xE) => Instruction::AddIndex(raw.x()),
+// As `disasm`, with instructions the version can't run as `Unsupported`, as it executes them
+pub fn disasm_for(
+    bytes: &[u8],
+    base_addr: u16,
+    version: &Chip8Version,
+) -> Vec<(Address, RawInstruction, Option<Instruction>)> {
+    disasm_with(bytes, base_addr, |raw| Decoder::decode_for(raw, version))
+}
 
-            // Misc
-            (0xC, _, _, _) => Instruction::Random(raw.x(), raw.nn()),
-            (0xF, _, 0x3, 0x3) => Instruction::BinaryDecimalConv(raw.x()),
-            _ => return None,
+fn disasm_with(
+    bytes: &[u8],
+    base_addr: u16,
+    decode: impl Fn(&RawInstruction) -> Option<Instruction>,
+) -> Vec<(Address, RawInstruction, Option<Instruction>)> {
+    bytes
+        .chunks_exact(CPU::INSTRUCTION_SIZE_B.into())
+        .enumerate()
+        .map_while(|(index, chunk)| {
+            let addr = base_addr as usize + index * CPU::INSTRUCTION_SIZE_B as usize;
+            let addr = Address::new(u16::try_from(addr).ok()?).ok()?;
+            let raw = RawInstruction::new(chunk[0], chunk[1]);
+            let inst = decode(&raw);
+            Some((addr, raw, inst))
         })
-    }
+        .collect()
 }
@@ -0,0 +1,38 @@
+// One-time warnings that any subsystem can raise. They are shown in the log panel, since
+// printing them would corrupt the raw-mode screen
+
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Warning {
+    MachineRoutine,     // The ROM called a 0NNN machine language routine
+    NoKeyReleaseEvents, // The terminal only reports key presses
+    RplFlagsNotSaved,   // FX75 couldn't write the flags to disk
+}
+
+impl Warning {
+    pub fn message(&self) -> &'static str {
+        use Warning::*;
+        match self {
+            MachineRoutine => "ROM uses 0NNN machine routines, which are ignored",
+            NoKeyReleaseEvents => {
+                "Terminal does not report key releases, so keys may appear held down"
+            }
+            RplFlagsNotSaved => {
+                "Could not save the RPL flags, so they won't be kept after quitting"
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Diagnostics {
+    emitted: HashSet<Warning>,
+}
+
+impl Diagnostics {
+    // Returns the warning's message the first time it is raised, and None after that
+    pub fn warn_once(&mut self, warning: Warning) -> Option<&'static str> {
+        self.emitted.insert(warning).then(|| warning.message())
+    }
+}
@@ -0,0 +1,92 @@
+// The machine run one instruction at a time by whatever embeds it, e.g. a GUI's event loop,
+// which owns the pacing, the keyboard and the display. Nothing here reads the terminal or
+// needs an async runtime: the 60Hz timers tick once every `cpu_hz / 60` steps
+
+use crate::chip8::Chip8;
+use crate::error::Chip8Error;
+use crate::framebuffer::FrameBuffer;
+use crate::hardware::{Hardware, HardwareExecutionConfig};
+use crate::input::{Chip8KeyEventKind, Chip8KeyState};
+
+pub struct Emulator<'a> {
+    pub hardware: Hardware<'a>,
+    key_state: Chip8KeyState,
+    steps_per_tick: u32,
+    steps_to_tick: u32, // Until the timers next tick
+}
+
+impl<'a> Emulator<'a> {
+    pub fn new(config: HardwareExecutionConfig) -> Self {
+        let steps_per_tick = ((config.cpu_hz / Chip8::TIMER_HZ) as u32).max(1);
+        Self {
+            hardware: Hardware::new(config),
+            key_state: Chip8KeyState::default(),
+            steps_per_tick,
+            steps_to_tick: steps_per_tick,
+        }
+    }
+
+    pub fn load_rom(&mut self, bytes: &'a [u8]) -> Result<(), Chip8Error> {
+        self.steps_to_tick = self.steps_per_tick;
+        self.hardware.load_rom(bytes)
+    }
+
+    // Timer ticks come this many steps apart
+    pub fn steps_per_tick(&self) -> usize {
+        self.steps_per_tick as usize
+    }
+
+    // Presses or releases one of the 16 keys
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        let kind = if pressed {
+            self.key_state.press(key);
+            Chip8KeyEventKind::Press
+        } else {
+            self.key_state.release(key);
+            Chip8KeyEventKind::Release
+        };
+        self.hardware.set_key_state(&self.key_state);
+        self.hardware.record_key_event(key, kind);
+        self.hardware.handle_key_when_waiting(key, kind);
+    }
+
+    // Runs one instruction, unless the ROM is waiting for a key or the display, and ticks the
+    // timers when they're due. Returns false once the ROM has exited, and the error if the
+    // instruction faulted, leaving the PC on it
+    pub fn step(&mut self) -> Result<bool, Chip8Error> {
+        self.run_steps(1)?;
+        Ok(!self.hardware.has_exited())
+    }
+
+    // Runs up to `steps` steps as `step()` would, but with straight-line code run as cached
+    // basic blocks. Returns how many ran, which is fewer once the ROM exits
+    pub fn run_steps(&mut self, steps: usize) -> Result<usize, Chip8Error> {
+        let mut ran = 0;
+        while ran < steps && !self.hardware.has_exited() {
+            // Blocks are cut short at the next tick, which can end a wait for the display
+            let budget = (steps - ran).min(self.steps_to_tick as usize);
+            let taken =
+                if self.hardware.is_waiting_for_key() || self.hardware.is_waiting_for_vblank() {
+                    budget
+                } else {
+                    self.hardware.execute_block(budget)?
+                };
+            ran += taken;
+            self.steps_to_tick -= taken as u32;
+            if self.steps_to_tick == 0 {
+                self.steps_to_tick = self.steps_per_tick;
+                self.hardware.decrement_timers();
+                self.hardware.update_audio();
+            }
+        }
+        Ok(ran)
+    }
+
+    pub fn framebuffer(&self) -> &FrameBuffer {
+        &self.hardware.framebuffer
+    }
+
+    pub fn is_beeping(&self) -> bool {
+        self.hardware.is_beeping()
+    }
+}
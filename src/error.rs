@@ -0,0 +1,77 @@
+// Why the machine stopped, or couldn't start. Returned rather than panicking, so the frontend
+// can leave the terminal tidily and report it, and so embedders and fuzzers can carry on
+
+use std::fmt;
+use std::io;
+
+use crate::chip8::Chip8Version;
+
+#[derive(Debug)]
+pub enum Chip8Error {
+    // In bytes, with room from the entry point to the end of memory
+    RomTooLarge {
+        size: usize,
+        max: usize,
+    },
+    InvalidInstruction {
+        pc: u16,
+        opcode: u16,
+    },
+    UnsupportedInstruction {
+        pc: u16,
+        opcode: u16,
+        version: Chip8Version,
+    },
+    // A return with no subroutine to return from
+    StackUnderflow {
+        pc: u16,
+    },
+    // The first address past the end of memory
+    MemoryOutOfBounds {
+        pc: u16,
+        addr: usize,
+    },
+    // One of the opt-in checks tripped, e.g. `--pc-guard`
+    Guard {
+        pc: u16,
+        reason: String,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Chip8Error::*;
+        match self {
+            RomTooLarge { size, max } => write!(
+                f,
+                "The ROM is too big to fit in memory ({size} bytes, at most {max})"
+            ),
+            InvalidInstruction { pc, opcode } => {
+                write!(f, "Invalid instruction {opcode:04X} at {pc:#05X}")
+            }
+            UnsupportedInstruction {
+                pc,
+                opcode,
+                version,
+            } => write!(
+                f,
+                "Instruction {opcode:04X} is not supported by {version} at {pc:#05X}"
+            ),
+            StackUnderflow { pc } => write!(f, "Return with an empty stack at {pc:#05X}"),
+            MemoryOutOfBounds { pc, addr } => {
+                write!(f, "Memory access out of bounds ({addr:#05X}) at {pc:#05X}")
+            }
+            Guard { pc, reason } => write!(f, "{reason} at {pc:#05X}"),
+            Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
+
+impl From<io::Error> for Chip8Error {
+    fn from(e: io::Error) -> Self {
+        Chip8Error::Io(e)
+    }
+}
@@ -0,0 +1,28 @@
+// The process exit codes, so scripts and CI pipelines can branch on how a run ended. Listed
+// in `--help`, and kept stable between releases
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,       // The user quit, or the ROM exited
+    Error = 1,         // Bad arguments, or an I/O error outside the ROM
+    RomLoadError = 2,  // The ROM couldn't be read, or doesn't fit in memory
+    Fault = 3,         // The emulator crashed, e.g. on an invalid instruction
+    TestMismatch = 4,  // A test run's output didn't match what was expected
+    LimitExceeded = 5, // The ROM hit one of the `--limits`
+    Interrupted = 130, // Stopped by SIGINT, as shells report it
+}
+
+impl ExitCode {
+    pub const HELP: &str = "Exit codes:
+  0  Quit normally, or the ROM exited
+  1  Bad arguments or another error
+  2  The ROM could not be loaded
+  3  The emulator faulted (see the crash report in the logs directory)
+  4  A test run did not match what was expected
+  5  The ROM hit one of the --limits
+130  Interrupted by SIGINT (see the crash report in the logs directory)";
+
+    pub fn exit(self) -> ! {
+        std::process::exit(self as i32)
+    }
+}
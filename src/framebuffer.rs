@@ -0,0 +1,279 @@
+// The CHIP-8 display as plain pixel state, with no terminal I/O. The emulator draws into it,
+// and frontends (the terminal renderer, the video recorder, tests) read from it
+
+use crate::savestate::{StateReader, StateWriter};
+use crate::util;
+
+// Each row is packed into the bits of a u128, with the leftmost pixel in the highest bit, so
+// a sprite row is drawn with a shift and an XOR. 64x32 rows only use the top 64 bits
+type Row = u128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameBuffer {
+    planes: [[Row; Self::HIRES_ROWS as usize]; Self::PLANES], // Only the first `height` are used
+    selected_planes: u8, // Bitmask of the XO-CHIP planes that drawing and clearing affect
+    hires: bool,         // SUPER-CHIP 128x64 mode, rather than 64x32
+    pub display_on: bool, // COSMAC VIP machine code can switch the display off
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameBuffer {
+    pub const LORES_COLS: u8 = 64;
+    pub const LORES_ROWS: u8 = 32;
+    pub const HIRES_COLS: u8 = 128;
+    pub const HIRES_ROWS: u8 = 64;
+    // XO-CHIP draws to two planes, which are shown in up to four colors. Everything else only
+    // uses the first
+    pub const PLANES: usize = 2;
+
+    pub fn new() -> Self {
+        Self {
+            planes: [[0; Self::HIRES_ROWS as usize]; Self::PLANES],
+            selected_planes: 1,
+            hires: false,
+            display_on: true,
+        }
+    }
+
+    pub fn width(&self) -> u8 {
+        if self.hires {
+            Self::HIRES_COLS
+        } else {
+            Self::LORES_COLS
+        }
+    }
+
+    pub fn height(&self) -> u8 {
+        if self.hires {
+            Self::HIRES_ROWS
+        } else {
+            Self::LORES_ROWS
+        }
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    // Switches between the 64x32 and SUPER-CHIP 128x64 resolutions, which clears every plane
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        for plane in &mut self.planes {
+            plane.fill(0);
+        }
+    }
+
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    // XO-CHIP's FN01, where bit 0 is the first plane and bit 1 the second
+    pub fn select_planes(&mut self, mask: u8) {
+        self.selected_planes = mask & 0b11;
+    }
+
+    // The first plane, row by row, `width()` pixels wide
+    pub fn pixels(&self) -> Vec<bool> {
+        let (width, height) = (self.width(), self.height());
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| self.planes[0][y as usize] & Self::bit(x) != 0)
+            .collect()
+    }
+
+    // A pixel of the first plane
+    pub fn get_pixel(&self, x: u8, y: u8) -> Option<bool> {
+        self.get_plane_pixel(0, x, y)
+    }
+
+    pub fn set_pixel(&mut self, x: u8, y: u8, value: bool) {
+        self.set_plane_pixel(0, x, y, value);
+    }
+
+    pub fn get_plane_pixel(&self, plane: usize, x: u8, y: u8) -> Option<bool> {
+        if x >= self.width() || y >= self.height() {
+            None
+        } else {
+            Some(self.planes[plane][y as usize] & Self::bit(x) != 0)
+        }
+    }
+
+    pub fn set_plane_pixel(&mut self, plane: usize, x: u8, y: u8, value: bool) {
+        if x >= self.width() || y >= self.height() {
+            return;
+        }
+        let row = &mut self.planes[plane][y as usize];
+        if value {
+            *row |= Self::bit(x);
+        } else {
+            *row &= !Self::bit(x);
+        }
+    }
+
+    // XORs a sprite row onto the plane, returning whether any lit pixel was turned off. The
+    // row is left-aligned in 16 bits, and drawn at `x`. Pixels past the right edge are clipped,
+    // or wrap around to the left edge
+    pub fn xor_sprite_row(
+        &mut self,
+        plane: usize,
+        x: u8,
+        y: u8,
+        sprite_row: u16,
+        wrap: bool,
+    ) -> bool {
+        let (width, height) = (self.width(), self.height());
+        if x >= width || y >= height {
+            return false;
+        }
+        let sprite = (sprite_row as Row) << (Row::BITS - 16);
+        let mut mask = sprite >> x;
+        if wrap {
+            mask |= sprite.checked_shl((width - x) as u32).unwrap_or(0);
+        }
+        mask &= Self::visible_columns(width);
+
+        let row = &mut self.planes[plane][y as usize];
+        let collided = *row & mask != 0;
+        *row ^= mask;
+        collided
+    }
+
+    // Which planes the pixel is on in, as a bitmask like `select_planes`'
+    pub fn color_index(&self, x: u8, y: u8) -> u8 {
+        (0..Self::PLANES)
+            .filter(|&plane| self.get_plane_pixel(plane, x, y) == Some(true))
+            .fold(0, |index, plane| index | 1 << plane)
+    }
+
+    // Whether the pixel is lit as seen on the display, which is dark while switched off
+    pub fn is_lit(&self, x: u8, y: u8) -> bool {
+        self.display_on && self.color_index(x, y) != 0
+    }
+
+    // Clears the selected planes, which is the whole display outside of XO-CHIP
+    pub fn clear(&mut self) {
+        for plane in self.selected() {
+            self.planes[plane].fill(0);
+        }
+    }
+
+    // XO-CHIP's 00DN, which moves the selected planes up by `rows`
+    pub fn scroll_up(&mut self, rows: u8) {
+        let height = self.height() as usize;
+        let shift = (rows as usize).min(height);
+        for plane in self.selected() {
+            let plane = &mut self.planes[plane][..height];
+            plane.copy_within(shift.., 0);
+            plane[height - shift..].fill(0);
+        }
+    }
+
+    // SUPER-CHIP's 00CN, which moves the selected planes down by `rows`
+    pub fn scroll_down(&mut self, rows: u8) {
+        let height = self.height() as usize;
+        let shift = (rows as usize).min(height);
+        for plane in self.selected() {
+            let plane = &mut self.planes[plane][..height];
+            plane.copy_within(..height - shift, shift);
+            plane[..shift].fill(0);
+        }
+    }
+
+    // SUPER-CHIP's 00FB and 00FC, which move the selected planes right (or left, for negative
+    // `columns`), clearing the columns scrolled in
+    pub fn scroll_horizontally(&mut self, columns: i8) {
+        let (height, visible) = (self.height() as usize, Self::visible_columns(self.width()));
+        let shift = columns.unsigned_abs() as u32;
+        for plane in self.selected() {
+            for row in &mut self.planes[plane][..height] {
+                let moved = if columns >= 0 {
+                    row.checked_shr(shift)
+                } else {
+                    row.checked_shl(shift)
+                };
+                *row = moved.unwrap_or(0) & visible;
+            }
+        }
+    }
+
+    // Stable across runs and platforms, so it can be stored and compared. Frames that only use
+    // the first plane hash the same as before there were two
+    pub fn hash(&self) -> u64 {
+        util::stable_hash(&self.color_indexes())
+    }
+
+    // The pixels whose colour differs from `other`, row by row. A change of resolution
+    // changes every pixel
+    pub fn changed_pixels(&self, other: &FrameBuffer) -> Vec<(u8, u8)> {
+        let (width, height) = (self.width(), self.height());
+        let resized = (width, height) != (other.width(), other.height());
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| resized || self.color_index(x, y) != other.color_index(x, y))
+            .collect()
+    }
+
+    // One line per row, with `#` for lit pixels and `.` for unlit ones. Pixels on the second
+    // XO-CHIP plane are `+`, or `*` when on both
+    pub fn to_text(&self) -> String {
+        self.color_indexes()
+            .chunks(self.width() as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|&index| ['.', '#', '+', '*'][index as usize])
+                    .collect()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn color_indexes(&self) -> Vec<u8> {
+        let (width, height) = (self.width(), self.height());
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| self.color_index(x, y)))
+            .collect()
+    }
+
+    pub(crate) fn save_state(&self, out: &mut StateWriter) {
+        out.u8(self.hires as u8);
+        out.u8(self.selected_planes);
+        out.u8(self.display_on as u8);
+        for plane in &self.planes {
+            for &row in plane {
+                out.u128(row);
+            }
+        }
+    }
+
+    pub(crate) fn load_state(state: &mut StateReader) -> Result<Self, String> {
+        let mut frame = Self::new();
+        frame.hires = state.bool()?;
+        frame.select_planes(state.u8()?);
+        frame.display_on = state.bool()?;
+        for plane in &mut frame.planes {
+            for row in plane {
+                *row = state.u128()?;
+            }
+        }
+        Ok(frame)
+    }
+
+    fn selected(&self) -> impl Iterator<Item = usize> + use<> {
+        let mask = self.selected_planes;
+        (0..Self::PLANES).filter(move |&plane| mask & (1 << plane) != 0)
+    }
+
+    fn bit(x: u8) -> Row {
+        1 << (Row::BITS - 1 - x as u32)
+    }
+
+    // The bits of a row that are on the display
+    fn visible_columns(width: u8) -> Row {
+        !0 << (Row::BITS - width as u32)
+    }
+}
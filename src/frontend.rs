@@ -0,0 +1,249 @@
+// What the hardware reports to whichever frontend shows it. `Hardware` queues a
+// `FrontendEvent` for each message, notice and change to the debug overlay or beep, and the
+// frontend takes them after running it, so the core never draws or plays anything itself
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{
+    audio::AudioEvent,
+    input::{Chip8KeyState, KeyHistory},
+    machinestate::StateDiff,
+    primitive::{Address, Instruction, RawInstruction},
+    timing::{LatencyStats, TimingStats},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlaybackMode {
+    Running,
+    Paused,
+    Stepping,
+}
+
+#[derive(Debug, Clone)]
+pub enum FrontendEvent {
+    Log(String),                 // For the frontend's log of warnings and faults
+    Notice(String, Duration),    // Shown over the display for the duration
+    ErrorBanner(Option<String>), // Shown over the display until it's cleared
+    Audio(AudioEvent),
+    SettingsMenu(Option<Vec<String>>), // The settings page's lines, while it's open
+    DebugInfo(DebugInfo),              // The whole debug overlay, sent first
+    DebugDelta(DebugDelta),            // What changed in the overlay since
+    FrameDiff(StateDiff),              // What the last frame that drew changed
+    ShowDiff(bool),
+    Color(ScreenColor),
+    CrashSnapshot(String), // The machine as it was when it faulted, for crash reports
+}
+
+// Events waiting for the frontend. Without one taking them, the oldest are dropped, so
+// machines run without a frontend don't keep every message
+#[derive(Default)]
+pub struct FrontendQueue {
+    events: VecDeque<FrontendEvent>,
+}
+
+impl FrontendQueue {
+    const CAPACITY: usize = 256;
+
+    pub fn push(&mut self, event: FrontendEvent) {
+        if self.events.len() == Self::CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    pub fn log(&mut self, message: String) {
+        self.push(FrontendEvent::Log(message));
+    }
+
+    pub fn take(&mut self) -> Vec<FrontendEvent> {
+        self.events.drain(..).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugInfo {
+    pub current_pc: u16,
+    pub raw_instruction: RawInstruction,
+    pub decoded_instruction: Instruction,
+    pub index_register: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub beeping: bool,
+    pub timer_history: Vec<(u8, u8)>, // (Delay, Sound), oldest first
+    pub registers: [u8; 16],
+    pub key_state: Chip8KeyState,
+    pub key_history: KeyHistory,
+    pub playback_mode: PlaybackMode,
+    pub speed: f64,
+    pub upcoming: Vec<(Address, RawInstruction, Option<Instruction>)>, // Instructions from PC on
+    pub memory_at_index: Vec<u8>,
+    pub timing: TimingStats,
+    pub latency: LatencyStats,
+}
+
+// Changes to `DebugInfo` since the last update. Only what changed is recomputed and sent, so
+// keeping the overlay up to date is cheap, especially while paused
+#[derive(Debug, Clone, Default)]
+pub struct DebugDelta {
+    pub current_pc: Option<u16>,
+    pub instruction: Option<(RawInstruction, Instruction)>,
+    pub index_register: Option<u16>,
+    pub delay_timer: Option<u8>,
+    pub sound_timer: Option<u8>,
+    pub beeping: Option<bool>,
+    pub timer_history: Option<Vec<(u8, u8)>>,
+    pub registers: Vec<(usize, u8)>, // (Register number, new value)
+    pub key_state: Option<Chip8KeyState>,
+    pub key_history: Option<KeyHistory>,
+    pub playback_mode: Option<PlaybackMode>,
+    pub speed: Option<f64>,
+    pub upcoming: Option<Vec<(Address, RawInstruction, Option<Instruction>)>>,
+    pub memory_at_index: Option<Vec<u8>>,
+    pub timing: Option<TimingStats>,
+    pub latency: Option<LatencyStats>,
+}
+
+impl DebugDelta {
+    pub fn is_empty(&self) -> bool {
+        self.current_pc.is_none()
+            && self.instruction.is_none()
+            && self.index_register.is_none()
+            && self.delay_timer.is_none()
+            && self.sound_timer.is_none()
+            && self.beeping.is_none()
+            && self.timer_history.is_none()
+            && self.registers.is_empty()
+            && self.key_state.is_none()
+            && self.key_history.is_none()
+            && self.playback_mode.is_none()
+            && self.speed.is_none()
+            && self.upcoming.is_none()
+            && self.memory_at_index.is_none()
+            && self.timing.is_none()
+            && self.latency.is_none()
+    }
+}
+
+impl DebugInfo {
+    pub fn apply(&mut self, delta: &DebugDelta) {
+        if let Some(pc) = delta.current_pc {
+            self.current_pc = pc;
+        }
+        if let Some((ref raw, ref inst)) = delta.instruction {
+            self.raw_instruction = raw.clone();
+            self.decoded_instruction = inst.clone();
+        }
+        if let Some(index) = delta.index_register {
+            self.index_register = index;
+        }
+        if let Some(delay) = delta.delay_timer {
+            self.delay_timer = delay;
+        }
+        if let Some(sound) = delta.sound_timer {
+            self.sound_timer = sound;
+        }
+        if let Some(beeping) = delta.beeping {
+            self.beeping = beeping;
+        }
+        if let Some(ref history) = delta.timer_history {
+            self.timer_history = history.clone();
+        }
+        for &(reg, value) in &delta.registers {
+            self.registers[reg] = value;
+        }
+        if let Some(key_state) = delta.key_state {
+            self.key_state = key_state;
+        }
+        if let Some(ref key_history) = delta.key_history {
+            self.key_history = key_history.clone();
+        }
+        if let Some(ref mode) = delta.playback_mode {
+            self.playback_mode = mode.clone();
+        }
+        if let Some(speed) = delta.speed {
+            self.speed = speed;
+        }
+        if let Some(ref upcoming) = delta.upcoming {
+            self.upcoming = upcoming.clone();
+        }
+        if let Some(ref memory) = delta.memory_at_index {
+            self.memory_at_index = memory.clone();
+        }
+        if let Some(timing) = delta.timing {
+            self.timing = timing;
+        }
+        if let Some(latency) = delta.latency {
+            self.latency = latency;
+        }
+    }
+}
+
+macro_rules! screen_color {
+    (
+        pub enum $name:ident {
+            $($variant:ident),* $(,)?
+        }
+    ) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+        pub enum $name {
+            $($variant,)*
+        }
+
+        impl From<$name> for crossterm::style::Color {
+            fn from(screen_color: $name) -> Self {
+                match screen_color {
+                    $($name::$variant => crossterm::style::Color::$variant,)*
+                }
+            }
+        }
+    };
+}
+
+screen_color!(
+    pub enum ScreenColor {
+        Red,
+        DarkRed,
+        Green,
+        DarkGreen,
+        Yellow,
+        DarkYellow,
+        Blue,
+        DarkBlue,
+        Magenta,
+        DarkMagenta,
+        Cyan,
+        DarkCyan,
+        White,
+        Grey,
+    }
+);
+
+impl std::fmt::Display for ScreenColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{:#?}", self).to_lowercase())
+    }
+}
+
+impl ScreenColor {
+    // Approximate RGB value of the terminal color, for output that doesn't go to the terminal
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        use ScreenColor::*;
+        match self {
+            Red => (255, 85, 85),
+            DarkRed => (170, 0, 0),
+            Green => (85, 255, 85),
+            DarkGreen => (0, 170, 0),
+            Yellow => (255, 255, 85),
+            DarkYellow => (170, 85, 0),
+            Blue => (85, 85, 255),
+            DarkBlue => (0, 0, 170),
+            Magenta => (255, 85, 255),
+            DarkMagenta => (170, 0, 170),
+            Cyan => (85, 255, 255),
+            DarkCyan => (0, 170, 170),
+            White => (255, 255, 255),
+            Grey => (170, 170, 170),
+        }
+    }
+}
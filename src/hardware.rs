@@ -1,101 +1,1292 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::annotations::MemoryAnnotations;
+use crate::audio::{AudioEvent, AudioPattern};
 use crate::chip8::{Chip8, Chip8Version};
 use crate::cpu::CPU;
-use crate::input::{Chip8KeyEventKind, Chip8KeyState};
+use crate::diagnostics::{Diagnostics, Warning};
+use crate::error::Chip8Error;
+use crate::framebuffer::FrameBuffer;
+use crate::frontend::{
+    DebugDelta, DebugInfo, FrontendEvent, FrontendQueue, PlaybackMode, ScreenColor,
+};
+use crate::icache::BasicBlock;
+use crate::input::{Chip8KeyEventKind, Chip8KeyState, KeyHistory, KeyboardLayout, MenuKey};
+use crate::keymacro::{MacroPlayback, MacroRecording, MacroStore};
+use crate::limits::{ExecutionLimits, LimitExceeded};
+use crate::machinestate::MachineState;
 use crate::primitive::*;
-use crate::scheduler::PlaybackMode;
-use crate::screen::{DebugInfo, Screen, ScreenColor};
+use crate::quirks::Quirks;
+use crate::recorder::VideoRecorder;
+use crate::replay::{Divergence, InputRecorder, InputReplay, ReplayEvent};
+use crate::rewind::RewindBuffer;
+use crate::rplflags::RplFlags;
+use crate::savestate::{SaveSlots, SaveState};
+use crate::settings::{Settings, SettingsMenu};
+use crate::timing::{InstructionTimer, LatencyTracker, TimerHistory, TimingStats};
+use crate::util;
+use crate::vip::VipRoutine;
+
+// What happens when the ROM halts, by jumping to itself or looping on GetKey
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum HaltAction {
+    Run,   // Keep running, for interactive play
+    Exit,  // Quit the emulator, for tests
+    Break, // Pause in the debugger, for development
+}
+
+impl std::fmt::Display for HaltAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use HaltAction::*;
+        write!(
+            f,
+            "{}",
+            match self {
+                Run => "run",
+                Exit => "exit",
+                Break => "break",
+            }
+        )
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct HardwareExecutionConfig {
     pub version: Chip8Version,
+    pub cpu_hz: f64,
     pub screen_color: ScreenColor,
+    pub quirks: Option<Quirks>, // None behaves like `version`
+    pub vip_routines: bool,     // Emulate common COSMAC VIP machine code routines called by 0NNN
+    pub on_halt: HaltAction,
+    pub on_invalid: InvalidAction,
+    pub limits: ExecutionLimits,
+    pub pc_guard: bool, // Fault when the PC goes odd or off the end of memory, rather than wrap
+    pub memory_protection: Option<MemoryProtection>, // None lets the ROM write anywhere
+    pub font_addr: u16, // Where the small font is loaded, with the big one right after it
+}
+
+impl Default for HardwareExecutionConfig {
+    fn default() -> Self {
+        Self {
+            version: Chip8Version::Cosmac,
+            cpu_hz: Chip8::CPU_FREQ_HZ,
+            screen_color: ScreenColor::Green,
+            quirks: None,
+            vip_routines: false,
+            on_halt: HaltAction::Run,
+            on_invalid: InvalidAction::Panic,
+            limits: ExecutionLimits::default(),
+            pc_guard: false,
+            memory_protection: None,
+            font_addr: Chip8::FONT_START_ADDR,
+        }
+    }
+}
+
+// What happens when the ROM reaches an instruction the configured version can't run
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum InvalidAction {
+    Halt,  // Stay on it with an error banner, so the machine can be looked at
+    Skip,  // Log it and run on past it
+    Panic, // Stop with a fault report
+}
+
+impl std::fmt::Display for InvalidAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use InvalidAction::*;
+        write!(
+            f,
+            "{}",
+            match self {
+                Halt => "halt",
+                Skip => "skip",
+                Panic => "panic",
+            }
+        )
+    }
+}
+
+// What happens when the ROM writes to the font or the interpreter's memory below 0x200, which
+// is almost always a bug, though sometimes a trick
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum MemoryProtection {
+    Log,   // Note each instruction that does it in the log
+    Fault, // Stop with a crash report
+}
+
+impl std::fmt::Display for MemoryProtection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use MemoryProtection::*;
+        write!(
+            f,
+            "{}",
+            match self {
+                Log => "log",
+                Fault => "fault",
+            }
+        )
+    }
+}
+
+// The registers from Vx to Vy, which XO-CHIP allows to go backwards
+fn register_range(regx: &Register, regy: &Register) -> Vec<Register> {
+    let (x, y) = (regx.get(), regy.get());
+    let range: Vec<u8> = if x <= y {
+        (x..=y).collect()
+    } else {
+        (y..=x).rev().collect()
+    };
+    range
+        .into_iter()
+        .map(|i| Register::new(i).unwrap())
+        .collect()
 }
 
-// Manages the internal state of the CPU and the Screen
+// Which of the debug overlay's histories changed since it was last updated, so the rest are
+// neither rebuilt nor compared. Memory writes are tracked by the CPU
+#[derive(Default)]
+struct DebugDirty {
+    key_history: bool,
+    timer_history: bool,
+}
+
+// Where a sprite is in memory, and its size in pixels
+struct Sprite {
+    addr: u16,
+    width: u8, // 8, or 16 for SUPER-CHIP's big sprites
+    height: u8,
+}
+
+// Manages the internal state of the CPU and the display
 pub struct Hardware<'a> {
     pub cpu: CPU,
-    pub screen: Screen,
+    pub framebuffer: FrameBuffer,
+    frontend: FrontendQueue, // What to tell the frontend, until it takes it
     key_state: Chip8KeyState,
     config: HardwareExecutionConfig,
+    quirks: Quirks, // The config's, or its version's
     playback_state: PlaybackMode,
-    playback_receiver: Option<tokio::sync::mpsc::Receiver<PlaybackMode>>,
+    speed: f64,         // How many times faster than normal the clock runs
+    beeping: bool,      // Whether the last audio event started the beep
+    vip_tone: bool,     // Whether a VIP machine code routine turned the speaker on
+    silent_sound: bool, // The sound timer was last set too short to sound, with `sound_threshold`
+    audio_pattern: Option<AudioPattern>, // XO-CHIP's audio buffer, once F002 loaded one
+    pitch: u8,          // XO-CHIP's FX3A, which the audio buffer plays at
     rom_ref: Option<&'a [u8]>,
+    video_recorder: Option<VideoRecorder>,
+    exited: bool,
+    limit_exceeded: Option<LimitExceeded>,
+    disk_written: u64, // By saves, macros, flags and input recordings, for the disk limit
+    faulted: Option<Chip8Error>,
+    loaded_at: Option<Instant>, // When the ROM was loaded, for the time limit
+    settings_menu: Option<SettingsMenu>, // The settings page, while it is open
+    rpl_flags: RplFlags,
+    save_slots: Option<SaveSlots>, // Where F5 saves the machine, and F9 loads it from
+    autosave_interval: Option<Duration>,
+    last_autosave: Instant,
+    rewind: RewindBuffer,
+    input_recorder: Option<InputRecorder>, // `--record`
+    input_replay: Option<InputReplay>,     // `--replay`, which takes the place of live input
+    macro_store: Option<MacroStore>,
+    macro_recording: Option<MacroRecording>,
+    macro_playback: Option<MacroPlayback>, // Which takes the place of live keys while it plays
+    replayed_frames: u64,
+    replay_divergence: Option<Divergence>,
+    last_matched: Option<MachineState>, // The state at the replay's last matching checksum
+    show_diff: bool,                    // Whether the frontend shows what each frame changed
+    last_flushed: Option<MachineState>, // The state at the last flush, while showing the diff
+    recent_pcs: VecDeque<u16>,          // Where the last instructions ran, oldest first
+    halted_at: Option<u16>,             // Address of the last halt that was reported
+    halted_on_invalid: bool,            // Stuck on an invalid instruction, with the error banner up
+    break_requested: bool,              // A halt asked for the debugger to pause
+    waiting_for_vblank: bool,           // A draw is waiting for the display interrupt
+    debug_snapshot: Option<DebugInfo>,  // What the frontend's debug overlay currently shows
+    debug_dirty: DebugDirty,            // What changed since, of what's costly to compare
+    crash_snapshots: bool, // Whether crash reports are kept up to date with this machine
+    annotations: MemoryAnnotations, // Labels for the call stack in crash reports
+    pub instruction_timer: InstructionTimer,
+    pub latency: LatencyTracker,
+    cycles: u64, // Instructions executed since the ROM was loaded
+    key_history: KeyHistory,
+    timer_history: TimerHistory,
+    diagnostics: Diagnostics,
+    protected_writes_logged: HashSet<u16>, // Instructions already logged writing to the font
+    invalid_skipped: HashSet<u16>,         // Invalid instructions already logged as skipped
+    rng: StdRng,                           // Used by the Random instruction
 }
 
 impl<'a> Hardware<'a> {
+    // The VIP's tone needs this many ticks to be heard at all
+    const MIN_AUDIBLE_SOUND_TIMER: u8 = 2;
+    // Length of the tone played by COSMAC GetKey while a key is held
+    const GET_KEY_TONE_TICKS: u8 = 4;
+    // How much of the program and memory is shown in the debug overlay
+    const DEBUG_UPCOMING_INSTRUCTIONS: usize = 4;
+    const DEBUG_MEMORY_BYTES: usize = 8;
+    // SUPER-CHIP's 00FB and 00FC move 4 pixels in either resolution, as later interpreters do
+    const SCROLL_COLUMNS: i8 = 4;
+    // XO-CHIP's pitch before FX3A sets one, which plays the pattern at 4000Hz
+    const DEFAULT_PITCH: u8 = 64;
+    const DEBUG_TIMER_HISTORY_WIDTH: usize = 60;
+    // How long the notice about reloaded settings is shown
+    const SETTINGS_MESSAGE_DURATION: Duration = Duration::from_secs(3);
+    const NOTICE_DURATION: Duration = Duration::from_secs(2);
+    const RESUME_MESSAGE_DURATION: Duration = Duration::from_secs(5);
+    // How far back each press of the rewind key goes
+    const REWIND_STEP_FRAMES: usize = 15;
+    // Longer straight-line runs are split into several basic blocks
+    const MAX_BLOCK_LEN: usize = 64;
+    // Skipping a delay wait gives up after this many frames, in case the ROM keeps resetting it
+    const MAX_SKIPPED_FRAMES: u32 = 256;
+    // How many of the last instructions run are kept for crash reports
+    const RECENT_INSTRUCTIONS: usize = 16;
+
     pub fn new(config: HardwareExecutionConfig) -> Self {
         Self {
-            cpu: CPU::new(),
-            screen: Screen::new(config.screen_color),
+            cpu: CPU::with_memory_size(config.version.memory_size()),
+            framebuffer: FrameBuffer::new(),
+            frontend: FrontendQueue::default(),
             key_state: Chip8KeyState::default(),
+            quirks: config
+                .quirks
+                .unwrap_or_else(|| Quirks::for_version(&config.version)),
             config,
             playback_state: PlaybackMode::Running,
-            playback_receiver: None,
+            speed: 1.0,
+            beeping: false,
+            vip_tone: false,
+            silent_sound: false,
+            audio_pattern: None,
+            pitch: Self::DEFAULT_PITCH,
             rom_ref: None,
+            video_recorder: None,
+            exited: false,
+            limit_exceeded: None,
+            disk_written: 0,
+            faulted: None,
+            loaded_at: None,
+            settings_menu: None,
+            rpl_flags: RplFlags::default(),
+            save_slots: None,
+            autosave_interval: None,
+            last_autosave: Instant::now(),
+            rewind: RewindBuffer::default(),
+            input_recorder: None,
+            input_replay: None,
+            macro_store: None,
+            macro_recording: None,
+            macro_playback: None,
+            replayed_frames: 0,
+            replay_divergence: None,
+            last_matched: None,
+            show_diff: false,
+            last_flushed: None,
+            recent_pcs: VecDeque::with_capacity(Self::RECENT_INSTRUCTIONS),
+            halted_at: None,
+            halted_on_invalid: false,
+            break_requested: false,
+            waiting_for_vblank: false,
+            debug_snapshot: None,
+            debug_dirty: DebugDirty::default(),
+            crash_snapshots: false,
+            annotations: MemoryAnnotations::default(),
+            instruction_timer: InstructionTimer::new(),
+            latency: LatencyTracker::default(),
+            cycles: 0,
+            key_history: KeyHistory::default(),
+            timer_history: TimerHistory::default(),
+            diagnostics: Diagnostics::default(),
+            protected_writes_logged: HashSet::new(),
+            invalid_skipped: HashSet::new(),
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    // Sends the frontend snapshots of the machine for its crash reports. The snapshot is
+    // shared by the whole process, so only the one machine the frontend runs should do this
+    pub fn enable_crash_snapshots(&mut self) {
+        self.crash_snapshots = true;
+    }
+
+    pub fn record_crash_snapshot(&mut self) {
+        if self.crash_snapshots {
+            let snapshot = self.machine_snapshot();
+            self.frontend.push(FrontendEvent::CrashSnapshot(snapshot));
+        }
+    }
+
+    // Everything the machine reported since this was last called, oldest first
+    pub fn take_frontend_events(&mut self) -> Vec<FrontendEvent> {
+        self.frontend.take()
+    }
+
+    // Labels the call stack in crash reports
+    pub fn set_annotations(&mut self, annotations: MemoryAnnotations) {
+        self.annotations = annotations;
+    }
+
+    // Makes the Random instruction produce the same numbers on every run
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // Whether the debugger has the machine running, paused or stepping
+    pub fn set_playback_mode(&mut self, mode: PlaybackMode) {
+        self.playback_state = mode;
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed;
+    }
+
+    // Emits an audio event when the beeper turns on or off. It is silent while not running
+    pub fn update_audio(&mut self) {
+        let beeping = ((self.cpu.get_sound_timer() > 0 && !self.silent_sound) || self.vip_tone)
+            && self.playback_state == PlaybackMode::Running;
+        if beeping == self.beeping {
+            return;
         }
+        self.beeping = beeping;
+        let event = if beeping {
+            AudioEvent::BeepStart
+        } else {
+            AudioEvent::BeepStop
+        };
+        self.frontend.push(FrontendEvent::Audio(event));
+    }
+
+    // Whether the beep is sounding, as of the last audio event
+    pub fn is_beeping(&self) -> bool {
+        self.beeping
     }
 
-    pub fn set_playback_receiver(&mut self, receiver: tokio::sync::mpsc::Receiver<PlaybackMode>) {
-        self.playback_receiver = Some(receiver);
+    // What the beep sounds like, if an XO-CHIP ROM set it
+    pub fn audio_pattern(&self) -> Option<AudioPattern> {
+        self.audio_pattern
+    }
+
+    fn set_audio_pattern(&mut self, pattern: Option<AudioPattern>) {
+        if pattern == self.audio_pattern {
+            return;
+        }
+        self.audio_pattern = pattern;
+        self.frontend
+            .push(FrontendEvent::Audio(AudioEvent::Pattern(pattern)));
+    }
+
+    // Flags that FX75 saves to disk, rather than only keeping them in memory
+    pub fn set_rpl_flags(&mut self, rpl_flags: RplFlags) {
+        self.rpl_flags = rpl_flags;
+    }
+
+    // Records input from now on, with the Random instruction seeded by `seed`
+    pub fn set_input_recorder(&mut self, recorder: InputRecorder, seed: u64) {
+        self.set_seed(seed);
+        self.input_recorder = Some(recorder);
+    }
+
+    // Replays recorded input instead of the keyboard and timers, until it runs out. Recordings
+    // of another ROM, or with other quirks, are refused, as they would play out differently
+    pub fn set_input_replay(&mut self, replay: InputReplay) -> Result<(), String> {
+        let rom_hash = util::stable_hash(self.rom_ref.unwrap_or_default());
+        replay.check_matches(rom_hash, &self.quirks)?;
+        self.set_seed(replay.seed);
+        self.input_replay = Some(replay);
+        Ok(())
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.input_replay.is_some()
+    }
+
+    // A key pressed or released on the keyboard, which replays ignore
+    pub fn live_key_event(&mut self, key: u8, kind: Chip8KeyEventKind) {
+        if self.is_replaying() || self.is_playing_macro() {
+            return;
+        }
+        if let Some(ref mut recording) = self.macro_recording {
+            recording.record(self.cycles, key, kind);
+        }
+        self.record_input(ReplayEvent::Key(key, kind));
+        self.record_key_event(key, kind);
+        self.handle_key_when_waiting(key, kind);
+    }
+
+    // A tick of the 60Hz timers, which replays ignore
+    pub fn live_timer_tick(&mut self) {
+        if self.is_replaying() {
+            return;
+        }
+        self.record_input(ReplayEvent::TimerTick);
+        self.decrement_timers();
+        if self
+            .input_recorder
+            .as_ref()
+            .is_some_and(InputRecorder::records_checksums)
+        {
+            self.record_input(ReplayEvent::Checksum(self.state_checksum()));
+        }
+    }
+
+    // CRC of the registers, memory and display, to tell where two runs went different ways
+    pub fn state_checksum(&self) -> u32 {
+        util::crc32(&SaveState::encode_machine(&self.cpu, &self.framebuffer))
+    }
+
+    // Where the replay first stopped matching its checksums, if it has
+    pub fn replay_divergence(&self) -> Option<Divergence> {
+        self.replay_divergence.clone()
+    }
+
+    fn record_input(&mut self, event: ReplayEvent) {
+        let Some(ref mut recorder) = self.input_recorder else {
+            return;
+        };
+        match recorder.record(self.cycles, event) {
+            Ok(bytes) => self.wrote_to_disk(bytes),
+            Err(e) => {
+                self.frontend.log(format!("Input recording stopped: {e}"));
+                self.input_recorder = None;
+            }
+        }
+    }
+
+    // Recordings and replays count cycles from the start of one continuous run, so they can't
+    // carry on past a restart or a jump to another state
+    fn stop_input_capture(&mut self, why: &str) {
+        if self.input_recorder.take().is_some() {
+            self.frontend
+                .log(format!("Input recording stopped, as {why}"));
+        }
+        if self.input_replay.take().is_some() {
+            self.frontend.log(format!(
+                "Replay stopped, as {why}, the keyboard is live again"
+            ));
+        }
+    }
+
+    // Plays back the recorded input that came before the next instruction
+    pub fn replay_due_input(&mut self) {
+        let Some(ref mut replay) = self.input_replay else {
+            return;
+        };
+        let mut due = Vec::new();
+        while let Some(event) = replay.next_due(self.cycles) {
+            due.push(event);
+        }
+        if replay.is_finished() {
+            self.input_replay = None;
+            self.frontend
+                .log("Replay finished, the keyboard is live again".to_string());
+        }
+        for event in due {
+            match event {
+                ReplayEvent::Key(key, kind) => {
+                    match kind {
+                        Chip8KeyEventKind::Press => self.key_state.press(key),
+                        Chip8KeyEventKind::Release => self.key_state.release(key),
+                    }
+                    self.record_key_event(key, kind);
+                    self.handle_key_when_waiting(key, kind);
+                }
+                ReplayEvent::TimerTick => {
+                    self.decrement_timers();
+                    self.replayed_frames += 1;
+                }
+                ReplayEvent::Checksum(recorded) => self.check_replayed_state(recorded),
+            }
+        }
+    }
+
+    pub fn set_macro_store(&mut self, store: MacroStore) {
+        self.macro_store = Some(store);
+    }
+
+    pub fn is_playing_macro(&self) -> bool {
+        self.macro_playback.is_some()
+    }
+
+    // Starts recording the keys pressed into the macro, or stops and saves it
+    pub fn toggle_macro_recording(&mut self) {
+        let Some(ref store) = self.macro_store else {
+            return;
+        };
+        let name = store.name();
+        let mut written = 0;
+        let message = match self.macro_recording.take() {
+            None if self.macro_playback.is_some() => {
+                format!("Macro '{name}' is playing, wait for it to finish")
+            }
+            None => {
+                self.macro_recording = Some(MacroRecording::start(self.cycles));
+                format!("Recording macro '{name}', press F3 to stop")
+            }
+            Some(recording) => {
+                let key_macro = recording.finish(self.cycles);
+                if key_macro.is_empty() {
+                    format!("Macro '{name}' not saved, no keys were pressed")
+                } else {
+                    match store.save(&key_macro) {
+                        Ok(bytes) => {
+                            written = bytes;
+                            format!(
+                                "Macro '{name}' saved with {} key presses, press F4 to play it",
+                                key_macro.presses()
+                            )
+                        }
+                        Err(e) => format!("Macro '{name}' not saved: {e}"),
+                    }
+                }
+            }
+        };
+        self.show_notice(message);
+        self.wrote_to_disk(written);
+    }
+
+    // Plays the macro's keys from the next instruction on, in place of the keyboard
+    pub fn play_macro(&mut self) {
+        let Some(ref store) = self.macro_store else {
+            return;
+        };
+        let name = store.name();
+        let message = if self.macro_recording.is_some() {
+            format!("Recording macro '{name}', press F3 to stop first")
+        } else if self.is_replaying() || self.is_playing_macro() {
+            return;
+        } else {
+            match store.load() {
+                Ok(Some(key_macro)) => {
+                    self.key_state = Chip8KeyState::default();
+                    self.macro_playback = Some(MacroPlayback::start(key_macro, self.cycles));
+                    format!("Playing macro '{name}'")
+                }
+                Ok(None) => format!("No macro '{name}' yet, press F3 to record one"),
+                Err(e) => format!("Macro '{name}' not played: {e}"),
+            }
+        };
+        self.show_notice(message);
+    }
+
+    // Presses and releases the macro's keys that come before the next instruction
+    pub fn play_due_macro_keys(&mut self) {
+        let Some(ref mut playback) = self.macro_playback else {
+            return;
+        };
+        let mut due = Vec::new();
+        while let Some(event) = playback.next_due(self.cycles) {
+            due.push(event);
+        }
+        if playback.is_finished() {
+            self.macro_playback = None;
+            self.frontend
+                .log("Macro finished, the keyboard is live again".to_string());
+        }
+        for (key, kind) in due {
+            match kind {
+                Chip8KeyEventKind::Press => self.key_state.press(key),
+                Chip8KeyEventKind::Release => self.key_state.release(key),
+            }
+            // Kept in any `--record`ing, so replays see the same keys
+            self.record_input(ReplayEvent::Key(key, kind));
+            self.record_key_event(key, kind);
+            self.handle_key_when_waiting(key, kind);
+        }
+    }
+
+    fn check_replayed_state(&mut self, recorded: u32) {
+        if self.replay_divergence.is_some() {
+            return;
+        }
+        let actual = self.state_checksum();
+        let state = MachineState::new(&self.cpu, &self.framebuffer);
+        if actual == recorded {
+            self.last_matched = Some(state);
+            return;
+        }
+        let divergence = Divergence {
+            frame: self.replayed_frames,
+            cycle: self.cycles,
+            recorded,
+            actual,
+            changes: self
+                .last_matched
+                .as_ref()
+                .map(|matched| matched.diff(&state)),
+        };
+        self.frontend.log(divergence.to_string());
+        self.replay_divergence = Some(divergence);
+    }
+
+    pub fn set_save_slots(&mut self, slots: SaveSlots) {
+        self.save_slots = Some(slots);
+    }
+
+    pub fn set_video_recorder(&mut self, recorder: VideoRecorder) {
+        self.video_recorder = Some(recorder);
+    }
+
+    pub fn take_video_recorder(&mut self) -> Option<VideoRecorder> {
+        self.video_recorder.take()
+    }
+
+    // Sends the current screen and sound state to the video recorder, if recording.
+    // Recording stops if the encoder goes away, rather than interrupting emulation
+    pub fn record_frame(&mut self) {
+        if let Some(ref mut recorder) = self.video_recorder
+            && let Err(e) =
+                recorder.record_frame(&self.framebuffer, self.config.screen_color, self.beeping)
+        {
+            self.frontend.log(format!("Video recording stopped: {e}"));
+            self.video_recorder = None;
+        }
+        self.wrote_to_disk(0);
+    }
+
+    // Counts bytes just written to disk, and stops the machine once everything it has written,
+    // the video recording included, goes past the disk limit
+    fn wrote_to_disk(&mut self, bytes: u64) {
+        self.disk_written += bytes;
+        let video = self
+            .video_recorder
+            .as_ref()
+            .map_or(0, VideoRecorder::disk_usage);
+        if self.limit_exceeded.is_none()
+            && let Err(e) = self.config.limits.check_disk(self.disk_written + video)
+        {
+            self.exceed_limit(e);
+        }
+    }
+
+    // Shows a warning in the log, unless it was already shown
+    pub fn warn_once(&mut self, warning: Warning) {
+        if let Some(message) = self.diagnostics.warn_once(warning) {
+            self.frontend.log(format!("Warning: {message}"));
+        }
+    }
+
+    pub fn record_key_event(&mut self, key: u8, kind: Chip8KeyEventKind) {
+        self.key_history.record(self.cycles, key, kind);
+        self.debug_dirty.key_history = true;
     }
 
     pub fn set_key_state(&mut self, key_state: &Chip8KeyState) {
         self.key_state = *key_state;
     }
 
+    // Feeds a key event to a waiting GetKey instruction. Returns true once the wait is over.
+    // COSMAC waits for a key to be pressed *and* released, sounding a tone while it is held.
+    // Other keys are ignored until the captured key is released. Later interpreters finish on
+    // the press
     pub fn handle_key_when_waiting(&mut self, key: u8, kind: Chip8KeyEventKind) -> bool {
-        if let Some(reg) = self.cpu.stop_waiting_for_key() {
-            let expected_kind = if self.config.version == Chip8Version::Cosmac {
-                Chip8KeyEventKind::Release
-            } else {
-                Chip8KeyEventKind::Press
-            };
-            if kind == expected_kind {
-                self.cpu.register_set(&reg, key);
-                self.cpu.increment_pc(); // Now we can move to the next instruction
+        let Some(wait) = self.cpu.key_wait() else {
+            return false;
+        };
+        if !self.quirks.key_release {
+            if kind == Chip8KeyEventKind::Press {
+                self.finish_key_wait(key);
                 return true;
-            } else {
-                // Wrong event type, continue waiting
-                self.cpu.start_waiting_for_key(reg);
             }
+            return false;
+        }
+        match (wait.captured_key, kind) {
+            (None, Chip8KeyEventKind::Press) => {
+                self.cpu.capture_key(key);
+                self.cpu.set_sound_timer(Self::GET_KEY_TONE_TICKS);
+                false
+            }
+            (Some(captured), Chip8KeyEventKind::Release) if captured == key => {
+                self.finish_key_wait(key);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn finish_key_wait(&mut self, key: u8) {
+        if let Some(reg) = self.cpu.stop_waiting_for_key() {
+            self.cpu.register_set(&reg, key);
+            self.cpu.increment_pc(); // Now we can move to the next instruction
+        }
+    }
+
+    // Ticks the delay and sound timers. While COSMAC GetKey holds a captured key, the tone
+    // keeps sounding. The same 60Hz interrupt ends any display wait
+    pub fn decrement_timers(&mut self) {
+        self.check_time_limit();
+        self.waiting_for_vblank = false;
+        self.timer_history
+            .record(self.cpu.get_delay_timer(), self.cpu.get_sound_timer());
+        self.debug_dirty.timer_history = true;
+        self.cpu.dec_delay();
+        self.cpu.dec_sound();
+        if let Some(wait) = self.cpu.key_wait()
+            && wait.captured_key.is_some()
+        {
+            self.cpu.set_sound_timer(Self::GET_KEY_TONE_TICKS);
         }
-        false
     }
 
     pub fn is_waiting_for_key(&self) -> bool {
         self.cpu.is_waiting_for_key()
     }
 
-    pub fn load_rom(&mut self, bytes: &'a [u8]) -> Result<(), ()> {
+    pub fn is_waiting_for_vblank(&self) -> bool {
+        self.waiting_for_vblank
+    }
+
+    // Instructions executed since the ROM was loaded
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    // Whether the ROM has asked the interpreter to quit, or hit an execution limit
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    pub fn limit_exceeded(&self) -> Option<LimitExceeded> {
+        self.limit_exceeded
+    }
+
+    // Stops the ROM if it has run for too long. Checked on every timer tick, so ROMs waiting
+    // for a key are stopped too
+    fn check_time_limit(&mut self) {
+        if let Some(loaded_at) = self.loaded_at
+            && let Err(e) = self.config.limits.check_time(loaded_at.elapsed())
+        {
+            self.exceed_limit(e);
+        }
+    }
+
+    fn exceed_limit(&mut self, limit: LimitExceeded) {
+        self.frontend.log(limit.to_string());
+        self.record_crash_snapshot();
+        self.limit_exceeded = Some(limit);
+        self.exited = true;
+    }
+
+    // Whether a halt asked for the debugger to pause, since this was last called
+    pub fn take_break_request(&mut self) -> bool {
+        std::mem::take(&mut self.break_requested)
+    }
+
+    // A jump to itself, or a GetKey followed by a jump back to it, never gets anywhere
+    fn is_halt(&self, inst: &Instruction) -> bool {
+        let pc = self.cpu.get_pc();
+        match inst {
+            Instruction::Jump(addr) => addr.get() == pc,
+            Instruction::GetKey(_) => match self.read_memory(pc + 2, 2)[..] {
+                [high, low] => matches!(
+                    crate::decoder::Decoder::decode_for(
+                        &RawInstruction::new(high, low),
+                        &self.config.version
+                    ),
+                    Some(Instruction::Jump(addr)) if addr.get() == pc
+                ),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn handle_halt(&mut self) {
+        let pc = self.cpu.get_pc();
+        if self.halted_at == Some(pc) {
+            return;
+        }
+        self.halted_at = Some(pc);
+        self.frontend.log(format!("ROM halted at {pc:#05X}"));
+        match self.config.on_halt {
+            HaltAction::Run => {}
+            HaltAction::Exit => self.exited = true,
+            HaltAction::Break => self.break_requested = true,
+        }
+    }
+
+    pub fn load_rom(&mut self, bytes: &'a [u8]) -> Result<(), Chip8Error> {
         // Load Fonts into memory
         self.cpu
-            .store_memory_slice(Chip8::FONT_START_ADDR as usize, &Chip8::FONT)
+            .store_memory_slice(self.config.font_addr as usize, &Chip8::FONT)
+            .expect("Fonts should fit into memory");
+        self.cpu
+            .store_memory_slice(self.big_font_addr() as usize, &Chip8::BIG_FONT)
             .expect("Fonts should fit into memory");
         // Load ROM into memory
         self.cpu
-            .store_memory_slice(Chip8::ENTRY_POINT.into(), bytes)?;
+            .store_memory_slice(Chip8::ENTRY_POINT.into(), bytes)
+            .map_err(|_| Chip8Error::RomTooLarge {
+                size: bytes.len(),
+                max: self.cpu.memory_size() - Chip8::ENTRY_POINT as usize,
+            })?;
         self.cpu.jump_to(&Address::new(Chip8::ENTRY_POINT).unwrap());
         self.rom_ref = Some(bytes);
+        // Decode the whole ROM up front, at every address since jumps can land on odd ones
+        let rom_end = (Chip8::ENTRY_POINT as usize + bytes.len()).min(self.cpu.memory_size() - 1);
+        for addr in Chip8::ENTRY_POINT..rom_end as u16 {
+            let inst = self.decode_at(addr);
+            self.cpu.cache_decoded_instruction(addr, inst);
+        }
+        self.loaded_at = Some(Instant::now());
         Ok(())
     }
 
+    // Applies settings that were changed while running. The clock and input are told separately
+    pub fn apply_settings(&mut self, changes: &Settings) {
+        if let Some(color) = changes.color {
+            self.config.screen_color = color;
+            self.frontend.push(FrontendEvent::Color(color));
+        }
+        if let Some(hz) = changes.cpu_hz {
+            self.config.cpu_hz = hz;
+        }
+        if let Some(show_diff) = changes.show_diff {
+            self.set_show_diff(show_diff);
+        }
+        let message = format!("Settings reloaded: {}", changes.describe());
+        self.frontend.log(message.clone());
+        self.show_message(message, Self::SETTINGS_MESSAGE_DURATION);
+    }
+
+    // Opens the settings page, which saves changes to the settings file at `path`
+    pub fn open_settings(&mut self, path: PathBuf, layout: KeyboardLayout) {
+        let current = Settings {
+            color: Some(self.config.screen_color),
+            layout: Some(layout),
+            cpu_hz: Some(self.config.cpu_hz),
+            show_diff: Some(self.show_diff),
+            keys: None,
+        };
+        let menu = SettingsMenu::new(path, current);
+        self.set_settings_menu(Some(menu.lines()));
+        self.settings_menu = Some(menu);
+    }
+
+    pub fn close_settings(&mut self) {
+        self.settings_menu = None;
+        self.set_settings_menu(None);
+    }
+
+    pub fn handle_settings_key(&mut self, key: MenuKey) {
+        let Some(ref mut menu) = self.settings_menu else {
+            return;
+        };
+        let saved = match key {
+            MenuKey::Up | MenuKey::Down => {
+                menu.select(if key == MenuKey::Up { -1 } else { 1 });
+                Ok(())
+            }
+            MenuKey::Left => menu.change(-1),
+            MenuKey::Right => menu.change(1),
+            MenuKey::Close => Ok(()),
+        };
+        let lines = menu.lines();
+        self.set_settings_menu(Some(lines));
+        if let Err(e) = saved {
+            self.frontend
+                .log(format!("Could not save the settings: {e}"));
+        }
+    }
+
+    // The settings file was edited, but couldn't be read
+    pub fn reject_settings(&mut self, error: String) {
+        self.frontend.log(format!("Settings not reloaded: {error}"));
+        self.show_message(
+            "Settings not reloaded, see the log (Tab)".to_string(),
+            Self::SETTINGS_MESSAGE_DURATION,
+        );
+    }
+
     pub fn restart_rom(&mut self) {
+        self.frontend.log("Restarted the ROM".to_string());
+        // Macros count from the cycle they started at, which is gone
+        if self.macro_recording.take().is_some() || self.macro_playback.take().is_some() {
+            self.frontend
+                .log("Stopped the macro, as the ROM restarted".to_string());
+        }
+        self.stop_input_capture("the ROM restarted");
         self.cpu.reset();
-        self.screen.clear();
-        self.screen.flush().unwrap();
+        self.cycles = 0;
+        self.key_history = KeyHistory::default();
+        self.debug_dirty.key_history = true;
+        self.recent_pcs.clear();
+        self.halted_at = None;
+        self.vip_tone = false;
+        self.silent_sound = false;
+        self.pitch = Self::DEFAULT_PITCH;
+        self.set_audio_pattern(None);
+        self.framebuffer = FrameBuffer::new();
         if let Some(rom_ref) = self.rom_ref {
             let _ = self.load_rom(rom_ref);
         }
     }
 
-    pub async fn execute_instruction(&mut self, inst: &Instruction) {
+    // Shows or hides what each frame changed
+    pub fn set_show_diff(&mut self, show_diff: bool) {
+        self.show_diff = show_diff;
+        self.frontend.push(FrontendEvent::ShowDiff(show_diff));
+    }
+
+    pub fn shows_diff(&self) -> bool {
+        self.show_diff
+    }
+
+    // Called as the frontend draws a frame, to work out what the last frame changed if the diff
+    // is shown. The diff is kept until a frame changes the screen again, so it doesn't vanish
+    // between draws
+    pub fn flush_screen(&mut self) {
+        if self.show_diff {
+            let state = MachineState::new(&self.cpu, &self.framebuffer);
+            if let Some(ref last) = self.last_flushed
+                && last.framebuffer != state.framebuffer
+            {
+                self.frontend
+                    .push(FrontendEvent::FrameDiff(last.diff(&state)));
+            }
+            self.last_flushed = Some(state);
+        } else {
+            self.last_flushed = None;
+        }
+    }
+
+    // Writes the autosave slot every `interval` while the ROM runs
+    pub fn set_autosave_interval(&mut self, interval: Duration) {
+        self.autosave_interval = Some(interval);
+        self.last_autosave = Instant::now();
+    }
+
+    pub fn autosave_if_due(&mut self) {
+        if self
+            .autosave_interval
+            .is_some_and(|interval| self.last_autosave.elapsed() >= interval)
+            && self.playback_state == PlaybackMode::Running
+        {
+            self.autosave();
+        }
+    }
+
+    // Writes the autosave slot now, if autosaving is on. Failures turn it off
+    pub fn autosave(&mut self) {
+        // A machine that crashed would be resumed straight into the crash
+        if self.autosave_interval.is_none()
+            || self.faulted.is_some()
+            || self.limit_exceeded.is_some()
+            || self.halted_on_invalid
+        {
+            return;
+        }
+        let Some(ref slots) = self.save_slots else {
+            return;
+        };
+        self.last_autosave = Instant::now();
+        match slots.autosave(&self.cpu, &self.framebuffer) {
+            Ok(bytes) => self.wrote_to_disk(bytes),
+            Err(e) => {
+                self.frontend.log(format!("Autosave stopped: {e}"));
+                self.autosave_interval = None;
+            }
+        }
+    }
+
+    // Continues from the autosave slot, returning whether there was one to continue from
+    pub fn resume_autosave(&mut self) -> bool {
+        let Some(ref slots) = self.save_slots else {
+            return false;
+        };
+        match slots.load_autosave() {
+            Ok(Some(state)) if state.cpu.memory_size() == self.cpu.memory_size() => {
+                let message = format!(
+                    "Continued from the autosave of {}, restart to start over",
+                    state.saved_at_text()
+                );
+                self.restore_state(state);
+                self.show_message(message, Self::RESUME_MESSAGE_DURATION);
+                true
+            }
+            Ok(_) => false,
+            Err(e) => {
+                self.frontend
+                    .log(format!("Could not continue from the autosave: {e}"));
+                false
+            }
+        }
+    }
+
+    pub fn save_state(&mut self) {
+        let Some(ref slots) = self.save_slots else {
+            return;
+        };
+        let (message, written) = match slots.save(&self.cpu, &self.framebuffer) {
+            Ok(bytes) => (format!("State saved to slot {}", slots.slot()), bytes),
+            Err(e) => (format!("State not saved: {e}"), 0),
+        };
+        self.show_notice(message);
+        self.wrote_to_disk(written);
+    }
+
+    // Puts the machine back how it was when the state was saved
+    pub fn load_state(&mut self) {
+        let Some(ref slots) = self.save_slots else {
+            return;
+        };
+        let slot = slots.slot();
+        let message = match slots.load() {
+            Ok(Some(state)) if state.cpu.memory_size() != self.cpu.memory_size() => {
+                "State not loaded: it was saved for a different CHIP-8 version".to_string()
+            }
+            Ok(Some(state)) => {
+                self.restore_state(state);
+                format!("State loaded from slot {slot}")
+            }
+            Ok(None) => format!("Slot {slot} is empty, press F5 to save to it"),
+            Err(e) => format!("State not loaded: {e}"),
+        };
+        self.show_notice(message);
+    }
+
+    fn restore_state(&mut self, state: SaveState) {
+        self.stop_input_capture("the machine jumped to a saved state");
+        self.cpu = state.cpu;
+        self.framebuffer = state.framebuffer;
+        self.halted_at = None;
+        self.vip_tone = false;
+        self.silent_sound = false;
+        self.waiting_for_vblank = false;
+    }
+
+    // Keeps the frame for rewinding, unless the machine is paused and nothing changed
+    pub fn record_rewind_snapshot(&mut self) {
+        if self.playback_state == PlaybackMode::Running {
+            self.rewind.record(&self.cpu, &self.framebuffer);
+        }
+    }
+
+    // Steps the machine back in time by a few frames
+    pub fn rewind(&mut self) {
+        let message = match self.rewind.rewind(Self::REWIND_STEP_FRAMES) {
+            Some(state) => {
+                self.restore_state(state);
+                let seconds = self.rewind.len() as f64 / RewindBuffer::FRAMES_PER_SECOND as f64;
+                format!("Rewinding, {seconds:.1}s left")
+            }
+            None => "Nothing left to rewind".to_string(),
+        };
+        self.show_message(message, Self::NOTICE_DURATION);
+    }
+
+    // Runs the machine at full tick rate, but without waiting in real time, until the delay
+    // timer runs out. Lets the debugger get past the loops that spin on FX07. Stops early at a
+    // key wait or a halt, and returns the number of frames skipped
+    pub fn skip_delay_wait(&mut self) -> Result<u32, Chip8Error> {
+        if self.is_replaying() {
+            return Ok(0);
+        }
+        let per_frame = ((self.config.cpu_hz / Chip8::TIMER_HZ) as u32).max(1);
+        let mut frames = 0;
+        while self.cpu.get_delay_timer() > 0 && frames < Self::MAX_SKIPPED_FRAMES {
+            for _ in 0..per_frame {
+                if self.is_waiting_for_key() || self.is_waiting_for_vblank() {
+                    break;
+                }
+                let inst = self.current_instruction();
+                self.execute_instruction(&inst)?;
+                if self.break_requested || self.exited {
+                    break;
+                }
+            }
+            if self.is_waiting_for_key() || self.break_requested || self.exited {
+                break;
+            }
+            self.live_timer_tick();
+            frames += 1;
+        }
+        let message = match self.cpu.get_delay_timer() {
+            0 if frames == 0 => "The delay timer is already 0".to_string(),
+            0 => format!("Skipped {frames} frames, the delay timer ran out"),
+            delay => format!("Skipped {frames} frames, stopped with the delay timer at {delay}"),
+        };
+        self.show_notice(message);
+        Ok(frames)
+    }
+
+    // Chooses the slot F5 and F9 use, and shows what's in it
+    pub fn select_save_slot(&mut self, delta: i8) {
+        let Some(ref mut slots) = self.save_slots else {
+            return;
+        };
+        slots.select(delta);
+        let message = slots.describe();
+        self.show_message(message, Self::NOTICE_DURATION);
+    }
+
+    // Logs a short notice and shows it over the screen, for saves, macros and the like
+    fn show_notice(&mut self, message: String) {
+        self.frontend.log(message.clone());
+        self.show_message(message, Self::NOTICE_DURATION);
+    }
+
+    fn set_settings_menu(&mut self, lines: Option<Vec<String>>) {
+        self.frontend.push(FrontendEvent::SettingsMenu(lines));
+    }
+
+    fn set_error_banner(&mut self, banner: Option<String>) {
+        self.frontend.push(FrontendEvent::ErrorBanner(banner));
+    }
+
+    fn show_message(&mut self, message: String, duration: Duration) {
+        self.frontend.push(FrontendEvent::Notice(message, duration));
+    }
+
+    // The instruction at the PC, as the configured version understands it
+    pub fn current_instruction(&mut self) -> Instruction {
+        let pc = self.cpu.get_pc();
+        if let Some(inst) = self.cpu.decoded_instruction(pc) {
+            return inst.clone();
+        }
+        let inst = self.decode_at(pc);
+        self.cpu.cache_decoded_instruction(pc, inst.clone());
+        inst
+    }
+
+    // Runs instructions from the PC to the end of its basic block, but no more than `max`,
+    // returning how many ran. Blocks end with anything that jumps, skips, waits, or writes to
+    // memory, so nothing between their instructions needs checking
+    pub fn execute_block(&mut self, max: usize) -> Result<usize, Chip8Error> {
+        if self.cpu.is_self_modifying() {
+            let inst = self.current_instruction();
+            self.execute_instruction(&inst)?;
+            return Ok(1);
+        }
+        let pc = self.cpu.get_pc();
+        let block = match self.cpu.basic_block(pc) {
+            Some(block) => block,
+            None => {
+                let block = self.decode_block(pc);
+                self.cpu.cache_basic_block(pc, block.clone());
+                block
+            }
+        };
+        let mut ran = 0;
+        for inst in block.iter().take(max) {
+            self.execute_instruction(inst)?;
+            ran += 1;
+            if self.exited {
+                break;
+            }
+        }
+        Ok(ran)
+    }
+
+    fn decode_block(&mut self, start: u16) -> BasicBlock {
+        let mut block = Vec::new();
+        let mut addr = start as usize;
+        while addr + 1 < self.cpu.memory_size() && block.len() < Self::MAX_BLOCK_LEN {
+            let inst = match self.cpu.decoded_instruction(addr as u16) {
+                Some(inst) => inst.clone(),
+                None => self.decode_at(addr as u16),
+            };
+            let ends_block = !Self::is_straight_line(&inst);
+            block.push(inst);
+            if ends_block {
+                break;
+            }
+            addr += CPU::INSTRUCTION_SIZE_B as usize;
+        }
+        block.into()
+    }
+
+    // Instructions that always move on to the next one, without waiting or writing to memory
+    fn is_straight_line(inst: &Instruction) -> bool {
+        use Instruction::*;
+        matches!(
+            inst,
+            ClearScreen
+                | SetFont(_)
+                | RegOp(..)
+                | SetRegImmediate(..)
+                | AddRegImmediate(..)
+                | Random(..)
+                | LoadAddr(_)
+                | SetSoundTimer(_)
+                | SetDelayTimer(_)
+                | GetDelayTimer(_)
+                | SetIndex(_)
+                | AddIndex(_)
+                | LowRes
+                | HighRes
+                | SetBigFont(_)
+                | LoadFlags(_)
+                | LoadRange(..)
+                | SelectPlanes(_)
+                | ScrollUp(_)
+                | ScrollDown(_)
+                | ScrollRight
+                | ScrollLeft
+                | LoadAudioPattern
+                | SetPitch(_)
+        )
+    }
+
+    fn decode_at(&self, addr: u16) -> Instruction {
+        let (Ok(high), Ok(low)) = (
+            self.cpu.load_from_addr(addr),
+            self.cpu.load_from_addr(addr + 1),
+        ) else {
+            return Instruction::Invalid;
+        };
+        let raw = RawInstruction::new(high, low);
+        crate::decoder::Decoder::decode_for(&raw, &self.config.version)
+            .unwrap_or(Instruction::Invalid)
+    }
+
+    pub fn execute_instruction(&mut self, inst: &Instruction) -> Result<(), Chip8Error> {
         use Instruction::*;
 
+        self.cycles += 1;
+        if self.recent_pcs.len() == Self::RECENT_INSTRUCTIONS {
+            self.recent_pcs.pop_front();
+        }
+        self.recent_pcs.push_back(self.cpu.get_pc());
+        if let Err(e) = self.config.limits.check_cycles(self.cycles) {
+            self.exceed_limit(e);
+            return Ok(());
+        }
+        if self.config.pc_guard {
+            self.check_pc()?;
+        }
+        if !self.quirks.memory_wrap
+            && let Some(len) = self.index_access_len(inst)
+            && self.cpu.get_index() as usize + len > self.cpu.memory_size()
+        {
+            return Err(Chip8Error::MemoryOutOfBounds {
+                pc: self.cpu.get_pc(),
+                addr: self.cpu.get_index() as usize + len,
+            });
+        }
+        if let Some(protection) = self.config.memory_protection {
+            self.check_protected_write(inst, protection)?;
+        }
+        if self.halted_on_invalid && !matches!(inst, Invalid | Unsupported(_)) {
+            // Restarted, or a state was loaded
+            self.halted_on_invalid = false;
+            self.set_error_banner(None);
+        }
+        if self.is_halt(inst) {
+            self.handle_halt();
+            if self.exited {
+                return Ok(());
+            }
+        }
+
         match inst {
-            ClearScreen => self.screen.clear(),
+            ClearScreen => self.framebuffer.clear(),
             Jump(addr) => {
                 self.cpu.jump_to(addr);
-                return;
+                return Ok(());
             }
             RegOp(reg_op, regx, regy) => self.execute_reg_op(reg_op, regx, regy),
             SetRegImmediate(reg, value) => self.cpu.register_set(reg, value.get()),
@@ -103,87 +1294,218 @@ impl<'a> Hardware<'a> {
             SetIndex(addr) => self.cpu.set_index(addr.get()),
             AddIndex(reg) => {
                 let reg_val = self.cpu.register_val(reg) as u16;
-                self.cpu.add_index(reg_val);
+                let overflowed = self.cpu.add_index(reg_val);
+                if self.quirks.index_overflow {
+                    *self.cpu.vf() = overflowed as u8;
+                }
             }
             Draw(regx, regy, row_count) => {
-                self.execute_draw(regx, regy, row_count);
+                self.execute_draw(regx, regy, row_count)?;
+                self.waiting_for_vblank = self.quirks.display_wait;
             }
             LoadAddr(reg) => {
-                if self.config.version == Chip8Version::Cosmac {
-                    self.cpu.load_registers_cosmac(reg);
+                let wrap = self.quirks.memory_wrap;
+                if self.quirks.index_increment {
+                    self.cpu.load_registers_cosmac(reg, wrap)?;
                 } else {
-                    self.cpu.load_registers(reg);
+                    self.cpu.load_registers(reg, wrap)?;
                 }
             }
             StoreAddr(reg) => {
-                if self.config.version == Chip8Version::Cosmac {
-                    self.cpu.store_registers_cosmac(reg);
+                let wrap = self.quirks.memory_wrap;
+                if self.quirks.index_increment {
+                    self.cpu.store_registers_cosmac(reg, wrap)?;
                 } else {
-                    self.cpu.store_registers(reg);
+                    self.cpu.store_registers(reg, wrap)?;
                 }
             }
             SetFont(reg) => {
-                let font_addr = Chip8::FONT_START_ADDR
+                let font_addr = self.config.font_addr
                     + ((self.cpu.register_val(reg) & 0x0F) as u16 * Chip8::BYTES_PER_FONT);
                 self.cpu.set_index(font_addr);
             }
             JumpWithOffset(addr) => {
-                let addr_to_jump = if self.config.version == Chip8Version::Cosmac {
-                    addr.get() + self.cpu.register_val(&Register::new(0).unwrap()) as u16
-                } else {
+                let addr_to_jump = if self.quirks.jump_vx {
                     // Strange quirk in newer interpreters where the addr was interpreted as XNN
                     let reg_index = ((addr.get() >> 8) & 0xF) as u8;
                     addr.get() + self.cpu.register_val(&Register::new(reg_index).unwrap()) as u16
+                } else {
+                    addr.get() + self.cpu.register_val(&Register::new(0).unwrap()) as u16
                 };
-                let jump_addr = Address::new(addr_to_jump).unwrap();
-                self.cpu.jump_to(&jump_addr);
-                return;
+                self.cpu.set_pc(addr_to_jump);
+                return Ok(());
             }
             CallSubroutine(addr) => {
                 self.cpu.push_stack(self.cpu.get_pc());
                 self.cpu.jump_to(addr);
-                return;
+                return Ok(());
             }
             Return => {
-                let return_addr = self.cpu.pop_stack().expect("CRITICAL: Stack is empty");
-                let addr = Address::new(return_addr).unwrap();
-                self.cpu.jump_to(&addr);
+                let Some(return_addr) = self.cpu.pop_stack() else {
+                    return Err(Chip8Error::StackUnderflow {
+                        pc: self.cpu.get_pc(),
+                    });
+                };
+                self.cpu.set_pc(return_addr);
             }
             Skip(skipif, reg, value) => {
                 let eq = self.cpu.register_val(reg) == value.get();
                 if (*skipif == SkipIf::Eq && eq) || (*skipif == SkipIf::NotEq && !eq) {
-                    self.cpu.increment_pc();
+                    self.skip_next_instruction();
                 }
             }
             SkipReg(skipif, regx, regy) => {
                 let eq = self.cpu.register_val(regx) == self.cpu.register_val(regy);
                 if (*skipif == SkipIf::Eq && eq) || (*skipif == SkipIf::NotEq && !eq) {
-                    self.cpu.increment_pc();
+                    self.skip_next_instruction();
                 }
             }
             SkipKeyPress(skipif, reg) => {
                 let pressed = self.key_state.is_key_pressed(self.cpu.register_val(reg));
                 if (*skipif == SkipIf::Eq && pressed) || (*skipif == SkipIf::NotEq && !pressed) {
-                    self.cpu.increment_pc();
+                    self.skip_next_instruction();
                 }
             }
             GetKey(reg) => {
                 // Set CPU to waiting state and don't increment PC
                 self.cpu.start_waiting_for_key(*reg);
-                return;
+                return Ok(());
             }
             Random(reg, value) => {
-                let random: u8 = rand::random();
+                let random: u8 = self.rng.random();
                 self.cpu.register_set(reg, value.get() & random);
             }
-            SetSoundTimer(reg) => self.cpu.set_sound_timer(self.cpu.register_val(reg)),
+            SetSoundTimer(reg) => {
+                let value = self.cpu.register_val(reg);
+                self.cpu.set_sound_timer(value);
+                self.silent_sound =
+                    self.quirks.sound_threshold && value < Self::MIN_AUDIBLE_SOUND_TIMER;
+            }
             SetDelayTimer(reg) => self.cpu.set_delay_timer(self.cpu.register_val(reg)),
             GetDelayTimer(reg) => self.cpu.register_set(reg, self.cpu.get_delay_timer()),
-            BinaryDecimalConv(reg) => self.cpu.binary_decimal_conv(reg),
-            Invalid => panic!("Invalid instruction encountered"),
-            ExecuteMachineLangRoutine => {}
+            BinaryDecimalConv(reg) => self.cpu.binary_decimal_conv(reg, self.quirks.memory_wrap)?,
+            Exit => {
+                self.exited = true;
+                return Ok(());
+            }
+            LowRes => self.framebuffer.set_hires(false),
+            HighRes => self.framebuffer.set_hires(true),
+            // SUPER-CHIP only has flags for V0 to V7
+            StoreFlags(reg) => {
+                let count = (reg.get() as usize + 1).min(RplFlags::COUNT);
+                let values = self.cpu.all_register_val();
+                match self.rpl_flags.store(&values[..count]) {
+                    Ok(bytes) => self.wrote_to_disk(bytes),
+                    Err(_) => self.warn_once(Warning::RplFlagsNotSaved),
+                }
+            }
+            LoadFlags(reg) => {
+                let count = (reg.get() as usize + 1).min(RplFlags::COUNT);
+                for (i, value) in self.rpl_flags.values().into_iter().enumerate().take(count) {
+                    self.cpu
+                        .register_set(&Register::new(i as u8).unwrap(), value);
+                }
+            }
+            SaveRange(regx, regy) => {
+                let index = self.cpu.get_index() as usize;
+                let wrap = self.quirks.memory_wrap;
+                for (offset, reg) in register_range(regx, regy).iter().enumerate() {
+                    let value = self.cpu.register_val(reg);
+                    self.cpu
+                        .resolve_addr(index + offset, wrap)
+                        .and_then(|addr| self.cpu.store_in_addr(addr, value))?;
+                }
+            }
+            LoadRange(regx, regy) => {
+                let index = self.cpu.get_index() as usize;
+                let wrap = self.quirks.memory_wrap;
+                for (offset, reg) in register_range(regx, regy).iter().enumerate() {
+                    let value = self
+                        .cpu
+                        .resolve_addr(index + offset, wrap)
+                        .and_then(|addr| self.cpu.load_from_addr(addr))?;
+                    self.cpu.register_set(reg, value);
+                }
+            }
+            LoadLongIndex => {
+                let addr = self.cpu.get_pc() as usize + CPU::INSTRUCTION_SIZE_B as usize;
+                if addr + 2 > self.cpu.memory_size() {
+                    return Err(Chip8Error::MemoryOutOfBounds {
+                        pc: self.cpu.get_pc(),
+                        addr: self.cpu.memory_size(),
+                    });
+                }
+                let high = self.cpu.load_from_addr(addr as u16)?;
+                let low = self.cpu.load_from_addr(addr as u16 + 1)?;
+                self.cpu.set_index(u16::from_be_bytes([high, low]));
+                self.cpu.increment_pc(); // Over the address
+            }
+            SelectPlanes(planes) => self.framebuffer.select_planes(planes.get()),
+            ScrollUp(rows) => self.framebuffer.scroll_up(rows.get()),
+            ScrollDown(rows) => self.framebuffer.scroll_down(rows.get()),
+            ScrollRight => self.framebuffer.scroll_horizontally(Self::SCROLL_COLUMNS),
+            ScrollLeft => self.framebuffer.scroll_horizontally(-Self::SCROLL_COLUMNS),
+            LoadAudioPattern => {
+                let index = self.cpu.get_index() as usize;
+                let wrap = self.quirks.memory_wrap;
+                let mut samples = [0; 16];
+                for (offset, sample) in samples.iter_mut().enumerate() {
+                    *sample = self
+                        .cpu
+                        .resolve_addr(index + offset, wrap)
+                        .and_then(|addr| self.cpu.load_from_addr(addr))?;
+                }
+                let pitch = self.pitch;
+                self.set_audio_pattern(Some(AudioPattern { samples, pitch }));
+            }
+            // Sets the pitch of the buffer loaded before it, and of those loaded after
+            SetPitch(reg) => {
+                self.pitch = self.cpu.register_val(reg);
+                let pattern = self.audio_pattern.map(|pattern| AudioPattern {
+                    pitch: self.pitch,
+                    ..pattern
+                });
+                self.set_audio_pattern(pattern);
+            }
+            SetBigFont(reg) => {
+                let font_addr = self.big_font_addr()
+                    + ((self.cpu.register_val(reg) & 0x0F) as u16 * Chip8::BYTES_PER_BIG_FONT);
+                self.cpu.set_index(font_addr);
+            }
+            Unsupported(version) => {
+                return self.invalid_instruction(Chip8Error::UnsupportedInstruction {
+                    pc: self.cpu.get_pc(),
+                    opcode: self.cpu.fetch_current_instruction().word(),
+                    version: version.clone(),
+                });
+            }
+            Invalid => {
+                return self.invalid_instruction(Chip8Error::InvalidInstruction {
+                    pc: self.cpu.get_pc(),
+                    opcode: self.cpu.fetch_current_instruction().word(),
+                });
+            }
+            ExecuteMachineLangRoutine(addr) => self.execute_machine_routine(addr),
         };
         self.cpu.increment_pc();
+        Ok(())
+    }
+
+    // Moves past the next instruction, which is twice as long if it's XO-CHIP's F000 NNNN
+    fn skip_next_instruction(&mut self) {
+        self.cpu.increment_pc();
+        if self.config.version == Chip8Version::XoChip
+            && self.cpu.fetch_current_instruction().word() == 0xF000
+        {
+            self.cpu.increment_pc();
+        }
+    }
+
+    // The COSMAC VIP's logic ops went through VF, leaving it 0
+    fn reset_vf_after_logic(&mut self) {
+        if self.quirks.vf_reset {
+            *self.cpu.vf() = 0;
+        }
     }
 
     fn execute_reg_op(&mut self, reg_op: &RegOperation, regx: &Register, regy: &Register) {
@@ -195,12 +1517,15 @@ impl<'a> Hardware<'a> {
             }
             RegOperation::Or => {
                 self.cpu.register_set(regx, vx | vy);
+                self.reset_vf_after_logic();
             }
             RegOperation::Xor => {
                 self.cpu.register_set(regx, vx ^ vy);
+                self.reset_vf_after_logic();
             }
             RegOperation::And => {
                 self.cpu.register_set(regx, vx & vy);
+                self.reset_vf_after_logic();
             }
             RegOperation::Add => {
                 let (result, overflow) = vx.overflowing_add(vy);
@@ -218,7 +1543,7 @@ impl<'a> Hardware<'a> {
                 *self.cpu.vf() = if vy > vx { 1 } else { 0 };
             }
             RegOperation::ShiftLeft => {
-                let val = if self.config.version == Chip8Version::Cosmac {
+                let val = if self.quirks.shift_vy {
                     self.cpu.register_set(regx, vy);
                     vy
                 } else {
@@ -228,7 +1553,7 @@ impl<'a> Hardware<'a> {
                 self.cpu.register_set(regx, val << 1);
             }
             RegOperation::ShiftRight => {
-                let val = if self.config.version == Chip8Version::Cosmac {
+                let val = if self.quirks.shift_vy {
                     self.cpu.register_set(regx, vy);
                     vy
                 } else {
@@ -240,61 +1565,394 @@ impl<'a> Hardware<'a> {
         }
     }
 
+    // Runs a 0NNN routine if it's a known VIP one, otherwise it is ignored
+    fn execute_machine_routine(&mut self, addr: &Address) {
+        let routine = if self.config.vip_routines {
+            VipRoutine::recognize(&self.read_memory(addr.get(), VipRoutine::MAX_LEN))
+        } else {
+            None
+        };
+        match routine {
+            Some(VipRoutine::DisplayOn) => self.framebuffer.display_on = true,
+            Some(VipRoutine::DisplayOff) => self.framebuffer.display_on = false,
+            Some(VipRoutine::ToneOn) => self.vip_tone = true,
+            Some(VipRoutine::ToneOff) => self.vip_tone = false,
+            None => self.warn_once(Warning::MachineRoutine),
+        }
+    }
+
+    // How many bytes from the index register an instruction reads or writes, checked up front
+    // so a fault leaves the machine as it was. FX33, FX55 and FX65 are left to the CPU, and
+    // nothing is checked with the `memory_wrap` quirk, which wraps instead
+    fn index_access_len(&self, inst: &Instruction) -> Option<usize> {
+        use Instruction::*;
+        match inst {
+            Draw(_, _, rows) => {
+                let sprite_len = if rows.get() == 0 && self.config.version.has_big_sprites() {
+                    32
+                } else {
+                    rows.get() as usize
+                };
+                // One sprite for each selected XO-CHIP plane
+                Some(sprite_len * self.framebuffer.selected_planes().count_ones() as usize)
+            }
+            SaveRange(regx, regy) | LoadRange(regx, regy) => {
+                Some(regx.get().abs_diff(regy.get()) as usize + 1)
+            }
+            _ => None,
+        }
+    }
+
+    fn big_font_addr(&self) -> u16 {
+        self.config.font_addr + Chip8::FONT.len() as u16
+    }
+
+    fn invalid_instruction(&mut self, error: Chip8Error) -> Result<(), Chip8Error> {
+        match self.config.on_invalid {
+            InvalidAction::Panic => return Err(error),
+            InvalidAction::Skip => {
+                // Once per instruction, as one in a loop would fill the log
+                if self.invalid_skipped.insert(self.cpu.get_pc()) {
+                    self.frontend.log(format!("Skipped: {error}"));
+                }
+                self.cpu.increment_pc();
+            }
+            // The PC stays put, so it's run again every cycle until the ROM is restarted
+            InvalidAction::Halt if !self.halted_on_invalid => {
+                self.frontend.log(format!("Halted: {error}"));
+                self.set_error_banner(Some(format!("Halted: {error}")));
+                self.halted_on_invalid = true;
+                self.record_crash_snapshot();
+            }
+            InvalidAction::Halt => {}
+        }
+        Ok(())
+    }
+
+    // How many bytes the instruction writes at I
+    fn index_write_len(&self, inst: &Instruction) -> Option<usize> {
+        use Instruction::*;
+        match inst {
+            StoreAddr(reg) => Some(reg.get() as usize + 1),
+            SaveRange(regx, regy) => Some(regx.get().abs_diff(regy.get()) as usize + 1),
+            BinaryDecimalConv(_) => Some(3),
+            _ => None,
+        }
+    }
+
+    fn check_protected_write(
+        &mut self,
+        inst: &Instruction,
+        protection: MemoryProtection,
+    ) -> Result<(), Chip8Error> {
+        let Some(len) = self.index_write_len(inst) else {
+            return Ok(());
+        };
+        let index = self.cpu.get_index();
+        if index >= Chip8::ENTRY_POINT {
+            return Ok(());
+        }
+        let font = self.config.font_addr..self.config.font_addr + Chip8::FONT.len() as u16;
+        let written = index..index.saturating_add(len as u16);
+        let reason = if written.start < font.end && font.start < written.end {
+            format!("Wrote to the font ({index:#05X})")
+        } else {
+            format!("Wrote to the interpreter's memory below 0x200 ({index:#05X})")
+        };
+        let pc = self.cpu.get_pc();
+        match protection {
+            MemoryProtection::Fault => return Err(Chip8Error::Guard { pc, reason }),
+            MemoryProtection::Log => {
+                // Once per instruction, as one in a loop would fill the log
+                if self.protected_writes_logged.insert(pc) {
+                    self.frontend.log(format!("{reason} at {pc:#05X}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // With the PC guard, instructions are only run from even addresses reached without running
+    // off the end of memory, as anything else is almost always a bad jump fetching data
+    fn check_pc(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.cpu.get_pc();
+        let reason = if self.cpu.take_pc_wrapped() {
+            format!(
+                "PC ran past the end of memory ({:#05X})",
+                self.cpu.memory_size() - 1
+            )
+        } else if pc % 2 == 1 {
+            "PC is odd, after a jump to a misaligned address".to_string()
+        } else {
+            return Ok(());
+        };
+        Err(Chip8Error::Guard { pc, reason })
+    }
+
+    // Stops the ROM after a fault, which `take_fault()` then returns. The fault leaves the
+    // machine as it was, so the fault report has it along with the subroutine calls that led
+    // to it
+    pub fn stop_with_fault(&mut self, error: Chip8Error) {
+        self.record_crash_snapshot();
+        self.frontend.log(error.to_string());
+        self.faulted = Some(error);
+        self.exited = true;
+    }
+
+    pub fn take_fault(&mut self) -> Option<Chip8Error> {
+        self.faulted.take()
+    }
+
+    // The registers, call stack, recent keys and display, for crash reports
+    pub fn machine_snapshot(&self) -> String {
+        let registers: Vec<String> = self
+            .cpu
+            .all_register_val()
+            .iter()
+            .map(|value| format!("{value:02X}"))
+            .collect();
+        format!(
+            "PC: {:#05X} | I: {:#05X} | DT: {} | ST: {} | cycles: {}\nV0-F: [{}]\nCall stack:\n{}\nRecent instructions:\n{}\nRecent keys: {}\nDisplay:\n{}",
+            self.cpu.get_pc(),
+            self.cpu.get_index(),
+            self.cpu.get_delay_timer(),
+            self.cpu.get_sound_timer(),
+            self.cycles,
+            registers.join(","),
+            self.format_call_stack(),
+            self.format_recent_instructions(),
+            self.key_history,
+            self.framebuffer.to_text()
+        )
+    }
+
+    // The last instructions run, oldest first, as they read in memory now
+    fn format_recent_instructions(&self) -> String {
+        if self.recent_pcs.is_empty() {
+            return "  None".to_string();
+        }
+        self.recent_pcs
+            .iter()
+            .map(|&pc| match self.read_memory(pc, 2)[..] {
+                [high, low] => format!(
+                    "  {pc:#05X}: {} {}",
+                    RawInstruction::new(high, low),
+                    self.decode_at(pc)
+                ),
+                _ => format!("  {pc:#05X}: past the end of memory"),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // The current PC, then each call site back to the outermost, labelled from the annotations
+    fn format_call_stack(&self) -> String {
+        let describe = |addr: u16| match self.annotations.label_at(addr) {
+            Some(label) => format!("{addr:#05X} [{label}]"),
+            None => format!("{addr:#05X}"),
+        };
+        std::iter::once(format!("  in {}", describe(self.cpu.get_pc())))
+            .chain(
+                self.cpu
+                    .call_stack()
+                    .iter()
+                    .rev()
+                    .map(|&addr| format!("  called from {}", describe(addr))),
+            )
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     // Draws sprite N pixels tall located at the index register
     // at the coordinate x, y in the regX and regY registers respectively
     // All the pixels that are "on" in the sprite will flip the screen.
     // If a pixel is turned off this way, the VF register is set to 1. Otherwise, it's set
     // to 0
-    // The starting coordinate wraps, but the drawing is clipped
-    fn execute_draw(&mut self, regx: &Register, regy: &Register, row_count: &Immediate4) {
-        let start_x = self.cpu.register_val(regx) % Screen::N_COLS;
-        let start_y = self.cpu.register_val(regy) % Screen::N_ROWS;
-        *self.cpu.vf() = 0;
-        let index_addr = self.cpu.get_index();
-
-        for row in 0..row_count.get() {
-            let y = start_y + row;
-            if y >= Screen::N_ROWS {
-                break;
+    // On SUPER-CHIP in high resolution, N = 0 draws a 16x16 sprite (two bytes per row) instead,
+    // and with the collision rows quirk VF is set to the number of rows that had a collision. In
+    // low resolution it draws 8x16, as SUPER-CHIP 1.1 did, while XO-CHIP draws 16x16 in both
+    // XO-CHIP draws to each selected plane, with the sprite for the second plane following the
+    // first's in memory
+    fn execute_draw(
+        &mut self,
+        regx: &Register,
+        regy: &Register,
+        row_count: &Immediate4,
+    ) -> Result<(), Chip8Error> {
+        let start_x = self.cpu.register_val(regx) % self.framebuffer.width();
+        let start_y = self.cpu.register_val(regy) % self.framebuffer.height();
+        let hires = self.framebuffer.is_hires();
+        let version = &self.config.version;
+        let (sprite_width, sprite_height) = if row_count.get() != 0 || !version.has_big_sprites() {
+            (8, row_count.get())
+        } else if *version == Chip8Version::Superchip && !hires {
+            (8, 16)
+        } else {
+            (16, 16)
+        };
+        let sprite_len = sprite_height as u16 * (sprite_width / 8) as u16;
+
+        let mut sprite_addr = self.cpu.get_index();
+        let mut collided_rows = 0;
+        for plane in 0..FrameBuffer::PLANES {
+            if self.framebuffer.selected_planes() & (1 << plane) == 0 {
+                continue;
             }
+            let sprite = Sprite {
+                addr: sprite_addr,
+                width: sprite_width,
+                height: sprite_height,
+            };
+            collided_rows = collided_rows.max(self.draw_sprite(plane, &sprite, start_x, start_y)?);
+            sprite_addr = sprite_addr.wrapping_add(sprite_len);
+        }
+
+        *self.cpu.vf() = if self.quirks.collision_rows && hires {
+            collided_rows
+        } else {
+            (collided_rows > 0) as u8
+        };
+        Ok(())
+    }
 
-            let sprite_data = self.cpu.load_from_addr(index_addr + row as u16);
+    // Draws to one plane, returning the number of rows that had a collision. The starting
+    // coordinate wraps, but the drawing is clipped, except on XO-CHIP where it wraps too
+    fn draw_sprite(
+        &mut self,
+        plane: usize,
+        sprite: &Sprite,
+        start_x: u8,
+        start_y: u8,
+    ) -> Result<u8, Chip8Error> {
+        let height = self.framebuffer.height();
+        let wraps = self.quirks.wrap;
+        let bytes_per_row = sprite.width / 8;
+        let mut collided_rows = 0;
 
-            for bit_pos in 0..8 {
-                let x = start_x + bit_pos;
-                if x >= Screen::N_COLS {
-                    break;
-                }
+        for row in 0..sprite.height {
+            let y = start_y as u16 + row as u16;
+            if y >= height as u16 && !wraps {
+                break;
+            }
+            let y = (y % height as u16) as u8;
 
-                let sprite_bit = (sprite_data >> (7 - bit_pos)) & 1;
-                if sprite_bit == 1 {
-                    let pixel = self.screen.get_pixel(x, y).unwrap();
-                    if pixel {
-                        self.screen.set_pixel(x, y, false);
-                        *self.cpu.vf() = 1;
-                    } else {
-                        self.screen.set_pixel(x, y, true);
-                    }
-                }
+            // Left-align the sprite row in 16 bits, so both sprite widths are drawn the same way
+            let row_addr = sprite.addr as usize + (row * bytes_per_row) as usize;
+            let mut sprite_data = (self.load_sprite_byte(row_addr)? as u16) << 8;
+            if bytes_per_row == 2 {
+                sprite_data |= self.load_sprite_byte(row_addr + 1)? as u16;
+            }
+
+            let row_collided =
+                self.framebuffer
+                    .xor_sprite_row(plane, start_x, y, sprite_data, wraps);
+            if row_collided {
+                collided_rows += 1;
             }
         }
+        Ok(collided_rows)
+    }
+
+    fn load_sprite_byte(&self, addr: usize) -> Result<u8, Chip8Error> {
+        self.cpu
+            .resolve_addr(addr, self.quirks.memory_wrap)
+            .and_then(|addr| self.cpu.load_from_addr(addr))
     }
 
     pub fn update_debug_info(&mut self) {
-        // Check for playback state updates
-        if let Some(ref mut receiver) = self.playback_receiver {
-            while let Ok(state) = receiver.try_recv() {
-                self.playback_state = state;
-            }
+        let timing = self
+            .instruction_timer
+            .end_batch(self.config.cpu_hz * self.speed);
+
+        let dirty = std::mem::take(&mut self.debug_dirty);
+        let memory_written = self.cpu.take_memory_written();
+        // Send the full state once, and only what changed after that
+        let Some(ref snapshot) = self.debug_snapshot else {
+            let mut debug_info = self.get_debug_info();
+            debug_info.timing = timing;
+            self.frontend
+                .push(FrontendEvent::DebugInfo(debug_info.clone()));
+            self.debug_snapshot = Some(debug_info);
+            return;
+        };
+        let mut delta = self.get_debug_delta(snapshot, &dirty, memory_written);
+        delta.timing = (snapshot.timing != timing).then_some(timing);
+        if delta.is_empty() {
+            return;
         }
+        if let Some(ref mut snapshot) = self.debug_snapshot {
+            snapshot.apply(&delta);
+        }
+        self.frontend.push(FrontendEvent::DebugDelta(delta));
+    }
 
-        let debug_info = self.get_debug_info();
-        self.screen.set_debug_info(debug_info);
+    // What changed since `old`. The histories are only rebuilt when they're `dirty`, and
+    // memory is only read again, and the code at PC decoded, when the PC or I moved or
+    // memory was written
+    fn get_debug_delta(
+        &self,
+        old: &DebugInfo,
+        dirty: &DebugDirty,
+        memory_written: bool,
+    ) -> DebugDelta {
+        fn changed<T: PartialEq>(old: &T, new: T) -> Option<T> {
+            if *old != new { Some(new) } else { None }
+        }
+
+        let pc = self.cpu.get_pc();
+        let upcoming_bytes = (pc != old.current_pc || memory_written)
+            .then(|| self.read_memory(pc, Self::DEBUG_UPCOMING_INSTRUCTIONS * 2));
+        let upcoming_changed = upcoming_bytes.as_ref().is_some_and(|bytes| {
+            pc != old.current_pc
+                || !bytes
+                    .chunks_exact(2)
+                    .map(|chunk| RawInstruction::new(chunk[0], chunk[1]))
+                    .eq(old.upcoming.iter().map(|(_, raw, _)| raw.clone()))
+        });
+        let instruction = upcoming_changed.then(|| {
+            let raw = self.cpu.fetch_current_instruction();
+            let inst = crate::decoder::Decoder::decode_for(&raw, &self.config.version)
+                .unwrap_or(Instruction::Invalid);
+            (raw, inst)
+        });
+
+        let registers = self.cpu.all_register_val();
+        DebugDelta {
+            current_pc: changed(&old.current_pc, pc),
+            instruction,
+            index_register: changed(&old.index_register, self.cpu.get_index()),
+            delay_timer: changed(&old.delay_timer, self.cpu.get_delay_timer()),
+            sound_timer: changed(&old.sound_timer, self.cpu.get_sound_timer()),
+            beeping: changed(&old.beeping, self.beeping),
+            timer_history: dirty
+                .timer_history
+                .then(|| {
+                    self.timer_history
+                        .downsample(Self::DEBUG_TIMER_HISTORY_WIDTH)
+                })
+                .and_then(|history| changed(&old.timer_history, history)),
+            registers: (0..CPU::REGISTER_COUNT)
+                .filter(|&reg| registers[reg] != old.registers[reg])
+                .map(|reg| (reg, registers[reg]))
+                .collect(),
+            key_state: changed(&old.key_state, self.key_state),
+            key_history: dirty.key_history.then(|| self.key_history.clone()),
+            playback_mode: changed(&old.playback_mode, self.playback_state.clone()),
+            speed: changed(&old.speed, self.speed),
+            upcoming: upcoming_bytes
+                .filter(|_| upcoming_changed)
+                .map(|bytes| crate::decoder::disasm_for(&bytes, pc, &self.config.version)),
+            memory_at_index: (self.cpu.get_index() != old.index_register || memory_written)
+                .then(|| self.read_memory(self.cpu.get_index(), Self::DEBUG_MEMORY_BYTES))
+                .and_then(|memory| changed(&old.memory_at_index, memory)),
+            timing: None,
+            latency: changed(&old.latency, self.latency.stats()),
+        }
     }
 
     pub fn get_debug_info(&self) -> DebugInfo {
         let raw_inst = self.cpu.fetch_current_instruction();
-        let decoded_inst = crate::decoder::Decoder::decode(&raw_inst)
+        let decoded_inst = crate::decoder::Decoder::decode_for(&raw_inst, &self.config.version)
             .unwrap_or(crate::primitive::Instruction::Invalid);
 
         let registers = self.cpu.all_register_val();
@@ -306,9 +1964,33 @@ impl<'a> Hardware<'a> {
             index_register: self.cpu.get_index(),
             delay_timer: self.cpu.get_delay_timer(),
             sound_timer: self.cpu.get_sound_timer(),
+            beeping: self.beeping,
+            timer_history: self
+                .timer_history
+                .downsample(Self::DEBUG_TIMER_HISTORY_WIDTH),
             registers,
             key_state: self.key_state,
+            key_history: self.key_history.clone(),
             playback_mode: self.playback_state.clone(),
+            speed: self.speed,
+            upcoming: crate::decoder::disasm_for(
+                &self.read_memory(self.cpu.get_pc(), Self::DEBUG_UPCOMING_INSTRUCTIONS * 2),
+                self.cpu.get_pc(),
+                &self.config.version,
+            ),
+            memory_at_index: self.read_memory(self.cpu.get_index(), Self::DEBUG_MEMORY_BYTES),
+            timing: TimingStats::default(),
+            latency: self.latency.stats(),
         }
     }
+
+    // Copies up to `len` bytes of memory starting at `addr`, stopping at the end of memory
+    fn read_memory(&self, addr: u16, len: usize) -> Vec<u8> {
+        let memory = self.cpu.memory();
+        memory[(addr as usize).min(memory.len())..]
+            .iter()
+            .take(len)
+            .copied()
+            .collect()
+    }
 }
@@ -0,0 +1,172 @@
+// Runs the emulator synchronously a whole frame at a time, without a terminal or wall-clock
+// pacing. Meant for fuzzing, tests, and scripted runs. Cores are `Send` and share no state, so
+// a batch of ROMs can be run on a thread each
+
+use std::fmt;
+
+use crate::emulator::Emulator;
+use crate::error::Chip8Error;
+use crate::framebuffer::FrameBuffer;
+use crate::hardware::HardwareExecutionConfig;
+use crate::input::Chip8KeyEventKind;
+
+// Identifies the contents of the screen at the end of a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameHash(pub u64);
+
+impl FrameHash {
+    pub fn of(frame: &FrameBuffer) -> Self {
+        Self(frame.hash())
+    }
+}
+
+impl fmt::Display for FrameHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+// Key presses and releases, by the frame they happen at the start of
+#[derive(Debug, Clone, Default)]
+pub struct InputSchedule {
+    events: Vec<(usize, u8, Chip8KeyEventKind)>,
+}
+
+impl InputSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn press(mut self, frame: usize, key: u8) -> Self {
+        self.events.push((frame, key, Chip8KeyEventKind::Press));
+        self
+    }
+
+    pub fn release(mut self, frame: usize, key: u8) -> Self {
+        self.events.push((frame, key, Chip8KeyEventKind::Release));
+        self
+    }
+
+    // Whether anything happens at or after `frame`
+    pub fn has_events_from(&self, frame: usize) -> bool {
+        self.events.iter().any(|&(at, _, _)| at >= frame)
+    }
+
+    fn events_at(&self, frame: usize) -> impl Iterator<Item = (u8, Chip8KeyEventKind)> + '_ {
+        self.events
+            .iter()
+            .filter(move |(at, _, _)| *at == frame)
+            .map(|&(_, key, kind)| (key, kind))
+    }
+}
+
+// What a frame callback is given at the end of each frame
+pub struct FrameInfo<'f> {
+    pub frame: usize,       // Frames run since the ROM was loaded, including this one
+    pub pixels: &'f [bool], // Row by row, `width` pixels wide
+    pub width: u8,
+    pub beeping: bool,
+    pub instructions: usize, // Instructions run during the frame
+    pub hash: FrameHash,
+}
+
+type FrameCallback<'a> = Box<dyn FnMut(&FrameInfo) + Send + 'a>;
+
+// An `Emulator` stepped a frame at a time, each frame being the steps between timer ticks
+pub struct Chip8Core<'a> {
+    pub emulator: Emulator<'a>,
+    frame: usize, // Frames run since the ROM was loaded
+    frame_callback: Option<FrameCallback<'a>>,
+}
+
+impl<'a> Chip8Core<'a> {
+    // Random numbers are the same on every run unless a different seed is set on the hardware
+    const SEED: u64 = 0;
+
+    pub fn new(config: HardwareExecutionConfig) -> Self {
+        let mut emulator = Emulator::new(config);
+        emulator.hardware.set_seed(Self::SEED);
+        Self {
+            emulator,
+            frame: 0,
+            frame_callback: None,
+        }
+    }
+
+    // Calls `callback` at the end of every frame, replacing any previous callback
+    pub fn on_frame(&mut self, callback: impl FnMut(&FrameInfo) + Send + 'a) {
+        self.frame_callback = Some(Box::new(callback));
+    }
+
+    pub fn load_rom(&mut self, bytes: &'a [u8]) -> Result<(), Chip8Error> {
+        self.frame = 0;
+        self.emulator.load_rom(bytes)
+    }
+
+    // Runs `n` frames, returning the hash of the screen after each one. Stops early if the
+    // ROM exits, or faults, which `hardware.take_fault()` then says why
+    pub fn run_frames(&mut self, n: usize, input: &InputSchedule) -> Vec<FrameHash> {
+        let mut hashes = Vec::with_capacity(n);
+        for _ in 0..n {
+            if self.emulator.hardware.has_exited() {
+                break;
+            }
+            hashes.push(self.run_frame(input));
+        }
+        hashes
+    }
+
+    // Runs frames until `max_cycles` instructions have run since the ROM was loaded, cutting
+    // the last one short to stop on exactly that many. Stops early if the ROM exits, or waits
+    // for a key with no input left to press one, as a halt
+    pub fn run_cycles(&mut self, max_cycles: u64, input: &InputSchedule) -> Vec<FrameHash> {
+        let mut hashes = Vec::new();
+        while !self.emulator.hardware.has_exited() && !self.is_stuck_on_key(input) {
+            let remaining = max_cycles.saturating_sub(self.emulator.hardware.cycles());
+            if remaining == 0 {
+                break;
+            }
+            let budget = (self.emulator.steps_per_tick() as u64).min(remaining) as usize;
+            hashes.push(self.run_frame_of(budget, input));
+        }
+        hashes
+    }
+
+    // Waiting for a key that none of the input left will press
+    pub fn is_stuck_on_key(&self, input: &InputSchedule) -> bool {
+        self.emulator.hardware.is_waiting_for_key() && !input.has_events_from(self.frame)
+    }
+
+    fn run_frame(&mut self, input: &InputSchedule) -> FrameHash {
+        self.run_frame_of(self.emulator.steps_per_tick(), input)
+    }
+
+    // Runs up to `budget` steps, which end with the timers ticking unless the budget is cut
+    // short
+    fn run_frame_of(&mut self, budget: usize, input: &InputSchedule) -> FrameHash {
+        for (key, kind) in input.events_at(self.frame) {
+            self.emulator.set_key(key, kind == Chip8KeyEventKind::Press);
+        }
+
+        let cycles = self.emulator.hardware.cycles();
+        if let Err(e) = self.emulator.run_steps(budget) {
+            self.emulator.hardware.stop_with_fault(e);
+        }
+        let instructions = (self.emulator.hardware.cycles() - cycles) as usize;
+        self.frame += 1;
+
+        let hash = FrameHash::of(self.emulator.framebuffer());
+        if let Some(ref mut callback) = self.frame_callback {
+            let framebuffer = self.emulator.framebuffer();
+            callback(&FrameInfo {
+                frame: self.frame,
+                pixels: &framebuffer.pixels(),
+                width: framebuffer.width(),
+                beeping: self.emulator.is_beeping(),
+                instructions,
+                hash,
+            });
+        }
+        hash
+    }
+}
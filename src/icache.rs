@@ -0,0 +1,78 @@
+// Instructions decoded ahead of time, by address. Decoding looks the opcode up in the table
+// and checks it against the version, which adds up when it's done every cycle. Programs rarely
+// modify themselves, so the decoding is kept until something writes to the instruction's bytes.
+//
+// Straight-line runs of instructions are also kept as basic blocks, which run one after the
+// other without looking anything up. Once a program writes over code that a block ran, it's
+// treated as self-modifying and the blocks are dropped for good, falling back to running one
+// instruction at a time
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::primitive::Instruction;
+
+pub type BasicBlock = Arc<[Instruction]>;
+
+#[derive(Clone, Debug)]
+pub struct InstructionCache {
+    entries: Vec<Option<Instruction>>,
+    blocks: HashMap<u16, BasicBlock>, // By the address of their first instruction
+    in_block: Vec<bool>,              // Bytes that are part of a block's instructions
+    self_modifying: bool,
+}
+
+impl InstructionCache {
+    pub fn new(memory_size: usize) -> Self {
+        Self {
+            entries: vec![None; memory_size],
+            blocks: HashMap::new(),
+            in_block: vec![false; memory_size],
+            self_modifying: false,
+        }
+    }
+
+    pub fn get(&self, addr: u16) -> Option<&Instruction> {
+        self.entries.get(addr as usize)?.as_ref()
+    }
+
+    pub fn insert(&mut self, addr: u16, inst: Instruction) {
+        if let Some(entry) = self.entries.get_mut(addr as usize) {
+            *entry = Some(inst);
+        }
+    }
+
+    // Forgets the instructions that include the byte at `addr`: the one starting there, and
+    // the one starting just before it
+    pub fn invalidate(&mut self, addr: usize) {
+        for addr in addr.saturating_sub(1)..=addr {
+            if let Some(entry) = self.entries.get_mut(addr) {
+                *entry = None;
+            }
+        }
+        if self.in_block.get(addr) == Some(&true) {
+            self.self_modifying = true;
+            self.blocks.clear();
+            self.in_block.fill(false);
+        }
+    }
+
+    pub fn block(&self, addr: u16) -> Option<BasicBlock> {
+        self.blocks.get(&addr).cloned()
+    }
+
+    // Keeps a block of two-byte instructions starting at `addr`, unless the program is
+    // self-modifying
+    pub fn insert_block(&mut self, addr: u16, block: BasicBlock) {
+        if self.self_modifying {
+            return;
+        }
+        let end = (addr as usize + block.len() * 2).min(self.in_block.len());
+        self.in_block[addr as usize..end].fill(true);
+        self.blocks.insert(addr, block);
+    }
+
+    pub fn is_self_modifying(&self) -> bool {
+        self.self_modifying
+    }
+}
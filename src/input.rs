@@ -1,11 +1,19 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use std::collections::HashMap;
-use std::time::Duration;
+use crossterm::event::KeyCode;
+#[cfg(feature = "tui")]
+use crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "tui")]
+use std::sync::{
+    Mutex, RwLock,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::{Duration, Instant};
 
 use crate::chip8::Chip8;
+use crate::keybindings::{BindingTarget, KeyBindings};
 
 // Struct to store and send key state to different components
-#[derive(Default, Clone, Copy, Debug)]
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
 pub struct Chip8KeyState {
     keys_pressed: [bool; Self::TOTAL_KEYS],
 }
@@ -36,6 +44,111 @@ impl Chip8KeyState {
     }
 }
 
+// When each CHIP-8 key was last pressed, or repeated by the terminal, for releasing keys
+// whose release was never reported
+#[derive(Default, Debug)]
+pub struct HeldKeys([Option<Instant>; 16]);
+
+impl HeldKeys {
+    pub fn press(&mut self, key: u8, at: Instant) {
+        self.0[key as usize & 0xF] = Some(at);
+    }
+
+    pub fn release(&mut self, key: u8) {
+        self.0[key as usize & 0xF] = None;
+    }
+
+    // A key that has gone longer than `timeout` without repeating, which is taken to be
+    // stuck, and forgotten
+    pub fn take_stuck(&mut self, now: Instant, timeout: Duration) -> Option<u8> {
+        let key = self
+            .0
+            .iter()
+            .position(|at| at.is_some_and(|at| now.duration_since(at) > timeout))?;
+        self.0[key] = None;
+        Some(key as u8)
+    }
+}
+
+// Hex digits pasted into the terminal, typed into the keypad one after another. Anything else
+// that was pasted is skipped
+#[derive(Default, Debug)]
+pub struct PastedKeys {
+    events: VecDeque<(Instant, u8, Chip8KeyEventKind)>, // When each is due, in order
+}
+
+impl PastedKeys {
+    // Long enough for games that poll the keypad once a frame or so to see each press
+    const PRESS_DURATION: Duration = Duration::from_millis(100);
+    const GAP: Duration = Duration::from_millis(100);
+
+    // Queues the text's keys after any that are still being typed
+    pub fn paste(&mut self, text: &str, now: Instant) {
+        let mut at = self
+            .events
+            .back()
+            .map_or(now, |&(last, _, _)| (last + Self::GAP).max(now));
+        for key in text.chars().filter_map(|c| c.to_digit(16)) {
+            self.events
+                .push_back((at, key as u8, Chip8KeyEventKind::Press));
+            self.events.push_back((
+                at + Self::PRESS_DURATION,
+                key as u8,
+                Chip8KeyEventKind::Release,
+            ));
+            at += Self::PRESS_DURATION + Self::GAP;
+        }
+    }
+
+    pub fn next_due(&mut self, now: Instant) -> Option<(u8, Chip8KeyEventKind)> {
+        match self.events.front() {
+            Some(&(at, key, kind)) if at <= now => {
+                self.events.pop_front();
+                Some((key, kind))
+            }
+            _ => None,
+        }
+    }
+}
+
+// The most recent CHIP-8 key events, with the instruction count they arrived at
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct KeyHistory {
+    events: VecDeque<(u64, u8, Chip8KeyEventKind)>, // (Cycle, key, kind), oldest first
+}
+
+impl KeyHistory {
+    const CAPACITY: usize = 8;
+
+    pub fn record(&mut self, cycle: u64, key: u8, kind: Chip8KeyEventKind) {
+        if self.events.len() == Self::CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back((cycle, key, kind));
+    }
+}
+
+// `5↓@1200 5↑@1260`, with ↓ for a press and ↑ for a release
+impl std::fmt::Display for KeyHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.events.is_empty() {
+            return write!(f, "None");
+        }
+        let events: Vec<String> = self
+            .events
+            .iter()
+            .map(|(cycle, key, kind)| {
+                let arrow = match kind {
+                    Chip8KeyEventKind::Press => '↓',
+                    Chip8KeyEventKind::Release => '↑',
+                };
+                format!("{key:X}{arrow}@{cycle}")
+            })
+            .collect();
+        write!(f, "{}", events.join(" "))
+    }
+}
+
 /// Keyboard layout options for CHIP-8 input mapping
 #[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
 pub enum KeyboardLayout {
@@ -57,6 +170,35 @@ pub enum KeyboardLayout {
     /// 1 2 3 4 5 6 7 8 9 0 Q W E R T Y
     /// to CHIP-8: 1 2 3 4 5 6 7 8 9 0 A B C D E F
     Sequential,
+
+    /// Same physical keys as Qwerty, on a French AZERTY keyboard
+    /// & é " '    =>    1 2 3 C
+    /// A Z E R    =>    4 5 6 D
+    /// Q S D F    =>    7 8 9 E
+    /// W X C V    =>    A 0 B F
+    Azerty,
+
+    /// Same physical keys as Qwerty, on a Dvorak keyboard
+    /// 1 2 3 4    =>    1 2 3 C
+    /// ' , . P    =>    4 5 6 D
+    /// A O E U    =>    7 8 9 E
+    /// ; Q J K    =>    A 0 B F
+    Dvorak,
+
+    /// Same physical keys as Qwerty, on a Colemak keyboard
+    /// 1 2 3 4    =>    1 2 3 C
+    /// Q W F P    =>    4 5 6 D
+    /// A R S T    =>    7 8 9 E
+    /// Z X C V    =>    A 0 B F
+    Colemak,
+
+    /// Maps the numeric keypad, which is laid out like the COSMAC VIP hex pad.
+    /// Enter is used as B, so `N` steps in debug mode instead
+    /// 7 8 9 /    =>    1 2 3 C
+    /// 4 5 6 *    =>    4 5 6 D
+    /// 1 2 3 -    =>    7 8 9 E
+    /// 0 . ⏎ +    =>    A 0 B F
+    Numpad,
 }
 
 impl std::fmt::Display for KeyboardLayout {
@@ -69,12 +211,76 @@ impl std::fmt::Display for KeyboardLayout {
                 Qwerty => "qwerty",
                 Sequential => "sequential",
                 Natural => "natural",
+                Azerty => "azerty",
+                Dvorak => "dvorak",
+                Colemak => "colemak",
+                Numpad => "numpad",
             }
         )
     }
 }
 
 impl KeyboardLayout {
+    // The key that restarts the ROM, kept where `P` is on a Qwerty keyboard
+    pub fn restart_key(&self) -> char {
+        match self {
+            KeyboardLayout::Dvorak => 'l',
+            KeyboardLayout::Colemak => ';',
+            _ => 'p',
+        }
+    }
+
+    // The key that steps one instruction in debug mode
+    pub fn step_key(&self) -> KeyCode {
+        match self {
+            KeyboardLayout::Numpad => KeyCode::Char('n'),
+            _ => KeyCode::Enter,
+        }
+    }
+
+    // The keys that run commands, unless the user moved them
+    pub fn command_map(&self) -> HashMap<KeyCode, Chip8Command> {
+        HashMap::from([
+            (KeyCode::Esc, Chip8Command::Quit),
+            (KeyCode::Char(' '), Chip8Command::DebugPlayPause),
+            (self.step_key(), Chip8Command::DebugStep),
+            (KeyCode::Char(self.restart_key()), Chip8Command::Restart),
+            (KeyCode::Tab, Chip8Command::ToggleLog),
+            (KeyCode::Char('h'), Chip8Command::ToggleFrameDiff),
+            (KeyCode::PageUp, Chip8Command::ScrollLogUp),
+            (KeyCode::PageDown, Chip8Command::ScrollLogDown),
+            (KeyCode::F(2), Chip8Command::OpenSettings),
+            (KeyCode::F(3), Chip8Command::RecordMacro),
+            (KeyCode::F(4), Chip8Command::PlayMacro),
+            (KeyCode::F(10), Chip8Command::SkipDelayWait),
+            (KeyCode::F(5), Chip8Command::SaveState),
+            (KeyCode::F(6), Chip8Command::PreviousSaveSlot),
+            (KeyCode::F(7), Chip8Command::NextSaveSlot),
+            (KeyCode::F(9), Chip8Command::LoadState),
+            (KeyCode::Backspace, Chip8Command::Rewind),
+            (KeyCode::Char('='), Chip8Command::SpeedUp),
+            (KeyCode::Char('+'), Chip8Command::SpeedUp),
+            (KeyCode::Char('-'), Chip8Command::SlowDown),
+            (KeyCode::Char('`'), Chip8Command::FastForward),
+            (KeyCode::Char('\\'), Chip8Command::SlowMotion),
+        ])
+    }
+
+    // Maps the rows of the 4x4 cluster, top to bottom, onto the standard CHIP-8 keypad
+    fn keypad_map(rows: [[char; 4]; 4]) -> HashMap<KeyCode, u8> {
+        const KEYPAD: [[u8; 4]; 4] = [
+            [0x1, 0x2, 0x3, 0xC],
+            [0x4, 0x5, 0x6, 0xD],
+            [0x7, 0x8, 0x9, 0xE],
+            [0xA, 0x0, 0xB, 0xF],
+        ];
+        rows.iter()
+            .flatten()
+            .zip(KEYPAD.iter().flatten())
+            .map(|(&c, &key)| (KeyCode::Char(c), key))
+            .collect()
+    }
+
     pub fn get_key_map(layout: &Self) -> HashMap<KeyCode, u8> {
         match layout {
             KeyboardLayout::Qwerty => {
@@ -151,6 +357,50 @@ impl KeyboardLayout {
                     (KeyCode::Char('y'), 0xF),
                 ])
             }
+
+            KeyboardLayout::Azerty => Self::keypad_map([
+                ['&', 'é', '"', '\''],
+                ['a', 'z', 'e', 'r'],
+                ['q', 's', 'd', 'f'],
+                ['w', 'x', 'c', 'v'],
+            ]),
+
+            KeyboardLayout::Dvorak => Self::keypad_map([
+                ['1', '2', '3', '4'],
+                ['\'', ',', '.', 'p'],
+                ['a', 'o', 'e', 'u'],
+                [';', 'q', 'j', 'k'],
+            ]),
+
+            KeyboardLayout::Colemak => Self::keypad_map([
+                ['1', '2', '3', '4'],
+                ['q', 'w', 'f', 'p'],
+                ['a', 'r', 's', 't'],
+                ['z', 'x', 'c', 'v'],
+            ]),
+
+            KeyboardLayout::Numpad => {
+                // Terminals report keypad digits the same as the number row, and keypad Enter
+                // the same as Return
+                HashMap::from([
+                    (KeyCode::Char('7'), 0x1),
+                    (KeyCode::Char('8'), 0x2),
+                    (KeyCode::Char('9'), 0x3),
+                    (KeyCode::Char('/'), 0xC),
+                    (KeyCode::Char('4'), 0x4),
+                    (KeyCode::Char('5'), 0x5),
+                    (KeyCode::Char('6'), 0x6),
+                    (KeyCode::Char('*'), 0xD),
+                    (KeyCode::Char('1'), 0x7),
+                    (KeyCode::Char('2'), 0x8),
+                    (KeyCode::Char('3'), 0x9),
+                    (KeyCode::Char('-'), 0xE),
+                    (KeyCode::Char('0'), 0xA),
+                    (KeyCode::Char('.'), 0x0),
+                    (KeyCode::Enter, 0xB),
+                    (KeyCode::Char('+'), 0xF),
+                ])
+            }
         }
     }
 }
@@ -159,36 +409,77 @@ impl KeyboardLayout {
 #[derive(Debug, Clone)]
 pub struct InputConfig {
     pub layout: KeyboardLayout,
+    pub bindings: KeyBindings, // Keys the user moved from where the layout puts them
     pub poll_rate: Duration,
+    // How long a key stays pressed without repeating, where None uses the terminal's default
+    // and zero keeps keys pressed until they're released
+    pub key_timeout: Option<Duration>,
 }
 
 impl Default for InputConfig {
     fn default() -> Self {
         Self {
             layout: KeyboardLayout::Qwerty,
+            bindings: KeyBindings::default(),
+            key_timeout: None,
             poll_rate: Duration::from_millis(Chip8::INPUT_POLL_RATE_MS),
         }
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Chip8KeyEventKind {
     Press,
     Release,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Chip8Command {
     Quit,
     Restart,
     DebugStep,
     DebugPlayPause,
+    SkipDelayWait, // Runs until the delay timer reaches 0
+    ToggleLog,
+    ToggleFrameDiff,
+    ScrollLogUp,
+    ScrollLogDown,
+    OpenSettings,
+    SaveState,
+    LoadState,
+    PreviousSaveSlot,
+    NextSaveSlot,
+    Rewind,
+    SpeedUp,
+    SlowDown,
+    FastForward, // Runs fast for as long as it's held
+    SlowMotion,  // Runs slowly for as long as it's held
+    RecordMacro, // Starts or stops recording the macro
+    PlayMacro,
+}
+
+impl Chip8Command {
+    // Commands that last until their key is released, rather than happening once
+    pub fn is_held(&self) -> bool {
+        matches!(self, Chip8Command::FastForward | Chip8Command::SlowMotion)
+    }
+}
+
+// Keys read while the settings page is open
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuKey {
+    Up,
+    Down,
+    Left,
+    Right,
+    Close,
 }
 
 #[derive(Debug)]
 pub struct Chip8KeyEvent {
     pub key: u8,
     pub kind: Chip8KeyEventKind,
+    pub received: Instant, // When the event was read from the terminal
 }
 
 #[derive(Debug)]
@@ -198,25 +489,206 @@ pub enum Chip8InputEvent {
         kind: Chip8KeyEventKind,
     },
     Chip8KeyEvent(Chip8KeyEvent),
+    MenuEvent(MenuKey),
+}
+
+// What each key does, under a layout and the user's bindings
+pub struct KeyMapping {
+    layout: KeyboardLayout,
+    bindings: KeyBindings,
+    keys: HashMap<KeyCode, u8>,
+    commands: HashMap<KeyCode, Chip8Command>,
+}
+
+impl KeyMapping {
+    pub fn new(layout: KeyboardLayout, bindings: KeyBindings) -> Self {
+        let mut keys = KeyboardLayout::get_key_map(&layout);
+        let mut commands = layout.command_map();
+        for &(target, code) in bindings.iter() {
+            keys.remove(&code);
+            commands.remove(&code);
+            match target {
+                BindingTarget::Key(key) => {
+                    keys.retain(|_, &mut k| k != key);
+                    keys.insert(code, key);
+                }
+                BindingTarget::Command(command) => {
+                    commands.retain(|_, &mut c| c != command);
+                    commands.insert(code, command);
+                }
+            }
+        }
+        Self {
+            layout,
+            bindings,
+            keys,
+            commands,
+        }
+    }
+
+    pub fn layout(&self) -> KeyboardLayout {
+        self.layout
+    }
+
+    pub fn bindings(&self) -> &KeyBindings {
+        &self.bindings
+    }
+
+    // What a key does. The keypad comes first, so layouts can use the command keys
+    pub fn target_of(&self, code: KeyCode) -> Option<BindingTarget> {
+        let key = self.keys.get(&code).map(|&key| BindingTarget::Key(key));
+        key.or_else(|| {
+            self.commands
+                .get(&code)
+                .map(|&command| BindingTarget::Command(command))
+        })
+    }
+
+    // The key that does `target`, as printed on the keycap
+    pub fn label(&self, target: BindingTarget) -> String {
+        let mut codes: Vec<&KeyCode> = self
+            .keys
+            .keys()
+            .chain(self.commands.keys())
+            .filter(|&&code| self.target_of(code) == Some(target))
+            .collect();
+        codes.sort_by_key(|code| code.to_string());
+        match codes.first() {
+            Some(KeyCode::Char(' ')) => "Space".to_string(),
+            Some(KeyCode::Char(c)) => c.to_uppercase().to_string(),
+            Some(code) => code.to_string(),
+            None => match target {
+                BindingTarget::Key(key) => format!("{key:X}"),
+                BindingTarget::Command(_) => "none".to_string(),
+            },
+        }
+    }
+
+    // Describes what a ROM's keys do in terms of this mapping, grouping keys that do the same
+    // thing, e.g. "W/S = paddle, Space = serve"
+    pub fn describe_keys(&self, hints: &[(u8, String)]) -> String {
+        let mut groups: Vec<(Vec<String>, &str)> = Vec::new();
+        for (key, hint) in hints {
+            let label = self.label(BindingTarget::Key(*key));
+            match groups.iter_mut().find(|(_, existing)| existing == hint) {
+                Some((labels, _)) => labels.push(label),
+                None => groups.push((vec![label], hint)),
+            }
+        }
+        groups
+            .iter()
+            .map(|(labels, hint)| format!("{} = {hint}", labels.join("/")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
+#[cfg(feature = "tui")]
 pub struct KeyEventHandler {
     config: InputConfig,
-    key_mapping: HashMap<KeyCode, u8>,
+    // The layout and bindings can be changed by reloading the settings while keys are read
+    key_mapping: RwLock<KeyMapping>,
+    menu_open: AtomicBool, // Whether keys go to the settings page instead
+    held_keys: Mutex<HeldKeys>,
+    held_command: Mutex<Option<(Chip8Command, Instant)>>,
+    pasted_keys: Mutex<PastedKeys>,
 }
 
+#[cfg(feature = "tui")]
 impl KeyEventHandler {
     pub fn new(config: InputConfig) -> Self {
         Self {
-            config: config.clone(),
-            key_mapping: KeyboardLayout::get_key_map(&config.layout),
+            key_mapping: RwLock::new(KeyMapping::new(config.layout, config.bindings.clone())),
+            config,
+            menu_open: AtomicBool::new(false),
+            held_keys: Mutex::new(HeldKeys::default()),
+            held_command: Mutex::new(None),
+            pasted_keys: Mutex::new(PastedKeys::default()),
+        }
+    }
+
+    // Long enough for the terminal's key repeat to start, so held keys don't flicker
+    const DEFAULT_KEY_TIMEOUT: Duration = Duration::from_millis(600);
+
+    // How long a key stays pressed without repeating. Terminals that report releases don't
+    // need one
+    pub fn key_timeout(&self) -> Option<Duration> {
+        match self.config.key_timeout {
+            Some(timeout) => Some(timeout).filter(|timeout| !timeout.is_zero()),
+            None => (!self.reports_key_releases()).then_some(Self::DEFAULT_KEY_TIMEOUT),
+        }
+    }
+
+    // Releases a key that has been held past the timeout, as its release was probably missed
+    fn release_stuck_key(&self) -> Option<Chip8InputEvent> {
+        let timeout = self.key_timeout()?;
+        let now = Instant::now();
+        let mut held_command = self.held_command.lock().unwrap();
+        if let Some((command, at)) = *held_command
+            && now.duration_since(at) > timeout
+        {
+            *held_command = None;
+            return Some(Chip8InputEvent::CommandEvent {
+                command,
+                kind: Chip8KeyEventKind::Release,
+            });
         }
+        let key = self.held_keys.lock().unwrap().take_stuck(now, timeout)?;
+        Some(Chip8InputEvent::Chip8KeyEvent(Chip8KeyEvent {
+            key,
+            kind: Chip8KeyEventKind::Release,
+            received: now,
+        }))
+    }
+
+    pub fn layout(&self) -> KeyboardLayout {
+        self.key_mapping.read().unwrap().layout()
+    }
+
+    pub fn set_layout(&self, layout: KeyboardLayout) {
+        let mut mapping = self.key_mapping.write().unwrap();
+        *mapping = KeyMapping::new(layout, mapping.bindings().clone());
+    }
+
+    pub fn set_bindings(&self, bindings: KeyBindings) {
+        let mut mapping = self.key_mapping.write().unwrap();
+        *mapping = KeyMapping::new(mapping.layout(), bindings);
+    }
+
+    // The key that does `target`, as printed on the keycap
+    pub fn label(&self, target: BindingTarget) -> String {
+        self.key_mapping.read().unwrap().label(target)
+    }
+
+    pub fn describe_keys(&self, hints: &[(u8, String)]) -> String {
+        self.key_mapping.read().unwrap().describe_keys(hints)
+    }
+
+    pub fn set_menu_open(&self, open: bool) {
+        self.menu_open.store(open, Ordering::Relaxed);
+    }
+
+    /// Whether the terminal sends key release events. Only Windows consoles report them
+    /// without the keyboard enhancement protocol, which isn't enabled
+    pub fn reports_key_releases(&self) -> bool {
+        cfg!(windows)
     }
 
     /// Update the key states by polling crossterm events
     pub async fn next_input_event(&self) -> Chip8InputEvent {
         let rate = self.config.poll_rate;
         loop {
+            if let Some(release) = self.release_stuck_key() {
+                return release;
+            }
+            let now = Instant::now();
+            if let Some((key, kind)) = self.pasted_keys.lock().unwrap().next_due(now) {
+                return Chip8InputEvent::Chip8KeyEvent(Chip8KeyEvent {
+                    key,
+                    kind,
+                    received: now,
+                });
+            }
             match tokio::task::spawn_blocking(move || {
                 event::poll(rate)
                     .ok()
@@ -232,6 +704,15 @@ impl KeyEventHandler {
                         continue;
                     }
                 }
+                Ok(Some(Event::Paste(text))) => {
+                    if !self.menu_open.load(Ordering::Relaxed) {
+                        self.pasted_keys
+                            .lock()
+                            .unwrap()
+                            .paste(&text, Instant::now());
+                    }
+                    continue;
+                }
                 _ => {
                     tokio::time::sleep(rate).await;
                     continue;
@@ -247,25 +728,48 @@ impl KeyEventHandler {
             _ => return None,
         };
 
-        // Map physical key to CHIP-8 key
-        if let Some(&chip8_key) = self.key_mapping.get(&key_event.code) {
-            Some(Chip8InputEvent::Chip8KeyEvent(Chip8KeyEvent {
-                key: chip8_key,
-                kind: pressed,
-            }))
-        // Physical key for debug/quit commands
-        } else {
-            let command = match key_event.code {
-                KeyCode::Esc => Chip8Command::Quit,
-                KeyCode::Char(' ') => Chip8Command::DebugPlayPause,
-                KeyCode::Enter => Chip8Command::DebugStep,
-                KeyCode::Char('p') => Chip8Command::Restart,
+        if self.menu_open.load(Ordering::Relaxed) {
+            if pressed == Chip8KeyEventKind::Release {
+                return None;
+            }
+            let key = match key_event.code {
+                KeyCode::Up => MenuKey::Up,
+                KeyCode::Down => MenuKey::Down,
+                KeyCode::Left => MenuKey::Left,
+                KeyCode::Right => MenuKey::Right,
+                KeyCode::Esc | KeyCode::F(2) => MenuKey::Close,
                 _ => return None,
             };
-            Some(Chip8InputEvent::CommandEvent {
-                command,
-                kind: pressed,
-            })
+            return Some(Chip8InputEvent::MenuEvent(key));
         }
+
+        let target = self.key_mapping.read().unwrap().target_of(key_event.code)?;
+        Some(match target {
+            BindingTarget::Key(key) => {
+                let received = Instant::now();
+                let mut held_keys = self.held_keys.lock().unwrap();
+                match pressed {
+                    Chip8KeyEventKind::Press => held_keys.press(key, received),
+                    Chip8KeyEventKind::Release => held_keys.release(key),
+                }
+                Chip8InputEvent::Chip8KeyEvent(Chip8KeyEvent {
+                    key,
+                    kind: pressed,
+                    received,
+                })
+            }
+            BindingTarget::Command(command) => {
+                if command.is_held() {
+                    *self.held_command.lock().unwrap() = match pressed {
+                        Chip8KeyEventKind::Press => Some((command, Instant::now())),
+                        Chip8KeyEventKind::Release => None,
+                    };
+                }
+                Chip8InputEvent::CommandEvent {
+                    command,
+                    kind: pressed,
+                }
+            }
+        })
     }
 }
@@ -0,0 +1,75 @@
+// Desktop integration on Linux: a `.desktop` entry, so launchers list the emulator, and a
+// MIME type for `.ch8` files, so file managers open ROMs with it. The emulator runs in a
+// terminal, so the entry asks the launcher for one
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::paths;
+
+pub const DESKTOP_FILE: &str = "chip8-emulator.desktop";
+pub const MIME_TYPE: &str = "application/x-chip8-rom";
+
+// A desktop entry that opens a ROM with `exe`
+pub fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=CHIP-8 Emulator\n\
+         Comment=Play CHIP-8 ROMs\n\
+         Exec=\"{}\" %f\n\
+         Terminal=true\n\
+         Categories=Game;Emulator;\n\
+         MimeType={MIME_TYPE};\n",
+        exe.display()
+    )
+}
+
+// Declares `.ch8` files as CHIP-8 ROMs, in the freedesktop shared MIME-info format
+pub fn mime_package() -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <mime-info xmlns=\"http://www.freedesktop.org/standards/shared-mime-info\">\n\
+         \x20 <mime-type type=\"{MIME_TYPE}\">\n\
+         \x20   <comment>CHIP-8 ROM</comment>\n\
+         \x20   <glob pattern=\"*.ch8\"/>\n\
+         \x20 </mime-type>\n\
+         </mime-info>\n"
+    )
+}
+
+// Installs the desktop entry and MIME type for the current user, and makes the emulator the
+// default for `.ch8` files. Returns the files written
+pub fn install() -> io::Result<Vec<PathBuf>> {
+    if !cfg!(target_os = "linux") {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Desktop integration is only supported on Linux",
+        ));
+    }
+    let exe = std::env::current_exe()?;
+    let data_home = paths::user_data_home();
+    let applications = data_home.join("applications");
+    let mime_packages = data_home.join("mime/packages");
+
+    let desktop_path = applications.join(DESKTOP_FILE);
+    let mime_path = mime_packages.join("chip8-emulator.xml");
+    fs::create_dir_all(&applications)?;
+    fs::create_dir_all(&mime_packages)?;
+    fs::write(&desktop_path, desktop_entry(&exe))?;
+    fs::write(&mime_path, mime_package())?;
+
+    // Refresh the caches and set the default. These tools are missing on some desktops, which
+    // only means the association is picked up later
+    run_quietly(Command::new("update-mime-database").arg(data_home.join("mime")));
+    run_quietly(Command::new("update-desktop-database").arg(&applications));
+    run_quietly(Command::new("xdg-mime").args(["default", DESKTOP_FILE, MIME_TYPE]));
+
+    Ok(vec![desktop_path, mime_path])
+}
+
+fn run_quietly(command: &mut Command) {
+    let _ = command.stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
@@ -0,0 +1,195 @@
+// Keys bound with `chip-8-emulator remap`, kept in the `[keys]` table of the settings file.
+// Each one moves a CHIP-8 key or a command away from where the keyboard layout puts it:
+//
+//     [keys]
+//     5 = "up"
+//     quit = "q"
+
+use crossterm::event::KeyCode;
+
+use crate::input::Chip8Command;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BindingTarget {
+    Key(u8),
+    Command(Chip8Command),
+}
+
+impl BindingTarget {
+    // The commands that can be moved, by their name in the settings file
+    const COMMANDS: [(&str, Chip8Command); 14] = [
+        ("quit", Chip8Command::Quit),
+        ("restart", Chip8Command::Restart),
+        ("pause", Chip8Command::DebugPlayPause),
+        ("step", Chip8Command::DebugStep),
+        ("skip_delay", Chip8Command::SkipDelayWait),
+        ("save_state", Chip8Command::SaveState),
+        ("load_state", Chip8Command::LoadState),
+        ("rewind", Chip8Command::Rewind),
+        ("speed_up", Chip8Command::SpeedUp),
+        ("slow_down", Chip8Command::SlowDown),
+        ("fast_forward", Chip8Command::FastForward),
+        ("slow_motion", Chip8Command::SlowMotion),
+        ("record_macro", Chip8Command::RecordMacro),
+        ("play_macro", Chip8Command::PlayMacro),
+    ];
+
+    // Everything that can be bound, in the order the remap wizard asks for them: the keypad
+    // row by row, then the commands
+    pub fn all() -> Vec<Self> {
+        const KEYPAD: [u8; 16] = [
+            0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD, 0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF,
+        ];
+        KEYPAD
+            .into_iter()
+            .map(BindingTarget::Key)
+            .chain(
+                Self::COMMANDS
+                    .iter()
+                    .map(|&(_, command)| BindingTarget::Command(command)),
+            )
+            .collect()
+    }
+
+    // Its name in the settings file, e.g. "A" or "quit"
+    pub fn name(&self) -> &'static str {
+        const KEY_NAMES: [&str; 16] = [
+            "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "A", "B", "C", "D", "E", "F",
+        ];
+        match self {
+            BindingTarget::Key(key) => KEY_NAMES[*key as usize & 0xF],
+            BindingTarget::Command(command) => Self::COMMANDS
+                .iter()
+                .find(|(_, c)| c == command)
+                .map_or("", |(name, _)| name),
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        if let Ok(key) = u8::from_str_radix(name, 16)
+            && key <= 0xF
+        {
+            return Some(BindingTarget::Key(key));
+        }
+        Self::COMMANDS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|&(_, command)| BindingTarget::Command(command))
+    }
+}
+
+impl std::fmt::Display for BindingTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BindingTarget::Key(key) => write!(f, "CHIP-8 key {key:X}"),
+            BindingTarget::Command(_) => write!(f, "{}", self.name().replace('_', " ")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeyBindings(Vec<(BindingTarget, KeyCode)>); // In the order they were bound
+
+impl KeyBindings {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(BindingTarget, KeyCode)> {
+        self.0.iter()
+    }
+
+    // Binds the target to `code`, replacing its earlier binding. Letters are bound whether or
+    // not shift is held, like the layouts' keys
+    pub fn bind(&mut self, target: BindingTarget, code: KeyCode) {
+        let code = unshifted(code);
+        self.0.retain(|(t, _)| *t != target);
+        self.0.push((target, code));
+    }
+
+    // What `code` is bound to, if anything
+    pub fn target_of(&self, code: KeyCode) -> Option<BindingTarget> {
+        let code = unshifted(code);
+        self.0.iter().find(|(_, c)| *c == code).map(|(t, _)| *t)
+    }
+
+    // A `name = "key"` line of the `[keys]` table
+    pub fn parse_setting(&mut self, name: &str, key: &str) -> Result<(), String> {
+        let target = BindingTarget::parse(name)
+            .ok_or_else(|| format!("'{name}' can't be bound to a key"))?;
+        self.bind(target, parse_key(key)?);
+        Ok(())
+    }
+
+    // The `[keys]` table's lines, without the header
+    pub fn toml_lines(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|(target, code)| {
+                key_name(*code).map(|key| format!("{} = \"{key}\"", target.name()))
+            })
+            .collect()
+    }
+}
+
+fn unshifted(code: KeyCode) -> KeyCode {
+    match code {
+        KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+        code => code,
+    }
+}
+
+// How keys are named in the settings file, e.g. "q", "space", "enter" or "f5". Keys with
+// no name can't be bound
+pub fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(' ') => "space",
+        KeyCode::Char(c) => return Some(c.to_lowercase().to_string()),
+        KeyCode::F(n) => return Some(format!("f{n}")),
+        KeyCode::Enter => "enter",
+        KeyCode::Esc => "esc",
+        KeyCode::Tab => "tab",
+        KeyCode::Backspace => "backspace",
+        KeyCode::Up => "up",
+        KeyCode::Down => "down",
+        KeyCode::Left => "left",
+        KeyCode::Right => "right",
+        KeyCode::Home => "home",
+        KeyCode::End => "end",
+        KeyCode::PageUp => "pageup",
+        KeyCode::PageDown => "pagedown",
+        KeyCode::Insert => "insert",
+        KeyCode::Delete => "delete",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+pub fn parse_key(name: &str) -> Result<KeyCode, String> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Ok(KeyCode::Char(c.to_ascii_lowercase()));
+    }
+    let code = match name.to_lowercase().as_str() {
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "insert" => KeyCode::Insert,
+        "delete" => KeyCode::Delete,
+        other => match other.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            Some(n @ 1..=24) => KeyCode::F(n),
+            _ => return Err(format!("'{name}' is not a key")),
+        },
+    };
+    Ok(code)
+}
@@ -0,0 +1,195 @@
+// Short runs of keypad input, recorded from live play with F3 and played back with F4, to get a
+// ROM into the same state over and over while debugging. Each ROM keeps its macros next to its
+// save states, as `{hash}-{name}.macro`, in the format of `--record`'s key lines, counted in
+// instructions from the start of the macro:
+//
+//     key 0 5 press
+//     key 240 5 release
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::input::{Chip8KeyEventKind, Chip8KeyState};
+use crate::replay::{self, ReplayEvent};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeyMacro {
+    events: Vec<(u64, u8, Chip8KeyEventKind)>, // By cycle since the start, in order
+}
+
+impl KeyMacro {
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    // How many keys it presses
+    pub fn presses(&self) -> usize {
+        self.events
+            .iter()
+            .filter(|&&(_, _, kind)| kind == Chip8KeyEventKind::Press)
+            .count()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut events = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            match replay::parse_event(line) {
+                Ok((cycle, ReplayEvent::Key(key, kind))) => events.push((cycle, key, kind)),
+                Ok(_) => return Err(format!("line {}: macros only have keys", number + 1)),
+                Err(e) => return Err(format!("line {}: {e}", number + 1)),
+            }
+        }
+        events.sort_by_key(|&(cycle, _, _)| cycle);
+        Ok(Self { events })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = "# chip-8-emulator key macro\n".to_string();
+        for &(cycle, key, kind) in &self.events {
+            let kind = match kind {
+                Chip8KeyEventKind::Press => "press",
+                Chip8KeyEventKind::Release => "release",
+            };
+            text += &format!("key {cycle} {key:X} {kind}\n");
+        }
+        text
+    }
+}
+
+// A macro being recorded
+pub struct MacroRecording {
+    started: u64, // The cycle it started at
+    held: Chip8KeyState,
+    events: Vec<(u64, u8, Chip8KeyEventKind)>,
+}
+
+impl MacroRecording {
+    pub fn start(cycle: u64) -> Self {
+        Self {
+            started: cycle,
+            held: Chip8KeyState::default(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, cycle: u64, key: u8, kind: Chip8KeyEventKind) {
+        match kind {
+            Chip8KeyEventKind::Press => self.held.press(key),
+            Chip8KeyEventKind::Release => self.held.release(key),
+        }
+        self.events
+            .push((cycle.saturating_sub(self.started), key, kind));
+    }
+
+    // Keys still held at the end are released, so playing it back leaves none stuck down
+    pub fn finish(mut self, cycle: u64) -> KeyMacro {
+        let end = cycle.saturating_sub(self.started);
+        for key in 0..16 {
+            if self.held.is_key_pressed(key) {
+                self.events.push((end, key, Chip8KeyEventKind::Release));
+            }
+        }
+        KeyMacro {
+            events: self.events,
+        }
+    }
+}
+
+// A macro being played back
+pub struct MacroPlayback {
+    started: u64,
+    events: VecDeque<(u64, u8, Chip8KeyEventKind)>,
+}
+
+impl MacroPlayback {
+    pub fn start(key_macro: KeyMacro, cycle: u64) -> Self {
+        Self {
+            started: cycle,
+            events: key_macro.events.into(),
+        }
+    }
+
+    // The next key event, if it's due before instruction `cycle` runs
+    pub fn next_due(&mut self, cycle: u64) -> Option<(u8, Chip8KeyEventKind)> {
+        match self.events.front() {
+            Some(&(at, key, kind)) if self.started + at <= cycle => {
+                self.events.pop_front();
+                Some((key, kind))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+// Where a ROM's macros are kept, and the one F3 and F4 use
+pub struct MacroStore {
+    dir: PathBuf,
+    rom_hash: u64,
+    name: String,
+}
+
+impl MacroStore {
+    pub const DEFAULT_NAME: &str = "default";
+
+    pub fn new(dir: PathBuf, rom_hash: u64, name: String) -> Self {
+        Self {
+            dir,
+            rom_hash,
+            name,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}-{}.macro", self.rom_hash, self.name))
+    }
+
+    // Returns the bytes written, for the disk limit
+    pub fn save(&self, key_macro: &KeyMacro) -> io::Result<u64> {
+        fs::create_dir_all(&self.dir)?;
+        let text = key_macro.to_text();
+        fs::write(self.path(), &text)?;
+        Ok(text.len() as u64)
+    }
+
+    // The macro, or None if it hasn't been recorded
+    pub fn load(&self) -> io::Result<Option<KeyMacro>> {
+        let text = match fs::read_to_string(self.path()) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        KeyMacro::parse(&text)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+// `--macro NAME`, which becomes part of a file name
+pub fn parse_name(name: &str) -> Result<String, String> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(name.to_string())
+    } else {
+        Err(format!(
+            "'{name}' is not a macro name, use letters, digits, '-' and '_'"
+        ))
+    }
+}
@@ -0,0 +1,61 @@
+// CHIP-8 emulator core. The `chip-8-emulator` binary is a terminal frontend on top of it, and
+// `Emulator` is the way in for other frontends, stepping the machine without the terminal.
+// The terminal frontend's modules, and its async runtime and audio, are behind the `tui`
+// feature, which is on by default
+
+pub mod annotations;
+pub mod audio;
+pub mod buildinfo;
+pub mod chip8;
+pub mod cpu;
+#[cfg(feature = "tui")]
+pub mod crash;
+pub mod decoder;
+pub mod diagnostics;
+pub mod emulator;
+pub mod error;
+#[cfg(feature = "tui")]
+pub mod exitcode;
+pub mod framebuffer;
+pub mod frontend;
+pub mod hardware;
+pub mod headless;
+pub mod icache;
+pub mod input;
+#[cfg(feature = "tui")]
+pub mod integration;
+pub mod keybindings;
+pub mod keymacro;
+pub mod limits;
+pub mod machinestate;
+mod macros;
+pub mod minitoml;
+pub mod paths;
+pub mod primitive;
+pub mod quirks;
+pub mod recorder;
+#[cfg(feature = "tui")]
+pub mod remap;
+pub mod replay;
+pub mod rewind;
+pub mod romconfig;
+pub mod rplflags;
+pub mod savestate;
+#[cfg(feature = "tui")]
+pub mod scheduler;
+#[cfg(feature = "tui")]
+pub mod screen;
+pub mod settings;
+pub mod stats;
+#[cfg(feature = "tui")]
+pub mod termwriter;
+pub mod testsuite;
+pub mod timing;
+pub mod util;
+pub mod vip;
+
+pub use cpu::CPU;
+pub use decoder::{Decoder, disasm};
+pub use emulator::Emulator;
+pub use error::Chip8Error;
+pub use hardware::Hardware;
@@ -0,0 +1,110 @@
+// Caps on how much a ROM may run and write, so batch runs over untrusted ROMs can't hang or
+// fill the disk. Given on the command line as e.g. `cycles=1000000,time=30s,disk=100M`
+
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExecutionLimits {
+    pub cycles: Option<u64>,     // Instructions executed
+    pub time: Option<Duration>,  // Wall-clock time since the ROM was loaded
+    pub disk_bytes: Option<u64>, // Written by recordings, savestates, macros and flags
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitExceeded {
+    Cycles(u64),
+    Time(Duration),
+    Disk(u64),
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use LimitExceeded::*;
+        match self {
+            Cycles(limit) => write!(f, "Cycle limit of {limit} instructions reached"),
+            Time(limit) => write!(f, "Time limit of {:.1}s reached", limit.as_secs_f64()),
+            Disk(limit) => write!(f, "Disk limit of {limit} bytes reached"),
+        }
+    }
+}
+
+impl ExecutionLimits {
+    // Parses comma separated `name=value` limits. Times take an `ms`, `s` or `m` suffix
+    // (seconds by default), and sizes a `K`, `M` or `G` suffix (bytes by default)
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut limits = Self::default();
+        for limit in text.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+            let (name, value) = limit
+                .split_once('=')
+                .ok_or_else(|| format!("'{limit}' is not a `name=value` limit"))?;
+            match name.trim() {
+                "cycles" => limits.cycles = Some(Self::parse_count(value)?),
+                "time" => limits.time = Some(Self::parse_duration(value)?),
+                "disk" => limits.disk_bytes = Some(Self::parse_size(value)?),
+                other => return Err(format!("unknown limit '{other}' (cycles, time, or disk)")),
+            }
+        }
+        Ok(limits)
+    }
+
+    pub fn check_cycles(&self, cycles: u64) -> Result<(), LimitExceeded> {
+        match self.cycles {
+            Some(limit) if cycles >= limit => Err(LimitExceeded::Cycles(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_time(&self, elapsed: Duration) -> Result<(), LimitExceeded> {
+        match self.time {
+            Some(limit) if elapsed >= limit => Err(LimitExceeded::Time(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_disk(&self, bytes: u64) -> Result<(), LimitExceeded> {
+        match self.disk_bytes {
+            Some(limit) if bytes >= limit => Err(LimitExceeded::Disk(limit)),
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_count(value: &str) -> Result<u64, String> {
+        value
+            .trim()
+            .parse()
+            .map_err(|e| format!("'{value}' is not a count: {e}"))
+    }
+
+    fn parse_duration(value: &str) -> Result<Duration, String> {
+        let value = value.trim();
+        let (number, scale) = if let Some(ms) = value.strip_suffix("ms") {
+            (ms, 0.001)
+        } else if let Some(secs) = value.strip_suffix('s') {
+            (secs, 1.0)
+        } else if let Some(mins) = value.strip_suffix('m') {
+            (mins, 60.0)
+        } else {
+            (value, 1.0)
+        };
+        let number: f64 = number
+            .parse()
+            .map_err(|e| format!("'{value}' is not a duration: {e}"))?;
+        Duration::try_from_secs_f64(number * scale)
+            .map_err(|e| format!("'{value}' is not a duration: {e}"))
+    }
+
+    fn parse_size(value: &str) -> Result<u64, String> {
+        let value = value.trim();
+        let (number, scale) = match value.to_ascii_uppercase().chars().last() {
+            Some('K') => (&value[..value.len() - 1], 1 << 10),
+            Some('M') => (&value[..value.len() - 1], 1 << 20),
+            Some('G') => (&value[..value.len() - 1], 1 << 30),
+            _ => (value, 1),
+        };
+        let number: u64 = number
+            .parse()
+            .map_err(|e| format!("'{value}' is not a size: {e}"))?;
+        Ok(number.saturating_mul(scale))
+    }
+}
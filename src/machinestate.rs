@@ -0,0 +1,125 @@
+// A copy of the whole machine, and what changed between two of them: registers, runs of
+// memory, and pixels. Shared by the debugger's frame diff and replays, which say what changed
+// since the last frame that matched the recording
+
+use std::fmt;
+use std::ops::Range;
+
+use crate::cpu::CPU;
+use crate::framebuffer::FrameBuffer;
+
+#[derive(Clone)]
+pub struct MachineState {
+    pub cpu: CPU,
+    pub framebuffer: FrameBuffer,
+}
+
+impl MachineState {
+    pub fn new(cpu: &CPU, framebuffer: &FrameBuffer) -> Self {
+        Self {
+            cpu: cpu.clone(),
+            framebuffer: framebuffer.clone(),
+        }
+    }
+
+    // What changed going from this state to `other`
+    pub fn diff(&self, other: &MachineState) -> StateDiff {
+        let (a, b) = (&self.cpu, &other.cpu);
+        StateDiff {
+            pc: changed(a.get_pc(), b.get_pc()),
+            index: changed(a.get_index(), b.get_index()),
+            registers: a
+                .all_register_val()
+                .into_iter()
+                .zip(b.all_register_val())
+                .enumerate()
+                .filter(|(_, (x, y))| x != y)
+                .map(|(reg, (x, y))| (reg as u8, x, y))
+                .collect(),
+            delay_timer: changed(a.get_delay_timer(), b.get_delay_timer()),
+            sound_timer: changed(a.get_sound_timer(), b.get_sound_timer()),
+            stack: (a.call_stack() != b.call_stack())
+                .then(|| (a.call_stack().to_vec(), b.call_stack().to_vec())),
+            memory: changed_ranges(a.memory(), b.memory()),
+            pixels: self.framebuffer.changed_pixels(&other.framebuffer),
+        }
+    }
+}
+
+// Each change is (before, after)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateDiff {
+    pub pc: Option<(u16, u16)>,
+    pub index: Option<(u16, u16)>,
+    pub registers: Vec<(u8, u8, u8)>, // Register number, before, after
+    pub delay_timer: Option<(u8, u8)>,
+    pub sound_timer: Option<(u8, u8)>,
+    pub stack: Option<(Vec<u16>, Vec<u16>)>,
+    pub memory: Vec<Range<usize>>, // Runs of changed bytes, in order
+    pub pixels: Vec<(u8, u8)>,     // Changed pixels, row by row
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+// One line, e.g. "PC 0x204 -> 0x20A, V3 00 -> 01, memory 0x300-0x302, 12 pixels"
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some((a, b)) = self.pc {
+            parts.push(format!("PC {a:#05X} -> {b:#05X}"));
+        }
+        if let Some((a, b)) = self.index {
+            parts.push(format!("I {a:#05X} -> {b:#05X}"));
+        }
+        for &(reg, a, b) in &self.registers {
+            parts.push(format!("V{reg:X} {a:02X} -> {b:02X}"));
+        }
+        if let Some((a, b)) = self.delay_timer {
+            parts.push(format!("DT {a} -> {b}"));
+        }
+        if let Some((a, b)) = self.sound_timer {
+            parts.push(format!("ST {a} -> {b}"));
+        }
+        if let Some((ref a, ref b)) = self.stack {
+            parts.push(format!("stack depth {} -> {}", a.len(), b.len()));
+        }
+        for range in &self.memory {
+            parts.push(match range.len() {
+                1 => format!("memory {:#05X}", range.start),
+                _ => format!("memory {:#05X}-{:#05X}", range.start, range.end - 1),
+            });
+        }
+        if !self.pixels.is_empty() {
+            parts.push(format!("{} pixels", self.pixels.len()));
+        }
+        if parts.is_empty() {
+            write!(f, "no changes")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+fn changed<T: PartialEq>(a: T, b: T) -> Option<(T, T)> {
+    (a != b).then_some((a, b))
+}
+
+// Runs of bytes that differ, over the shorter of the two. Memory of different sizes differs
+// from the end of the shorter to the end of the longer
+fn changed_ranges(a: &[u8], b: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for addr in (0..a.len().min(b.len())).filter(|&addr| a[addr] != b[addr]) {
+        match ranges.last_mut() {
+            Some(range) if range.end == addr => range.end += 1,
+            _ => ranges.push(addr..addr + 1),
+        }
+    }
+    if a.len() != b.len() {
+        ranges.push(a.len().min(b.len())..a.len().max(b.len()));
+    }
+    ranges
+}
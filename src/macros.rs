@@ -34,3 +34,111 @@ macro_rules! validated_struct {
         }
     };
 }
+
+// Generates the decoder, the encoder and the opcode listing from one table, so they can't
+// drift apart. Each row is
+//
+//     VALUE / MASK, "PATTERN", "MNEMONIC", [versions] => Variant(operands);
+//
+// where a word is the instruction if `word & MASK == VALUE`, rows are tried in order, and each
+// operand is a field of the word (`x`, `y`, `n`, `nn`, `nnn` or `p`) or a `{constant}`
+#[macro_export]
+macro_rules! opcode_table {
+    ($(
+        $value:literal / $mask:literal, $pattern:literal, $mnemonic:literal, [$($version:ident),*]
+            => $variant:ident $(($($arg:tt),*))?;
+    )*) => {
+        pub const OPCODES: &[Opcode] = &[$(
+            Opcode {
+                value: $value,
+                mask: $mask,
+                pattern: $pattern,
+                mnemonic: $mnemonic,
+                versions: &[$(Chip8Version::$version),*],
+            },
+        )*];
+
+        impl Decoder {
+            pub fn decode(raw: &RawInstruction) -> Option<Instruction> {
+                let word = raw.word();
+                $(
+                    if word & $mask == $value {
+                        return Some(Instruction::$variant $(($($crate::opcode_table!(@decode raw $arg)),*))?);
+                    }
+                )*
+                None
+            }
+
+            // The inverse of `decode`. Returns None for instructions with no encoding
+            pub fn encode(inst: &Instruction) -> Option<RawInstruction> {
+                let word = match inst {
+                    $(
+                        Instruction::$variant $(($($crate::opcode_table!(@pattern $arg)),*))? => {
+                            $value $($(| $crate::opcode_table!(@encode $arg))*)?
+                        }
+                    )*
+                    #[allow(unreachable_patterns)]
+                    _ => return None,
+                };
+                Some(RawInstruction::from_word(word))
+            }
+        }
+    };
+
+    // Reads an operand out of the raw instruction
+    (@decode $raw:ident x) => { $raw.x() };
+    (@decode $raw:ident y) => { $raw.y() };
+    (@decode $raw:ident n) => { $raw.n() };
+    (@decode $raw:ident nn) => { $raw.nn() };
+    (@decode $raw:ident nnn) => { $raw.nnn() };
+    (@decode $raw:ident p) => { $raw.p() };
+    (@decode $raw:ident {$($constant:tt)*}) => { $($constant)* };
+
+    // Matches an operand, binding fields to their own name
+    (@pattern {$($constant:tt)*}) => { $($constant)* };
+    (@pattern $field:ident) => { $field };
+
+    // Puts a bound operand back into its place in the word
+    (@encode {$($constant:tt)*}) => { 0 };
+    (@encode $field:ident) => { $crate::opcode_table!(@shift $field $field) };
+    (@shift x $value:ident) => { ($value.get() as u16) << 8 };
+    (@shift y $value:ident) => { ($value.get() as u16) << 4 };
+    (@shift n $value:ident) => { $value.get() as u16 };
+    (@shift nn $value:ident) => { $value.get() as u16 };
+    (@shift nnn $value:ident) => { $value.get() };
+    (@shift p $value:ident) => { ($value.get() as u16) << 8 };
+}
+
+// Defines a test that runs a ROM headless for a number of frames, then compares the screen
+// with a golden bitmap from `FrameBuffer::to_text`:
+//
+//     golden_rom!(ibm_logo, include_bytes!("roms/ibm.ch8"), frames = 60,
+//                 expected = include_str!("golden/ibm.txt"));
+//
+// An optional `config = ...` sets the `HardwareExecutionConfig`, which is the default otherwise
+#[macro_export]
+macro_rules! golden_rom {
+    ($name:ident, $rom:expr, frames = $frames:expr, expected = $expected:expr $(,)?) => {
+        $crate::golden_rom!(
+            $name,
+            $rom,
+            frames = $frames,
+            expected = $expected,
+            config = $crate::hardware::HardwareExecutionConfig::default()
+        );
+    };
+    ($name:ident, $rom:expr, frames = $frames:expr, expected = $expected:expr, config = $config:expr $(,)?) => {
+        #[test]
+        fn $name() {
+            let rom: &[u8] = $rom;
+            let mut core = $crate::headless::Chip8Core::new($config);
+            core.load_rom(rom).expect("ROM should fit in memory");
+            core.run_frames($frames, &$crate::headless::InputSchedule::new());
+            assert_eq!(
+                core.emulator.hardware.framebuffer.to_text().trim_end(),
+                $expected.trim_end(),
+                "screen differs from the golden bitmap"
+            );
+        }
+    };
+}
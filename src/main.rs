@@ -1,106 +1,756 @@
 use std::{
-    fs,
-    io::{self, Write},
-    panic::{self, PanicHookInfo},
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
-mod chip8;
-mod cpu;
-mod decoder;
-mod hardware;
-mod input;
-mod macros;
-mod primitive;
-mod scheduler;
-mod screen;
-mod util;
-
-use chip8::*;
-use clap::Parser;
+use chip_8_emulator::annotations::MemoryAnnotations;
+use chip_8_emulator::audio::BellMode;
+use chip_8_emulator::chip8::*;
+use chip_8_emulator::exitcode::ExitCode;
+use chip_8_emulator::frontend::ScreenColor;
+use chip_8_emulator::hardware::{
+    HaltAction, HardwareExecutionConfig, InvalidAction, MemoryProtection,
+};
+use chip_8_emulator::headless::{Chip8Core, FrameHash, InputSchedule};
+use chip_8_emulator::keymacro::{self, MacroStore};
+use chip_8_emulator::limits::ExecutionLimits;
+use chip_8_emulator::quirks::{self, Quirks};
+use chip_8_emulator::replay::{InputRecorder, InputReplay};
+use chip_8_emulator::romconfig::RomConfig;
+use chip_8_emulator::rplflags::RplFlags;
+use chip_8_emulator::savestate::SaveSlots;
+use chip_8_emulator::screen::TerminalRenderer;
+use chip_8_emulator::settings::Settings;
+use chip_8_emulator::stats::RomStats;
+use chip_8_emulator::{
+    buildinfo, crash, input, integration, minitoml, paths, recorder, remap, testsuite, util,
+};
+use clap::{Parser, ValueEnum};
 
-use crate::screen::ScreenColor;
+// How long a ROM's controls are shown over the display after loading
+const KEY_HINTS_DURATION: Duration = Duration::from_secs(5);
 
 #[derive(Parser)]
 #[command(name = "chip8-emulator")]
 #[command(about = "A CHIP-8 emulator written in Rust")]
+#[command(after_help = ExitCode::HELP)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(help = "Path to the CHIP-8 ROM file")]
-    rom_file: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(required = true, help = "Path to the CHIP-8 ROM file")]
+    rom_file: Option<String>,
+
+    #[arg(
+        long,
+        exclusive = true,
+        action = clap::ArgAction::SetTrue,
+        help = "Print the version, features, supported variants and quirk defaults as JSON"
+    )]
+    version_info: bool,
 
     #[arg(long, action = clap::ArgAction::SetTrue, help = "Dump the HEX instructions in the ROM")]
     dump_inst: bool,
 
+    #[arg(
+        long,
+        default_value = "0",
+        value_parser = parse_number,
+        help = "Byte offset into the ROM to start dumping from (odd offsets read misaligned words)"
+    )]
+    dump_offset: usize,
+
+    #[arg(long, value_parser = parse_number, help = "Number of bytes to dump")]
+    dump_length: Option<usize>,
+
+    #[arg(
+        long,
+        default_value_t = InvalidDataPolicy::Data,
+        help = "How to dump words that aren't instructions: data (.dw) or invalid"
+    )]
+    dump_invalid: InvalidDataPolicy,
+
     #[arg(long, action = clap::ArgAction::SetTrue, help = "Enable debug mode showing CPU state each cycle")]
     debug: bool,
 
     #[arg(
         long,
-        default_value_t = input::KeyboardLayout::Qwerty,
-        help = "Keyboard layout: qwerty, natural, or sequential"
+        help = "Keyboard layout: qwerty, natural, sequential, azerty, dvorak, colemak, or numpad [default: qwerty, or config.toml]"
+    )]
+    layout: Option<input::KeyboardLayout>,
+
+    #[arg(
+        long,
+        help = "CHIP-8 version: cosmac, chip48, superchip, or xochip [default: cosmac, or the ROM's .toml]"
     )]
-    layout: input::KeyboardLayout,
+    version: Option<Chip8Version>,
 
     #[arg(
         long,
-        default_value_t = Chip8Version::Cosmac,
-        help = "CHIP-8 version: cosmac, chip48, or superchip"
+        visible_alias = "ipf",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with = "cpu_hz",
+        help = "Instructions run per 60Hz frame, as Octo sets the speed (e.g. 15 for SUPER-CHIP games) [default: 500Hz, or the ROM's .toml, or config.toml]"
     )]
-    version: Chip8Version,
+    cycles_per_frame: Option<u32>,
+
+    #[arg(
+        long,
+        visible_alias = "speed",
+        value_name = "HZ",
+        value_parser = minitoml::parse_hz,
+        help = "Instructions run per second, in place of --cycles-per-frame"
+    )]
+    cpu_hz: Option<f64>,
+
+    #[arg(long, help = "Color of the emulation [default: green, or config.toml]")]
+    color: Option<ScreenColor>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        help = "Drawing in high resolution sets VF to the number of sprite rows with a collision, as on SUPER-CHIP. Same as --quirk collision_rows=on"
+    )]
+    collision_rows: bool,
+
+    #[arg(
+        long = "quirk",
+        value_name = "NAME=on|off",
+        value_parser = quirks::parse_override,
+        help = "Turn one of the --version's quirks on or off, e.g. vf_reset=off. Can be repeated"
+    )]
+    quirks: Vec<(String, bool)>,
+
+    #[arg(
+        long,
+        default_value_t = Accuracy::Balanced,
+        help = "Accuracy preset for the clock and display: fast, balanced, or cycle-accurate"
+    )]
+    accuracy: Accuracy,
+
+    #[arg(
+        long,
+        help = "What the CPU clock does when it falls behind: skip, burst, or cap (catch up a little). Overrides --accuracy"
+    )]
+    missed_ticks: Option<MissedTickPolicy>,
+
+    #[arg(
+        long,
+        value_name = "on|off",
+        value_parser = quirks::parse_on_off,
+        help = "Whether drawing waits for the next display refresh, as on the COSMAC VIP. Overrides --accuracy"
+    )]
+    display_wait: Option<bool>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        help = "Emulate the COSMAC VIP machine code routines hybrid ROMs call with 0NNN (display and tone control)"
+    )]
+    vip_routines: bool,
+
+    #[arg(
+        long,
+        default_value_t = HaltAction::Run,
+        requires_if("break", "debug"),
+        help = "What to do when the ROM halts in a loop: run, exit, or break (pause, needs --debug)"
+    )]
+    on_halt: HaltAction,
+
+    #[arg(
+        long,
+        default_value_t = InvalidAction::Halt,
+        help = "What to do at an instruction the version can't run: halt (with an error, to look at the machine), skip, or panic (stop with a fault report)"
+    )]
+    on_invalid: InvalidAction,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        help = "Fault when the PC becomes odd or runs off the end of memory, rather than running on"
+    )]
+    pc_guard: bool,
+
+    #[arg(
+        long,
+        value_name = "ACTION",
+        help = "Catch writes to the font and below 0x200, which are almost always bugs: log (to the log panel) or fault"
+    )]
+    protect_memory: Option<MemoryProtection>,
+
+    #[arg(
+        long,
+        value_name = "ADDR",
+        value_parser = minitoml::parse_font_addr,
+        help = "Quirk: where the font is loaded, e.g. 0x000 for ROMs that hardcode it there [default: 0x050, or the ROM's .toml]"
+    )]
+    font_addr: Option<u16>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        value_name = "MS",
+        help = "Shortest audible beep in milliseconds, shorter beeps are stretched"
+    )]
+    min_beep_ms: u64,
+
+    #[arg(
+        long,
+        help = "How the beep is played: visual, audio, both, or none [default: audio, or visual without an audio device]"
+    )]
+    bell: Option<BellMode>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Where to keep save states [default: the savestates directory in `paths`]"
+    )]
+    state_dir: Option<PathBuf>,
+
+    #[arg(
+        long = "macro",
+        value_name = "NAME",
+        value_parser = keymacro::parse_name,
+        default_value = MacroStore::DEFAULT_NAME,
+        help = "The ROM's macro that F3 records and F4 plays"
+    )]
+    macro_name: String,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Memory labels for the debugger, one `START-END LABEL` per line"
+    )]
+    annotations: Option<String>,
+
+    #[arg(
+        long,
+        value_parser = ExecutionLimits::parse,
+        value_name = "LIMITS",
+        help = "Stop with exit code 5 and a fault report past any of these, e.g. cycles=1000000,time=30s,disk=100M"
+    )]
+    limits: Option<ExecutionLimits>,
+
+    #[arg(long, action = clap::ArgAction::SetTrue, help = "Print input latency statistics on exit")]
+    latency_report: bool,
 
     #[arg(
         long,
-        default_value_t = ScreenColor::Green,
-        help = "Color of the emulation"
+        value_name = "FILE",
+        help = "Record a video of the run to FILE using ffmpeg (e.g. out.mp4)"
     )]
-    color: ScreenColor,
+    record_video: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        help = "Release keys held this long without repeating, for terminals that don't report releases. 0 turns it off [default: 600, or off if releases are reported]"
+    )]
+    key_timeout: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Save to the ROM's autosave slot every SECONDS, and on quitting [default: off]"
+    )]
+    autosave: Option<u64>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        conflicts_with_all = ["replay", "record"],
+        help = "Continue from the ROM's autosave, if it has one"
+    )]
+    autoresume: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "replay",
+        help = "Seed for the Random instruction, so runs are repeatable [default: random]"
+    )]
+    seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with = "replay",
+        help = "Record the keys pressed to FILE, to play them back with --replay (e.g. run.c8rec)"
+    )]
+    record: Option<PathBuf>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        requires = "record",
+        help = "Also record a checksum of the machine every frame, so --replay can tell where it diverged"
+    )]
+    checksums: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Play back keys recorded with --record, exactly as they were pressed"
+    )]
+    replay: Option<PathBuf>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        conflicts_with_all = ["debug", "replay", "record", "record_video"],
+        help = "Run without the terminal UI as fast as possible until the ROM halts or exits, then print the hash of the screen"
+    )]
+    headless: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "headless",
+        help = "With --headless, stop after N instructions, rather than ten minutes' worth at --cpu-hz"
+    )]
+    max_cycles: Option<u64>,
+
+    #[arg(
+        long,
+        action = clap::ArgAction::SetTrue,
+        requires = "headless",
+        help = "With --headless, also print the final screen"
+    )]
+    dump_screen: bool,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    #[command(about = "Print where configuration, saves and logs are kept")]
+    Paths,
+    #[command(about = "Choose the keys for the CHIP-8 keypad and commands, saved to config.toml")]
+    Remap,
+    #[command(about = "Add the emulator to desktop launchers and open .ch8 files with it (Linux)")]
+    InstallIntegration {
+        #[arg(
+            long,
+            action = clap::ArgAction::SetTrue,
+            help = "Print the desktop entry and MIME type instead of installing them"
+        )]
+        print: bool,
+    },
+    #[command(
+        about = "Run test ROMs, such as Timendus' suite, on each version and compare the screens they leave with their pass screens"
+    )]
+    Test {
+        #[arg(required = true, help = "Paths to the test ROMs")]
+        roms: Vec<PathBuf>,
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "Versions to test on, separated by commas [default: all of them]"
+        )]
+        versions: Vec<Chip8Version>,
+        #[arg(
+            long,
+            value_name = "N",
+            default_value_t = 300,
+            help = "Frames to run each ROM for before looking at the screen"
+        )]
+        frames: usize,
+        #[arg(
+            long,
+            action = clap::ArgAction::SetTrue,
+            help = "Save the screens left as the pass screens, next to each ROM as ROM.VERSION.pass"
+        )]
+        bless: bool,
+    },
+}
+
+fn main() -> io::Result<()> {
+    crash::install_hook();
+    // The hook has written the crash report by the time a panic gets here
+    std::panic::catch_unwind(run).unwrap_or_else(|_| ExitCode::Fault.exit())
 }
 
 #[tokio::main]
-async fn main() -> io::Result<()> {
-    panic::set_hook(Box::new(panic_handler));
+async fn run() -> io::Result<()> {
+    crash::install_interrupt_handler();
 
-    let args = Args::parse();
-    let bytes = fs::read(args.rom_file)?;
+    let args = Args::try_parse().unwrap_or_else(|e| {
+        // Clap's own usage error code would clash with the ROM load error
+        let _ = e.print();
+        if e.use_stderr() {
+            ExitCode::Error.exit()
+        } else {
+            ExitCode::Success.exit()
+        }
+    });
+    if args.version_info {
+        println!("{}", buildinfo::version_info_json());
+        return Ok(());
+    }
+    match args.command {
+        Some(Command::Paths) => {
+            for (name, path) in paths::all() {
+                println!("{name:<12} {}", path.display());
+            }
+            return Ok(());
+        }
+        Some(Command::Remap) => {
+            let path = Settings::path();
+            let settings = Settings::load(&path)?;
+            let layout = args
+                .layout
+                .or(settings.layout)
+                .unwrap_or(input::KeyboardLayout::Qwerty);
+            if let Some(bindings) = remap::run(layout, settings.keys.unwrap_or_default())? {
+                Settings::save_key_bindings(&path, &bindings)?;
+                println!("Saved to {}", path.display());
+            }
+            return Ok(());
+        }
+        Some(Command::InstallIntegration { print: true }) => {
+            let exe = std::env::current_exe()?;
+            print!("{}", integration::desktop_entry(&exe));
+            println!();
+            print!("{}", integration::mime_package());
+            return Ok(());
+        }
+        Some(Command::InstallIntegration { print: false }) => {
+            for path in integration::install()? {
+                println!("Installed {}", path.display());
+            }
+            return Ok(());
+        }
+        Some(Command::Test {
+            roms,
+            versions,
+            frames,
+            bless,
+        }) => run_tests(&roms, versions, frames, bless),
+        None => {}
+    }
+    let rom_file = args
+        .rom_file
+        .expect("clap requires a ROM without a subcommand");
+    let bytes = fs::read(&rom_file).unwrap_or_else(|e| {
+        eprintln!("Could not read the ROM {rom_file}: {e}");
+        ExitCode::RomLoadError.exit()
+    });
 
     if args.dump_inst {
-        Chip8::dump_inst(&bytes);
+        let options = DumpOptions {
+            offset: args.dump_offset,
+            length: args.dump_length,
+            invalid: args.dump_invalid,
+        };
+        if let Err(e) = Chip8::dump_inst(&bytes, &options) {
+            eprintln!("Could not dump {rom_file}: {e}");
+            ExitCode::Error.exit();
+        }
         return Ok(());
     }
+    // The user's settings, which are the defaults for the flags below. Edits to the file are
+    // applied while running
+    let settings_path = Settings::path();
+    let (settings, settings_error) = match Settings::load(&settings_path) {
+        Ok(settings) => (settings, None),
+        Err(e) => (Settings::default(), Some(e)),
+    };
+
     // Create input configuration
     let input_config = input::InputConfig {
-        layout: args.layout,
+        layout: args
+            .layout
+            .or(settings.layout)
+            .unwrap_or(input::KeyboardLayout::Qwerty),
+        bindings: settings.keys.clone().unwrap_or_default(),
+        key_timeout: args.key_timeout.map(Duration::from_millis),
         ..Default::default()
     };
 
     // Create input handler
     let input_handler = input::KeyEventHandler::new(input_config);
 
+    // Settings shipped next to the ROM, which command line flags take precedence over
+    let (rom_config, rom_config_error) = match RomConfig::load_for(Path::new(&rom_file)) {
+        Ok(rom_config) => (rom_config.unwrap_or_default(), None),
+        Err(e) => (RomConfig::default(), Some(e)),
+    };
+
+    let version = args
+        .version
+        .or(rom_config.version)
+        .unwrap_or(Chip8Version::Cosmac);
+    let mut quirks = Quirks::for_version(&version);
+    quirks.collision_rows |= args.collision_rows || rom_config.collision_rows;
+    quirks.display_wait = args
+        .display_wait
+        .unwrap_or(quirks.display_wait || rom_config.display_wait || args.accuracy.display_wait());
+    for (name, on) in rom_config.quirks.iter().chain(&args.quirks) {
+        quirks.set(name, *on).map_err(io::Error::other)?;
+    }
+
     // Create emulator
     let config = Chip8Config {
-        version: args.version,
+        version,
+        cpu_hz: args
+            .cpu_hz
+            .or(args
+                .cycles_per_frame
+                .map(|cycles| cycles as f64 * Chip8::SCREEN_HZ))
+            .or(rom_config.cpu_hz)
+            .or(settings.cpu_hz)
+            .unwrap_or(Chip8::CPU_FREQ_HZ),
         debug: args.debug,
-        color: args.color,
+        color: args.color.or(settings.color).unwrap_or(ScreenColor::Green),
+        quirks,
+        missed_ticks: args
+            .missed_ticks
+            .unwrap_or_else(|| args.accuracy.missed_ticks()),
+        vip_routines: args.vip_routines || rom_config.vip_routines,
+        on_halt: args.on_halt,
+        on_invalid: args.on_invalid,
+        min_beep: Duration::from_millis(args.min_beep_ms),
+        bell: args.bell,
+        limits: args.limits.unwrap_or_default(),
+        pc_guard: args.pc_guard,
+        memory_protection: args.protect_memory,
+        font_addr: args
+            .font_addr
+            .or(rom_config.font_addr)
+            .unwrap_or(Chip8::FONT_START_ADDR),
+        settings_path: Some(settings_path),
     };
+    if args.headless {
+        run_headless(
+            &config,
+            &bytes,
+            args.seed,
+            args.max_cycles,
+            args.dump_screen,
+        );
+    }
     let mut chip8 = Chip8::new(config, input_handler);
-    chip8.load_rom(&bytes).expect("Could not load the ROM");
-    chip8.run().await;
+    if let Err(e) = chip8.load_rom(&bytes) {
+        eprintln!("Could not load the ROM {rom_file}: {e}");
+        ExitCode::RomLoadError.exit();
+    }
+    let mut screen = TerminalRenderer::new(chip8.config.color);
+    chip8
+        .hardware
+        .set_show_diff(settings.show_diff.unwrap_or(false));
+    if let Some(e) = settings_error {
+        screen.log.push(format!("Could not load the settings: {e}"));
+    }
+    if let Some(e) = rom_config_error {
+        screen
+            .log
+            .push(format!("Could not load the ROM's settings: {e}"));
+    }
+    if !rom_config.key_hints.is_empty() {
+        let key_hints = chip8.input.describe_keys(&rom_config.key_hints);
+        screen.set_key_hints(key_hints);
+        screen.show_key_hints(KEY_HINTS_DURATION);
+    }
+    if let Some(path) = args.annotations {
+        let annotations = MemoryAnnotations::load(Path::new(&path))?;
+        chip8.hardware.set_annotations(annotations.clone());
+        screen.annotations = annotations;
+    }
+    if let Some(path) = args.record_video {
+        let recorder = recorder::VideoRecorder::start(&path, Chip8::SCREEN_HZ)?;
+        chip8.hardware.set_video_recorder(recorder);
+    }
+    if let Some(seed) = args.seed {
+        chip8.hardware.set_seed(seed);
+    }
+    let rom_hash = util::stable_hash(&bytes);
+    if let Some(ref path) = args.record {
+        let seed = args.seed.unwrap_or_else(rand::random);
+        let quirks = &chip8.config.quirks;
+        let recorder = InputRecorder::create(path, rom_hash, quirks, seed, args.checksums)?;
+        chip8.hardware.set_input_recorder(recorder, seed);
+    }
+    if let Some(ref path) = args.replay {
+        chip8
+            .hardware
+            .set_input_replay(InputReplay::load(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path:?}: {e}")))?;
+    }
+
+    // Track how often and how long this ROM has been played
+    crash::set_run_info(rom_hash, &chip8.config);
+    chip8.hardware.enable_crash_snapshots();
+    chip8.hardware.set_rpl_flags(RplFlags::load(rom_hash));
+    let state_dir = args.state_dir.clone().unwrap_or_else(paths::savestates_dir);
+    chip8.hardware.set_save_slots(SaveSlots::new(
+        state_dir.clone(),
+        rom_hash,
+        chip8.config.quirks,
+    ));
+    chip8.hardware.set_macro_store(MacroStore::new(
+        state_dir,
+        rom_hash,
+        args.macro_name.clone(),
+    ));
+    if let Some(seconds) = args.autosave {
+        chip8
+            .hardware
+            .set_autosave_interval(Duration::from_secs(seconds));
+    }
+    if args.autoresume {
+        chip8.hardware.resume_autosave();
+    }
+    let mut stats = RomStats::load(rom_hash);
+    stats.launches += 1;
+    screen.set_subtitle(match rom_config.title {
+        Some(title) => format!("{title} | {}", stats.summary()),
+        None => stats.summary(),
+    });
+    let started = Instant::now();
+
+    chip8.run(&mut screen).await;
+    chip8.hardware.autosave();
+    stats.playtime += started.elapsed();
+
+    // Leave the emulator's screen before reporting anything
+    let recorder = chip8.hardware.take_video_recorder();
+    let latency = chip8.hardware.latency.stats();
+    let limit_exceeded = chip8.hardware.limit_exceeded();
+    let fault = chip8.hardware.take_fault();
+    let divergence = chip8.hardware.replay_divergence();
+    drop(chip8);
+    drop(screen);
+    if let Some(recorder) = recorder
+        && let Err(e) = recorder.finish()
+    {
+        eprintln!("Could not save video recording: {e}");
+    }
+    if let Some(divergence) = divergence {
+        eprintln!("{divergence}");
+    }
+    if args.latency_report {
+        println!("Input latency: {latency}");
+    }
+    if let Err(e) = stats.save(rom_hash) {
+        eprintln!("Could not save play statistics: {e}");
+    }
+    if let Some(limit) = limit_exceeded {
+        eprintln!("{limit}");
+        match crash::write_fault_report(&limit.to_string()) {
+            Ok(path) => eprintln!("Fault report written to {}", path.display()),
+            Err(e) => eprintln!("Could not write fault report: {e}"),
+        }
+        ExitCode::LimitExceeded.exit();
+    }
+    if let Some(fault) = fault {
+        eprintln!("{fault}");
+        match crash::write_fault_report(&fault.to_string()) {
+            Ok(path) => eprintln!("Fault report written to {}", path.display()),
+            Err(e) => eprintln!("Could not write fault report: {e}"),
+        }
+        ExitCode::Fault.exit();
+    }
 
     Ok(())
 }
 
-fn panic_handler(panic_info: &PanicHookInfo) {
-    let panic_msg = format!(
-        "PANIC:
-  {}\n",
-        panic_info
+// Emulated time a headless run gets without --max-cycles
+const HEADLESS_SECONDS: u64 = 10 * 60;
+
+// `--headless`: runs the ROM without a terminal or real-time pacing, and prints how the screen
+// was left, for CI and benchmarks. A halt ends the run, as nothing can get the ROM out of it
+fn run_headless(
+    config: &Chip8Config,
+    bytes: &[u8],
+    seed: Option<u64>,
+    max_cycles: Option<u64>,
+    dump_screen: bool,
+) -> ! {
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        on_halt: HaltAction::Exit,
+        // Nothing can get the ROM past a halt here either
+        on_invalid: match config.on_invalid {
+            InvalidAction::Halt => InvalidAction::Panic,
+            action => action,
+        },
+        ..config.hardware_config()
+    });
+    if let Some(seed) = seed {
+        core.emulator.hardware.set_seed(seed);
+    }
+    crash::set_run_info(util::stable_hash(bytes), config);
+    if let Err(e) = core.load_rom(bytes) {
+        eprintln!("Could not load the ROM: {e}");
+        ExitCode::RomLoadError.exit();
+    }
+    let input = InputSchedule::new();
+    // Enough for a ROM that never exits to still finish
+    let max_cycles = max_cycles.unwrap_or(HEADLESS_SECONDS * config.cpu_hz as u64);
+    let frames = core.run_cycles(max_cycles, &input);
+    if core.is_stuck_on_key(&input) {
+        eprintln!("Stopped waiting for a key, with no input to press one");
+    }
+    if dump_screen {
+        println!(
+            "{}",
+            core.emulator.hardware.framebuffer.to_text().trim_end()
+        );
+    }
+    println!(
+        "{} after {} instructions, {} frames",
+        FrameHash::of(&core.emulator.hardware.framebuffer),
+        core.emulator.hardware.cycles(),
+        frames.len()
     );
-    if let Ok(mut file) = std::fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("panic.log")
-    {
-        let _ = file.write_all(panic_msg.as_bytes());
+    if let Some(limit) = core.emulator.hardware.limit_exceeded() {
+        eprintln!("{limit}");
+        ExitCode::LimitExceeded.exit();
+    }
+    if let Some(e) = core.emulator.hardware.take_fault() {
+        eprintln!("{e}");
+        ExitCode::Fault.exit();
+    }
+    ExitCode::Success.exit()
+}
+
+// `test`: prints how each ROM did on each version, and fails if any didn't pass
+fn run_tests(roms: &[PathBuf], versions: Vec<Chip8Version>, frames: usize, bless: bool) -> ! {
+    let versions = if versions.is_empty() {
+        Chip8Version::value_variants().to_vec()
+    } else {
+        versions
+    };
+    let mut passed = true;
+    for rom in roms {
+        for version in &versions {
+            let outcome = testsuite::check(rom, version, frames, bless).unwrap_or_else(|e| {
+                eprintln!("Could not test {}: {e}", rom.display());
+                ExitCode::RomLoadError.exit()
+            });
+            println!(
+                "{:<32} {:<10} {outcome}",
+                rom.display().to_string(),
+                version.to_string()
+            );
+            passed &= outcome.is_pass();
+        }
     }
-    // Also print to stderr if possible
-    eprintln!("{}", panic_msg);
+    if passed {
+        ExitCode::Success.exit()
+    } else {
+        ExitCode::TestMismatch.exit()
+    }
+}
+
+// Parses decimal, or hexidecimal with a `0x` prefix
+fn parse_number(value: &str) -> Result<usize, String> {
+    let parsed = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|e| format!("'{value}' is not a number: {e}"))
 }
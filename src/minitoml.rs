@@ -0,0 +1,130 @@
+// The small subset of TOML the emulator's settings files use: `[table]` headers, and
+// `name = value` lines with string, number or boolean values. `#` starts a comment
+
+use clap::ValueEnum;
+
+use crate::chip8::Chip8;
+
+// Calls `setting` with the table (empty before any header), name and raw value of every
+// setting in `text`. Errors are prefixed with their line number
+pub(crate) fn for_each_setting(
+    text: &str,
+    mut setting: impl FnMut(&str, &str, &str) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut table = String::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let result = match line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            Some(name) => {
+                table = name.trim().to_string();
+                Ok(())
+            }
+            None => split_assignment(line).and_then(|(name, value)| setting(&table, name, value)),
+        };
+        result.map_err(|e| format!("line {}: {e}", number + 1))?;
+    }
+    Ok(())
+}
+
+// Splits `name = value # comment` into the name and value
+fn split_assignment(line: &str) -> Result<(&str, &str), String> {
+    let (name, value) = line
+        .split_once('=')
+        .ok_or_else(|| format!("'{line}' is not a `name = value` setting"))?;
+    let value = value.trim();
+    let value = match value.strip_prefix('"').and_then(|v| v.find('"')) {
+        Some(end) => &value[..end + 2], // Up to the closing quote
+        None => value.split('#').next().unwrap_or_default().trim(),
+    };
+    Ok((name.trim().trim_matches('"'), value))
+}
+
+// `text` without the table `name`, and with it written at the end as `lines`, unless there
+// are none
+pub(crate) fn replace_table(text: &str, name: &str, lines: &[String]) -> String {
+    let mut in_table = false;
+    let mut kept: Vec<&str> = text
+        .lines()
+        .filter(|line| {
+            let line = line.trim();
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                in_table = header.trim() == name;
+            }
+            !in_table
+        })
+        .collect();
+    while kept.last().is_some_and(|line| line.trim().is_empty()) {
+        kept.pop();
+    }
+    let mut text = kept.join("\n");
+    if !lines.is_empty() {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(&format!("[{name}]\n{}", lines.join("\n")));
+    }
+    text + "\n"
+}
+
+pub(crate) fn parse_string(value: &str) -> Result<String, String> {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .map(str::to_string)
+        .ok_or_else(|| format!("{value} is not a quoted string"))
+}
+
+pub(crate) fn parse_bool(value: &str) -> Result<bool, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{value}' is not true or false"))
+}
+
+pub(crate) fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a valid number"))
+}
+
+// A quoted name of one of the enum's command line values, e.g. `"superchip"`
+pub(crate) fn parse_enum<T: ValueEnum>(value: &str) -> Result<T, String> {
+    T::from_str(&parse_string(value)?, true)
+}
+
+// The command line value of an enum, which `parse_enum` reads back
+pub(crate) fn enum_name<T: ValueEnum>(value: &T) -> String {
+    value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+// A speed in Hz, which has to be positive. Also parses `--cpu-hz`
+pub fn parse_hz(value: &str) -> Result<f64, String> {
+    let hz: f64 = parse_number(value)?;
+    if hz <= 0.0 || !hz.is_finite() {
+        return Err(format!("'{value}' is not a positive speed"));
+    }
+    Ok(hz)
+}
+
+// `font_addr`, which can be written in hex as `0x000`. Also parses `--font-addr`
+pub fn parse_font_addr(value: &str) -> Result<u16, String> {
+    let addr = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+    .ok_or_else(|| format!("'{value}' is not an address"))?;
+    Chip8::check_font_addr(addr)
+}
+
+// `cycles_per_frame`, the speed as Octo sets it, in Hz
+pub(crate) fn parse_cycles_per_frame(value: &str) -> Result<f64, String> {
+    Ok(parse_hz(value)? * Chip8::SCREEN_HZ)
+}
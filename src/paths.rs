@@ -0,0 +1,71 @@
+// Where the emulator keeps files between runs, following each platform's conventions:
+// XDG directories on Linux, Application Support on macOS, and AppData on Windows
+
+use std::path::PathBuf;
+
+const APP_DIR: &str = "chip8-emulator";
+
+// User settings
+pub fn config_dir() -> PathBuf {
+    platform_dir("XDG_CONFIG_HOME", ".config").join(APP_DIR)
+}
+
+// Persistent data the emulator writes itself
+pub fn data_dir() -> PathBuf {
+    user_data_home().join(APP_DIR)
+}
+
+// The base directory shared by every application's data, where desktop entries live on Linux
+pub fn user_data_home() -> PathBuf {
+    platform_dir("XDG_DATA_HOME", ".local/share")
+}
+
+pub fn stats_dir() -> PathBuf {
+    data_dir().join("stats")
+}
+
+pub fn savestates_dir() -> PathBuf {
+    data_dir().join("savestates")
+}
+
+// SUPER-CHIP RPL user flags, which persist between runs of a ROM
+pub fn rpl_flags_dir() -> PathBuf {
+    data_dir().join("rpl")
+}
+
+pub fn screenshots_dir() -> PathBuf {
+    data_dir().join("screenshots")
+}
+
+pub fn logs_dir() -> PathBuf {
+    data_dir().join("logs")
+}
+
+// Every location, by name, for `chip8-emulator paths`
+pub fn all() -> Vec<(&'static str, PathBuf)> {
+    vec![
+        ("config", config_dir()),
+        ("data", data_dir()),
+        ("stats", stats_dir()),
+        ("savestates", savestates_dir()),
+        ("rpl flags", rpl_flags_dir()),
+        ("screenshots", screenshots_dir()),
+        ("logs", logs_dir()),
+    ]
+}
+
+// The platform's base directory for a kind of file. On Linux this is the XDG variable `xdg_var`,
+// or `xdg_default` in the home directory. Falls back to the current directory
+fn platform_dir(xdg_var: &str, xdg_default: &str) -> PathBuf {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os(xdg_var)
+            .map(PathBuf::from)
+            .or_else(|| home().map(|home| home.join(xdg_default)))
+    };
+    base.unwrap_or_else(|| PathBuf::from("."))
+}
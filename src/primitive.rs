@@ -1,5 +1,6 @@
 // Low level primitives, like what defines an address, or an instruction, etc.
 
+use crate::chip8::Chip8Version;
 use crate::validated_struct;
 use std::fmt::Display;
 
@@ -58,7 +59,7 @@ fn is_4_bit(value: u8) -> Result<(), String> {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RawInstruction(u16);
 
 /*
@@ -66,6 +67,7 @@ pub struct RawInstruction(u16);
     X: The second nibble. Used to look up one of the 16 registers (VX) from V0 through VF.
     Y: The third nibble. Also used to look up one of the 16 registers (VY) from V0 through VF.
     N: The fourth nibble. A 4-bit number.
+    P: The second nibble as a 4-bit number, rather than a register. Used as XO-CHIP's plane mask.
     NN: The second byte (third and fourth nibbles). An 8-bit immediate number.
     NNN: The second, third and fourth nibbles. A 12-bit immediate memory address.
 */
@@ -74,6 +76,14 @@ impl RawInstruction {
         RawInstruction(u16::from_be_bytes([byte1, byte2]))
     }
 
+    pub fn from_word(word: u16) -> RawInstruction {
+        RawInstruction(word)
+    }
+
+    pub fn word(&self) -> u16 {
+        self.0
+    }
+
     pub fn to_nibbles(&self) -> (u8, u8, u8, u8) {
         (
             ((self.0 & 0xF000) >> 12) as u8,
@@ -102,6 +112,10 @@ impl RawInstruction {
     pub fn n(&self) -> Immediate4 {
         Immediate4(self.to_nibbles().3)
     }
+
+    pub fn p(&self) -> Immediate4 {
+        Immediate4(self.to_nibbles().1)
+    }
 }
 
 impl Display for RawInstruction {
@@ -186,9 +200,28 @@ pub enum Instruction {
     AddIndex(Register),
     // Misc
     BinaryDecimalConv(Register),
+    // SUPER-CHIP
+    Exit,
+    LowRes,  // Switch to the 64x32 display
+    HighRes, // Switch to the 128x64 display
+    ScrollDown(Immediate4),
+    ScrollRight, // By 4 pixels
+    ScrollLeft,  // By 4 pixels
+    SetBigFont(Register),
+    StoreFlags(Register), // Save V0 to Vx in the RPL user flags
+    LoadFlags(Register),  // Restore V0 to Vx from the RPL user flags
+    // XO-CHIP
+    SaveRange(Register, Register), // Save Vx to Vy in memory at I, without changing I
+    LoadRange(Register, Register), // Load Vx to Vy from memory at I, without changing I
+    LoadLongIndex,                 // Set I to the 16-bit word after the instruction
+    SelectPlanes(Immediate4),      // Choose the planes that drawing, clearing and scrolling use
+    ScrollUp(Immediate4),
+    LoadAudioPattern, // Play the 16 bytes at I as 128 1-bit samples, in place of the beep
+    SetPitch(Register), // Set the rate the audio pattern plays at
     // Debugging and NoOp
     // This cannot be implemented in an interpreter, and so it is a No
-    ExecuteMachineLangRoutine,
+    ExecuteMachineLangRoutine(Address),
+    Unsupported(Chip8Version), // Exists, but not on this version
     Invalid,
 }
 
@@ -230,7 +263,24 @@ impl Display for Instruction {
             SetIndex(addr) => write!(f, "Set Index {addr}"),
             AddIndex(regx) => write!(f, "Add Index {regx}"),
             BinaryDecimalConv(regx) => write!(f, "BinaryDecimalConv {regx}"),
-            ExecuteMachineLangRoutine => write!(f, "ExecMachineLangRoutine"),
+            Exit => write!(f, "Exit"),
+            LowRes => write!(f, "Low Resolution"),
+            HighRes => write!(f, "High Resolution"),
+            ScrollDown(rows) => write!(f, "ScrollDown {rows}"),
+            ScrollRight => write!(f, "ScrollRight"),
+            ScrollLeft => write!(f, "ScrollLeft"),
+            SetBigFont(regx) => write!(f, "SetBigFont {regx}"),
+            StoreFlags(regx) => write!(f, "StoreFlags {regx}"),
+            LoadFlags(regx) => write!(f, "LoadFlags {regx}"),
+            SaveRange(regx, regy) => write!(f, "SaveRange {regx}-{regy}"),
+            LoadRange(regx, regy) => write!(f, "LoadRange {regx}-{regy}"),
+            LoadLongIndex => write!(f, "LoadLongIndex"),
+            SelectPlanes(planes) => write!(f, "SelectPlanes {planes}"),
+            ScrollUp(rows) => write!(f, "ScrollUp {rows}"),
+            LoadAudioPattern => write!(f, "LoadAudioPattern"),
+            SetPitch(regx) => write!(f, "SetPitch {regx}"),
+            ExecuteMachineLangRoutine(addr) => write!(f, "ExecMachineLangRoutine {addr}"),
+            Unsupported(version) => write!(f, "UNSUPPORTED on {version}"),
             Invalid => write!(f, "INVALID"),
         }
     }
@@ -0,0 +1,159 @@
+// The behaviours CHIP-8 interpreters disagree on. Each `--version` is a preset of them, and
+// `--quirk NAME=on|off` changes one on top of it, for ROMs written against an interpreter that
+// mixed them differently
+
+use crate::chip8::Chip8Version;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quirks {
+    pub shift_vy: bool,        // 8XY6/8XYE shift VY into VX, rather than VX in place
+    pub index_increment: bool, // FX55/FX65 leave I pointing past the last register
+    pub jump_vx: bool,         // BNNN jumps to XNN + VX, rather than NNN + V0
+    pub vf_reset: bool,        // 8XY1/8XY2/8XY3 set VF to 0
+    pub wrap: bool,            // Sprites wrap around the edges of the screen, rather than clip
+    pub display_wait: bool,    // Drawing waits for the next 60Hz display interrupt
+    pub collision_rows: bool,  // In high resolution, VF counts the sprite rows that collided
+    pub key_release: bool,     // FX0A finishes when the key is released, rather than pressed
+    pub index_overflow: bool,  // FX1E sets VF when I goes past 0x0FFF, as on the Amiga
+    pub memory_wrap: bool,     // Reads and writes from I wrap past the end of memory, not fault
+    pub sound_threshold: bool, // FX18 below 2 sets the timer but makes no sound, as on the VIP
+}
+
+impl Quirks {
+    pub const NAMES: [&str; 11] = [
+        "shift_vy",
+        "index_increment",
+        "jump_vx",
+        "vf_reset",
+        "wrap",
+        "display_wait",
+        "collision_rows",
+        "key_release",
+        "index_overflow",
+        "memory_wrap",
+        "sound_threshold",
+    ];
+
+    // How each interpreter behaved. Display wait is left off, as it makes most games slower
+    // than people remember them
+    pub fn for_version(version: &Chip8Version) -> Self {
+        let none = Self {
+            shift_vy: false,
+            index_increment: false,
+            jump_vx: false,
+            vf_reset: false,
+            wrap: false,
+            display_wait: false,
+            collision_rows: false,
+            key_release: false,
+            index_overflow: false,
+            memory_wrap: false,
+            sound_threshold: false,
+        };
+        match version {
+            Chip8Version::Cosmac => Self {
+                shift_vy: true,
+                index_increment: true,
+                vf_reset: true,
+                key_release: true,
+                sound_threshold: true,
+                ..none
+            },
+            Chip8Version::Chip48 | Chip8Version::Superchip => Self {
+                jump_vx: true,
+                ..none
+            },
+            // XO-CHIP went back to the original shifts and FX55/FX65
+            Chip8Version::XoChip => Self {
+                shift_vy: true,
+                index_increment: true,
+                wrap: true,
+                ..none
+            },
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<bool> {
+        let mut quirks = *self;
+        quirks.flag(name).map(|flag| *flag)
+    }
+
+    pub fn set(&mut self, name: &str, on: bool) -> Result<(), String> {
+        let flag = self.flag(name).ok_or_else(|| {
+            format!(
+                "'{name}' is not a quirk, expected one of {}",
+                Self::NAMES.join(", ")
+            )
+        })?;
+        *flag = on;
+        Ok(())
+    }
+
+    // Every quirk by name, in the order of `NAMES`
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, bool)> + '_ {
+        Self::NAMES
+            .into_iter()
+            .map(|name| (name, self.get(name).unwrap_or_default()))
+    }
+
+    // One bit per quirk, in the order of `NAMES`
+    pub fn bits(&self) -> u16 {
+        self.iter()
+            .enumerate()
+            .fold(0, |bits, (i, (_, on))| bits | (on as u16) << i)
+    }
+
+    pub fn from_bits(bits: u16) -> Self {
+        let mut quirks = Self::for_version(&Chip8Version::Cosmac);
+        for (i, name) in Self::NAMES.into_iter().enumerate() {
+            let _ = quirks.set(name, bits & 1 << i != 0);
+        }
+        quirks
+    }
+
+    // The quirks set differently in `other`, as `name=on|off` by their value there
+    pub fn differences(&self, other: &Quirks) -> String {
+        other
+            .iter()
+            .filter(|&(name, on)| self.get(name) != Some(on))
+            .map(|(name, on)| format!("{name}={}", if on { "on" } else { "off" }))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn flag(&mut self, name: &str) -> Option<&mut bool> {
+        Some(match name {
+            "shift_vy" => &mut self.shift_vy,
+            "index_increment" => &mut self.index_increment,
+            "jump_vx" => &mut self.jump_vx,
+            "vf_reset" => &mut self.vf_reset,
+            "wrap" => &mut self.wrap,
+            "display_wait" => &mut self.display_wait,
+            "collision_rows" => &mut self.collision_rows,
+            "key_release" => &mut self.key_release,
+            "index_overflow" => &mut self.index_overflow,
+            "memory_wrap" => &mut self.memory_wrap,
+            "sound_threshold" => &mut self.sound_threshold,
+            _ => return None,
+        })
+    }
+}
+
+// `NAME=on|off`, as `--quirk` takes it
+pub fn parse_override(text: &str) -> Result<(String, bool), String> {
+    let (name, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("'{text}' is not NAME=on or NAME=off"))?;
+    let on = parse_on_off(value)?;
+    Quirks::for_version(&Chip8Version::Cosmac).set(name, on)?;
+    Ok((name.to_string(), on))
+}
+
+// `on` or `off`, for flags that turn one quirk on or off
+pub fn parse_on_off(value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" => Ok(true),
+        "off" | "false" => Ok(false),
+        _ => Err(format!("'{value}' is not on or off")),
+    }
+}
@@ -0,0 +1,157 @@
+// Records emulator output to a video file using an ffmpeg subprocess
+
+use std::f32::consts::TAU;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use crate::framebuffer::FrameBuffer;
+use crate::frontend::ScreenColor;
+
+pub struct VideoRecorder {
+    output: PathBuf,
+    video_path: PathBuf,
+    audio_path: PathBuf,
+    ffmpeg: Child,
+    video_in: Option<ChildStdin>,
+    audio_out: BufWriter<File>,
+    fps: f64,
+    beep_phase: f32,
+}
+
+impl VideoRecorder {
+    pub const SCALE: usize = 10; // Each low resolution pixel becomes a SCALE x SCALE block
+    pub const AUDIO_SAMPLE_RATE: u32 = 44100;
+    const BEEP_HZ: f32 = 440.0;
+    const BEEP_AMPLITUDE: f32 = 0.1;
+
+    // Starts ffmpeg, which encodes frames piped into its stdin. Audio is written next to the
+    // output and muxed in once recording has finished
+    pub fn start(output: &str, fps: f64) -> io::Result<Self> {
+        let output = PathBuf::from(output);
+        let video_path = Self::temp_path(&output, "video");
+        let audio_path = Self::temp_path(&output, "audio.pcm");
+        let width = FrameBuffer::LORES_COLS as usize * Self::SCALE;
+        let height = FrameBuffer::LORES_ROWS as usize * Self::SCALE;
+
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &format!("{width}x{height}"), "-r", &fps.to_string()])
+            .args(["-i", "-", "-pix_fmt", "yuv420p"])
+            .arg(&video_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| io::Error::new(e.kind(), format!("Could not start ffmpeg: {e}")))?;
+        let video_in = ffmpeg.stdin.take();
+
+        Ok(Self {
+            audio_out: BufWriter::new(File::create(&audio_path)?),
+            output,
+            video_path,
+            audio_path,
+            ffmpeg,
+            video_in,
+            fps,
+            beep_phase: 0.0,
+        })
+    }
+
+    // Bytes written to disk so far, by the encoder and the audio track
+    pub fn disk_usage(&self) -> u64 {
+        let size = |path: &PathBuf| fs::metadata(path).map_or(0, |m| m.len());
+        size(&self.video_path) + size(&self.audio_path)
+    }
+
+    // Writes one frame of video, and one frame's worth of audio
+    pub fn record_frame(
+        &mut self,
+        frame: &FrameBuffer,
+        color: ScreenColor,
+        beeping: bool,
+    ) -> io::Result<()> {
+        self.write_video_frame(frame, color)?;
+        self.write_audio_frame(beeping)
+    }
+
+    fn write_video_frame(&mut self, frame: &FrameBuffer, color: ScreenColor) -> io::Result<()> {
+        let Some(video_in) = self.video_in.as_mut() else {
+            return Ok(());
+        };
+        let on = color.rgb();
+        let off = (0, 0, 0);
+        // High resolution pixels are scaled by half, so the video keeps the same size
+        let scale = Self::SCALE * FrameBuffer::LORES_COLS as usize / frame.width() as usize;
+        let mut row = Vec::with_capacity(frame.width() as usize * scale * 3);
+        for y in 0..frame.height() {
+            row.clear();
+            for x in 0..frame.width() {
+                let (r, g, b) = if frame.is_lit(x, y) { on } else { off };
+                for _ in 0..scale {
+                    row.extend_from_slice(&[r, g, b]);
+                }
+            }
+            for _ in 0..scale {
+                video_in.write_all(&row)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Signed 16-bit mono samples of a sine wave while the sound timer is active, silence otherwise
+    fn write_audio_frame(&mut self, beeping: bool) -> io::Result<()> {
+        let samples = (Self::AUDIO_SAMPLE_RATE as f64 / self.fps).round() as usize;
+        let step = TAU * Self::BEEP_HZ / Self::AUDIO_SAMPLE_RATE as f32;
+        for _ in 0..samples {
+            let sample = if beeping {
+                self.beep_phase = (self.beep_phase + step) % TAU;
+                (self.beep_phase.sin() * Self::BEEP_AMPLITUDE * i16::MAX as f32) as i16
+            } else {
+                0
+            };
+            self.audio_out.write_all(&sample.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Closes the video stream and muxes the recorded audio into the final output file
+    pub fn finish(mut self) -> io::Result<()> {
+        drop(self.video_in.take());
+        self.audio_out.flush()?;
+        let status = self.ffmpeg.wait()?;
+        if !status.success() {
+            return Err(io::Error::other(format!("ffmpeg exited with {status}")));
+        }
+
+        let status = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error", "-i"])
+            .arg(&self.video_path)
+            .args(["-f", "s16le", "-ac", "1"])
+            .args(["-ar", &Self::AUDIO_SAMPLE_RATE.to_string()])
+            .arg("-i")
+            .arg(&self.audio_path)
+            .args(["-c:v", "copy", "-shortest"])
+            .arg(&self.output)
+            .status()?;
+        let _ = fs::remove_file(&self.video_path);
+        let _ = fs::remove_file(&self.audio_path);
+        if status.success() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!("ffmpeg exited with {status}")))
+        }
+    }
+
+    // `out.mp4` => `out.video.mp4`, keeping the extension so ffmpeg picks the same container
+    fn temp_path(output: &Path, suffix: &str) -> PathBuf {
+        let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match output.extension() {
+            Some(ext) if suffix == "video" => format!("{stem}.{suffix}.{}", ext.to_string_lossy()),
+            _ => format!("{stem}.{suffix}"),
+        };
+        output.with_file_name(name)
+    }
+}
@@ -0,0 +1,123 @@
+// `chip-8-emulator remap`: asks for a key for each CHIP-8 key and command in turn, and writes
+// them into the `[keys]` table of the settings file. Ctrl+K keeps a key where it is, and
+// Ctrl+C leaves without saving anything
+
+use std::io::{self, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+
+use crate::input::{KeyMapping, KeyboardLayout};
+use crate::keybindings::{self, BindingTarget, KeyBindings};
+
+pub struct RemapWizard {
+    layout: KeyboardLayout,
+    bindings: KeyBindings,
+    chosen: KeyBindings, // The keys pressed so far, which can't be used twice
+    targets: Vec<BindingTarget>,
+    next: usize,
+}
+
+impl RemapWizard {
+    pub fn new(layout: KeyboardLayout, bindings: KeyBindings) -> Self {
+        Self {
+            layout,
+            bindings,
+            chosen: KeyBindings::default(),
+            targets: BindingTarget::all(),
+            next: 0,
+        }
+    }
+
+    // What the next key is for, or None when every one has been asked for
+    pub fn current(&self) -> Option<BindingTarget> {
+        self.targets.get(self.next).copied()
+    }
+
+    // e.g. "CHIP-8 key 5 (now W): "
+    pub fn prompt(&self) -> Option<String> {
+        let target = self.current()?;
+        let mapping = KeyMapping::new(self.layout, self.bindings.clone());
+        Some(format!(
+            "[{}/{}] {target} (now {}): ",
+            self.next + 1,
+            self.targets.len(),
+            mapping.label(target)
+        ))
+    }
+
+    // Binds the key to the current target, unless it can't be
+    pub fn press(&mut self, code: KeyCode) -> Result<(), String> {
+        let Some(target) = self.current() else {
+            return Ok(());
+        };
+        let Some(name) = keybindings::key_name(code) else {
+            return Err("That key can't be bound, press another".to_string());
+        };
+        if let Some(other) = self.chosen.target_of(code) {
+            return Err(format!("'{name}' is already {other}, press another"));
+        }
+        self.chosen.bind(target, code);
+        self.bindings.bind(target, code);
+        self.next += 1;
+        Ok(())
+    }
+
+    // Moves on, leaving the current target's key as it was
+    pub fn keep(&mut self) {
+        self.next += 1;
+    }
+
+    pub fn into_bindings(self) -> KeyBindings {
+        self.bindings
+    }
+}
+
+// Runs the wizard on the terminal, returning the new bindings, or None if it was cancelled
+pub fn run(layout: KeyboardLayout, bindings: KeyBindings) -> io::Result<Option<KeyBindings>> {
+    println!(
+        "Press the key for each CHIP-8 key and command. Ctrl+K keeps the current one, and Ctrl+C cancels"
+    );
+    terminal::enable_raw_mode()?;
+    let result = ask_all(RemapWizard::new(layout, bindings));
+    terminal::disable_raw_mode()?;
+    println!();
+    result
+}
+
+fn ask_all(mut wizard: RemapWizard) -> io::Result<Option<KeyBindings>> {
+    let mut out = io::stdout();
+    while let Some(prompt) = wizard.prompt() {
+        write!(out, "\r\n{prompt}")?;
+        out.flush()?;
+        loop {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Char('c') if ctrl => {
+                    write!(out, "\r\nCancelled, nothing was saved")?;
+                    return Ok(None);
+                }
+                KeyCode::Char('k') if ctrl => {
+                    write!(out, "kept")?;
+                    wizard.keep();
+                }
+                code => match wizard.press(code) {
+                    Ok(()) => write!(out, "{}", keybindings::key_name(code).unwrap_or_default())?,
+                    Err(e) => {
+                        write!(out, "\r\n  {e}: ")?;
+                        out.flush()?;
+                        continue;
+                    }
+                },
+            }
+            break;
+        }
+    }
+    Ok(Some(wizard.into_bindings()))
+}
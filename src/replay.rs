@@ -0,0 +1,231 @@
+// Recordings of everything a run depends on besides the ROM: the key events, the 60Hz timer
+// ticks, and the seed of the Random instruction. A header names the format version, and the ROM
+// and quirks the run used, which a replay needs to play out the same. Each event is stamped with the number of
+// instructions run before it, so `--replay` gives every instruction the same input as when
+// `--record` ran, whatever the speed. With `--checksums`, each tick is followed by a CRC of
+// the machine's state, which replays check to find the first frame that came out differently.
+// The files are text, one event per line:
+//
+//     version 1
+//     rom 5d2c0f1e8a3b7c46
+//     quirks 40f
+//     seed 1234
+//     key 5120 A press
+//     tick 5125
+//     crc 5125 1c291ca3
+//
+// `#` starts a comment
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::input::Chip8KeyEventKind;
+use crate::machinestate::StateDiff;
+use crate::quirks::Quirks;
+
+// Bumped whenever the meaning of a recording changes, such as the quirks' bits
+pub const FORMAT_VERSION: u64 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReplayEvent {
+    Key(u8, Chip8KeyEventKind),
+    TimerTick,
+    Checksum(u32), // Of the machine after the tick before it
+}
+
+pub struct InputRecorder {
+    file: BufWriter<File>,
+    checksums: bool, // Whether each tick is followed by the state's checksum
+}
+
+impl InputRecorder {
+    pub fn create(
+        path: &Path,
+        rom_hash: u64,
+        quirks: &Quirks,
+        seed: u64,
+        checksums: bool,
+    ) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "# chip-8-emulator input recording")?;
+        writeln!(file, "version {FORMAT_VERSION}")?;
+        writeln!(file, "rom {rom_hash:016x}")?;
+        writeln!(file, "quirks {:x}", quirks.bits())?;
+        writeln!(file, "seed {seed}")?;
+        Ok(Self { file, checksums })
+    }
+
+    pub fn records_checksums(&self) -> bool {
+        self.checksums
+    }
+
+    // Returns the bytes written, for the disk limit
+    pub fn record(&mut self, cycle: u64, event: ReplayEvent) -> io::Result<u64> {
+        let line = match event {
+            ReplayEvent::Key(key, kind) => {
+                let kind = match kind {
+                    Chip8KeyEventKind::Press => "press",
+                    Chip8KeyEventKind::Release => "release",
+                };
+                format!("key {cycle} {key:X} {kind}\n")
+            }
+            ReplayEvent::TimerTick => format!("tick {cycle}\n"),
+            ReplayEvent::Checksum(crc) => format!("crc {cycle} {crc:08x}\n"),
+        };
+        self.file.write_all(line.as_bytes())?;
+        // Written out once a frame, so little is lost if the emulator is killed
+        if event == ReplayEvent::TimerTick {
+            self.file.flush()?;
+        }
+        Ok(line.len() as u64)
+    }
+}
+
+pub struct InputReplay {
+    pub rom_hash: u64, // The ROM it was recorded from, which it can only be replayed on
+    pub quirks: Quirks,
+    pub seed: u64,
+    events: VecDeque<(u64, ReplayEvent)>, // By cycle, in the order they happened
+}
+
+impl InputReplay {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path:?}: {e}")))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let (mut version, mut rom_hash, mut quirks, mut seed) = (None, None, None, None);
+        let mut events = VecDeque::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+            let result = match name {
+                "version" => parse_number(value).map(|value| version = Some(value)),
+                "rom" => u64::from_str_radix(value, 16)
+                    .map(|value| rom_hash = Some(value))
+                    .map_err(|_| format!("'{value}' is not a hexadecimal ROM hash")),
+                "quirks" => u16::from_str_radix(value, 16)
+                    .map(|bits| quirks = Some(Quirks::from_bits(bits)))
+                    .map_err(|_| format!("'{value}' is not hexadecimal quirk bits")),
+                "seed" => parse_number(value).map(|value| seed = Some(value)),
+                _ => parse_event(line).and_then(|(cycle, event)| match events.back() {
+                    // Replays play events in order, so one out of order would stall the rest
+                    Some(&(previous, _)) if cycle < previous => Err(format!(
+                        "cycle {cycle} comes before the previous event's, {previous}"
+                    )),
+                    _ => {
+                        events.push_back((cycle, event));
+                        Ok(())
+                    }
+                }),
+            };
+            result.map_err(|e| format!("line {}: {e}", number + 1))?;
+        }
+        match version.ok_or("the recording has no format version")? {
+            FORMAT_VERSION => {}
+            version => {
+                return Err(format!(
+                    "the recording is format version {version}, this emulator plays {FORMAT_VERSION}"
+                ));
+            }
+        }
+        Ok(Self {
+            rom_hash: rom_hash.ok_or("the recording doesn't say which ROM it's of")?,
+            quirks: quirks.ok_or("the recording doesn't say which quirks it used")?,
+            seed: seed.ok_or("the recording has no seed")?,
+            events,
+        })
+    }
+
+    // Why the recording can't be replayed on the ROM and quirks, if it can't
+    pub fn check_matches(&self, rom_hash: u64, quirks: &Quirks) -> Result<(), String> {
+        if self.rom_hash != rom_hash {
+            Err("the recording is of a different ROM".to_string())
+        } else if self.quirks != *quirks {
+            Err(format!(
+                "the recording was made with other quirks ({})",
+                quirks.differences(&self.quirks)
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    // The next event, if it happened before instruction `cycle` ran
+    pub fn next_due(&mut self, cycle: u64) -> Option<ReplayEvent> {
+        match self.events.front() {
+            Some(&(at, event)) if at <= cycle => {
+                self.events.pop_front();
+                Some(event)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+// `tick CYCLE`, `crc CYCLE CHECKSUM`, or `key CYCLE KEY press|release`
+pub(crate) fn parse_event(line: &str) -> Result<(u64, ReplayEvent), String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match fields[..] {
+        ["tick", cycle] => Ok((parse_number(cycle)?, ReplayEvent::TimerTick)),
+        ["crc", cycle, crc] => {
+            let crc = u32::from_str_radix(crc, 16)
+                .map_err(|_| format!("'{crc}' is not a hexadecimal checksum"))?;
+            Ok((parse_number(cycle)?, ReplayEvent::Checksum(crc)))
+        }
+        ["key", cycle, key, kind] => {
+            let key = u8::from_str_radix(key, 16)
+                .ok()
+                .filter(|&key| key <= 0xF)
+                .ok_or_else(|| format!("'{key}' is not a CHIP-8 key (0-F)"))?;
+            let kind = match kind {
+                "press" => Chip8KeyEventKind::Press,
+                "release" => Chip8KeyEventKind::Release,
+                _ => return Err(format!("'{kind}' is not press or release")),
+            };
+            Ok((parse_number(cycle)?, ReplayEvent::Key(key, kind)))
+        }
+        _ => Err(format!("'{line}' is not an event")),
+    }
+}
+
+fn parse_number(value: &str) -> Result<u64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{value}' is not a valid number"))
+}
+
+// The first frame whose state didn't match the recording's checksum
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    pub frame: u64, // Timer ticks replayed before it
+    pub cycle: u64,
+    pub recorded: u32,
+    pub actual: u32,
+    pub changes: Option<StateDiff>, // Since the last frame that matched, if one did
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Replay diverged from the recording at frame {} (cycle {}): checksum {:08x}, recorded {:08x}",
+            self.frame, self.cycle, self.actual, self.recorded
+        )?;
+        if let Some(ref changes) = self.changes {
+            write!(f, "; since the last frame that matched: {changes}")?;
+        }
+        Ok(())
+    }
+}
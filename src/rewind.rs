@@ -0,0 +1,66 @@
+// The last few seconds of the machine, one snapshot per frame, so Backspace can step back in
+// time. Snapshots are packed like save states, without their header: a 64KB XO-CHIP snapshot
+// is only a few KB
+
+use std::collections::VecDeque;
+
+use crate::cpu::CPU;
+use crate::framebuffer::FrameBuffer;
+use crate::savestate::{self, SaveState};
+
+pub struct RewindBuffer {
+    snapshots: VecDeque<Vec<u8>>, // Oldest first
+    capacity: usize,
+}
+
+impl Default for RewindBuffer {
+    fn default() -> Self {
+        Self::new(Self::SECONDS * Self::FRAMES_PER_SECOND)
+    }
+}
+
+impl RewindBuffer {
+    pub const SECONDS: usize = 10;
+    pub const FRAMES_PER_SECOND: usize = 60;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    // Keeps a snapshot of the machine, dropping the oldest one when full
+    pub fn record(&mut self, cpu: &CPU, framebuffer: &FrameBuffer) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots
+            .push_back(savestate::pack(&SaveState::encode_machine(
+                cpu,
+                framebuffer,
+            )));
+    }
+
+    // The machine `frames` snapshots ago, or the oldest one kept. Newer snapshots are dropped,
+    // so rewinding again goes further back
+    pub fn rewind(&mut self, frames: usize) -> Option<SaveState> {
+        let keep = self.snapshots.len().saturating_sub(frames.max(1));
+        let packed = self.snapshots.drain(keep..).next()?;
+        SaveState::decode_machine(&savestate::unpack(&packed)).ok()
+    }
+
+    // Frames that can be rewound
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    // Memory used by the snapshots, in bytes
+    pub fn packed_size(&self) -> usize {
+        self.snapshots.iter().map(Vec::len).sum()
+    }
+}
@@ -0,0 +1,102 @@
+// Settings a ROM's author can ship next to it, in `game.ch8.toml`: the quirks and speed the
+// ROM needs, its title, and what its keys do. For example:
+//
+//     title = "Pong"
+//     version = "chip48"
+//     cycles_per_frame = 12
+//     collision_rows = true
+//
+//     [quirks]
+//     vf_reset = false
+//
+//     [keys]
+//     1 = "Left paddle up"
+//     4 = "Left paddle down"
+//
+// Unknown settings are ignored, so sidecars written for other emulators still load
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::chip8::Chip8Version;
+use crate::minitoml;
+use crate::quirks::Quirks;
+
+#[derive(Debug, Clone, Default)]
+pub struct RomConfig {
+    pub title: Option<String>,
+    pub version: Option<Chip8Version>,
+    pub cpu_hz: Option<f64>,
+    pub collision_rows: bool,
+    pub display_wait: bool,
+    pub vip_routines: bool,
+    pub font_addr: Option<u16>,
+    pub quirks: Vec<(String, bool)>, // Quirks turned on or off over the version's, in file order
+    pub key_hints: Vec<(u8, String)>, // CHIP-8 key and what it does, in file order
+}
+
+impl RomConfig {
+    // `game.ch8` is configured by `game.ch8.toml`
+    pub fn sidecar_path(rom: &Path) -> PathBuf {
+        let mut path = rom.as_os_str().to_owned();
+        path.push(".toml");
+        PathBuf::from(path)
+    }
+
+    // The configuration shipped next to the ROM, if there is one
+    pub fn load_for(rom: &Path) -> io::Result<Option<Self>> {
+        let path = Self::sidecar_path(rom);
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Self::parse(&text)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path:?}: {e}")))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        minitoml::for_each_setting(text, |table, name, value| match table {
+            "keys" => config.parse_key_hint(name, value),
+            "quirks" => config.parse_quirk(name, value),
+            "" => config.parse_setting(name, value),
+            _ => Ok(()),
+        })?;
+        Ok(config)
+    }
+
+    fn parse_setting(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "title" => self.title = Some(minitoml::parse_string(value)?),
+            "version" => self.version = Some(minitoml::parse_enum(value)?),
+            "cpu_hz" => self.cpu_hz = Some(minitoml::parse_hz(value)?),
+            "cycles_per_frame" => self.cpu_hz = Some(minitoml::parse_cycles_per_frame(value)?),
+            "collision_rows" => self.collision_rows = minitoml::parse_bool(value)?,
+            "display_wait" => self.display_wait = minitoml::parse_bool(value)?,
+            "vip_routines" => self.vip_routines = minitoml::parse_bool(value)?,
+            "font_addr" => self.font_addr = Some(minitoml::parse_font_addr(value)?),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn parse_quirk(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let on = minitoml::parse_bool(value)?;
+        // Checks the name here, so a misspelt quirk is reported with its line
+        Quirks::from_bits(0).set(name, on)?;
+        self.quirks.push((name.to_string(), on));
+        Ok(())
+    }
+
+    fn parse_key_hint(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let key = u8::from_str_radix(key, 16)
+            .ok()
+            .filter(|&key| key <= 0xF)
+            .ok_or_else(|| format!("'{key}' is not a CHIP-8 key (0-F)"))?;
+        self.key_hints.push((key, minitoml::parse_string(value)?));
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+// SUPER-CHIP's RPL user flags, which FX75 saves registers to and FX85 restores them from. On
+// the HP-48 they survived turning the calculator off, and games used them to keep high scores
+// and settings, so they're saved per ROM in the data directory
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::paths;
+
+#[derive(Debug, Default, Clone)]
+pub struct RplFlags {
+    values: [u8; RplFlags::COUNT],
+    rom_hash: Option<u64>, // The ROM they're saved for, or None to only keep them in memory
+}
+
+impl RplFlags {
+    pub const COUNT: usize = 8;
+
+    // Loads the flags saved for the ROM with the given hash, or zeroes if none were saved
+    pub fn load(rom_hash: u64) -> Self {
+        let mut values = [0; Self::COUNT];
+        if let Ok(saved) = fs::read(Self::path(rom_hash)) {
+            let count = saved.len().min(Self::COUNT);
+            values[..count].copy_from_slice(&saved[..count]);
+        }
+        Self {
+            values,
+            rom_hash: Some(rom_hash),
+        }
+    }
+
+    pub fn values(&self) -> [u8; Self::COUNT] {
+        self.values
+    }
+
+    // Sets the first flags to `values`, and saves them all. Returns the bytes written
+    pub fn store(&mut self, values: &[u8]) -> io::Result<u64> {
+        let count = values.len().min(Self::COUNT);
+        self.values[..count].copy_from_slice(&values[..count]);
+        let Some(rom_hash) = self.rom_hash else {
+            return Ok(0);
+        };
+        let path = Self::path(rom_hash);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.values)?;
+        Ok(Self::COUNT as u64)
+    }
+
+    fn path(rom_hash: u64) -> PathBuf {
+        paths::rpl_flags_dir().join(format!("{rom_hash:016x}.bin"))
+    }
+}
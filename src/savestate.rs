@@ -0,0 +1,362 @@
+// Snapshots of the whole machine, saved with F5 and loaded with F9 into one of ten slots per
+// ROM. States are kept in the data directory, or `--state-dir`, in a small binary format: a
+// header with the format version, the ROM and quirks it was saved with, and a CRC, then the
+// machine packed with PackBits, since most of memory and the display is runs of zeroes. States
+// from an older build, another ROM or configuration, or damaged on disk are refused rather
+// than loaded wrong
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cpu::CPU;
+use crate::framebuffer::FrameBuffer;
+use crate::quirks::Quirks;
+use crate::util;
+
+#[derive(Clone)]
+pub struct SaveState {
+    pub rom_hash: u64, // The ROM it was saved from, which it can only be loaded into
+    pub saved_at: u64, // Unix time, in seconds
+    pub quirks: Quirks,
+    pub cpu: CPU,
+    pub framebuffer: FrameBuffer,
+}
+
+impl SaveState {
+    const MAGIC: &[u8; 4] = b"C8ST";
+    const FORMAT_VERSION: u8 = 5;
+
+    // A state of the machine right now
+    pub fn new(rom_hash: u64, quirks: Quirks, cpu: &CPU, framebuffer: &FrameBuffer) -> Self {
+        Self {
+            rom_hash,
+            quirks,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            cpu: cpu.clone(),
+            framebuffer: framebuffer.clone(),
+        }
+    }
+
+    // e.g. "2025-01-31T12:00:00Z"
+    pub fn saved_at_text(&self) -> String {
+        util::utc_timestamp(UNIX_EPOCH + Duration::from_secs(self.saved_at))
+    }
+
+    // Returns the bytes written, for the disk limit
+    pub fn save(&self, path: &Path) -> io::Result<u64> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let bytes = self.encode();
+        fs::write(path, &bytes)?;
+        Ok(bytes.len() as u64)
+    }
+
+    // The state saved at `path`, or None if nothing was saved there
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        Self::decode(&bytes)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path:?}: {e}")))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let machine = Self::encode_machine(&self.cpu, &self.framebuffer);
+        let mut out = StateWriter::default();
+        out.bytes(Self::MAGIC);
+        out.u8(Self::FORMAT_VERSION);
+        out.u64(self.rom_hash);
+        out.u64(self.saved_at);
+        out.u16(self.quirks.bits());
+        out.u32(util::crc32(&machine));
+        out.bytes(&pack(&machine));
+        out.0
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        let mut state = StateReader(bytes);
+        if state.bytes(Self::MAGIC.len())? != Self::MAGIC {
+            return Err("not a save state".to_string());
+        }
+        let version = state.u8()?;
+        if version != Self::FORMAT_VERSION {
+            return Err(format!("save state format {version} is not supported"));
+        }
+        let rom_hash = state.u64()?;
+        let saved_at = state.u64()?;
+        let quirks = Quirks::from_bits(state.u16()?);
+        let crc = state.u32()?;
+        let machine = unpack(state.0);
+        if util::crc32(&machine) != crc {
+            return Err("the save state is damaged".to_string());
+        }
+        Ok(Self {
+            rom_hash,
+            saved_at,
+            quirks,
+            ..Self::decode_machine(&machine)?
+        })
+    }
+
+    // Just the CPU and display, without a header or packing
+    pub fn encode_machine(cpu: &CPU, framebuffer: &FrameBuffer) -> Vec<u8> {
+        let mut out = StateWriter::default();
+        cpu.save_state(&mut out);
+        framebuffer.save_state(&mut out);
+        out.0
+    }
+
+    // A state with no ROM, time or quirks, from `encode_machine`
+    pub fn decode_machine(bytes: &[u8]) -> Result<Self, String> {
+        let mut state = StateReader(bytes);
+        let cpu = CPU::load_state(&mut state)?;
+        let framebuffer = FrameBuffer::load_state(&mut state)?;
+        if !state.0.is_empty() {
+            return Err("unexpected data after the save state".to_string());
+        }
+        Ok(Self {
+            rom_hash: 0,
+            saved_at: 0,
+            quirks: Quirks::from_bits(0),
+            cpu,
+            framebuffer,
+        })
+    }
+}
+
+// The ten save slots of a ROM, and which one F5 and F9 use. There's also an autosave slot,
+// written every few seconds with `--autosave`
+pub struct SaveSlots {
+    dir: PathBuf,
+    rom_hash: u64,
+    quirks: Quirks, // What the ROM is running with, which states have to match
+    slot: u8,
+}
+
+impl SaveSlots {
+    pub const COUNT: u8 = 10;
+
+    pub fn new(dir: PathBuf, rom_hash: u64, quirks: Quirks) -> Self {
+        Self {
+            dir,
+            rom_hash,
+            quirks,
+            slot: 0,
+        }
+    }
+
+    pub fn slot(&self) -> u8 {
+        self.slot
+    }
+
+    // Moves to the next or previous slot, wrapping around
+    pub fn select(&mut self, delta: i8) {
+        self.slot = (self.slot as i8 + delta).rem_euclid(Self::COUNT as i8) as u8;
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}-{}.state", self.rom_hash, self.slot))
+    }
+
+    pub fn autosave_path(&self) -> PathBuf {
+        self.dir.join(format!("{:016x}-auto.state", self.rom_hash))
+    }
+
+    pub fn save(&self, cpu: &CPU, framebuffer: &FrameBuffer) -> io::Result<u64> {
+        SaveState::new(self.rom_hash, self.quirks, cpu, framebuffer).save(&self.path())
+    }
+
+    pub fn autosave(&self, cpu: &CPU, framebuffer: &FrameBuffer) -> io::Result<u64> {
+        SaveState::new(self.rom_hash, self.quirks, cpu, framebuffer).save(&self.autosave_path())
+    }
+
+    // The state in the selected slot, or None if it's empty
+    pub fn load(&self) -> io::Result<Option<SaveState>> {
+        self.load_from(&self.path())
+    }
+
+    pub fn load_autosave(&self) -> io::Result<Option<SaveState>> {
+        self.load_from(&self.autosave_path())
+    }
+
+    fn load_from(&self, path: &Path) -> io::Result<Option<SaveState>> {
+        let state = SaveState::load(path)?;
+        let Some(ref saved) = state else {
+            return Ok(None);
+        };
+        let mismatch = if saved.rom_hash != self.rom_hash {
+            Some("from a different ROM".to_string())
+        } else if saved.quirks != self.quirks {
+            Some(format!(
+                "with other quirks ({})",
+                self.quirks.differences(&saved.quirks)
+            ))
+        } else {
+            None
+        };
+        match mismatch {
+            Some(mismatch) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path:?} was saved {mismatch}"),
+            )),
+            None => Ok(state),
+        }
+    }
+
+    // e.g. "Slot 3, saved 2025-01-31T12:00:00Z"
+    pub fn describe(&self) -> String {
+        let contents = match self.load() {
+            Ok(Some(state)) => format!("saved {}", state.saved_at_text()),
+            Ok(None) => "empty".to_string(),
+            Err(_) => "unreadable".to_string(),
+        };
+        format!("Slot {}, {contents}", self.slot)
+    }
+}
+
+// Little-endian fields, in the order they're read back
+#[derive(Default)]
+pub(crate) struct StateWriter(Vec<u8>);
+
+impl StateWriter {
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.0.extend(value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.0.extend(value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.0.extend(value.to_le_bytes());
+    }
+
+    pub(crate) fn u128(&mut self, value: u128) {
+        self.0.extend(value.to_le_bytes());
+    }
+
+    pub(crate) fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    // Nothing, or a byte after a marker
+    pub(crate) fn optional_u8(&mut self, value: Option<u8>) {
+        match value {
+            Some(value) => {
+                self.u8(1);
+                self.u8(value);
+            }
+            None => self.u8(0),
+        }
+    }
+}
+
+pub(crate) struct StateReader<'b>(&'b [u8]);
+
+impl<'b> StateReader<'b> {
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'b [u8], String> {
+        if self.0.len() < len {
+            return Err("the save state is cut short".to_string());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn u128(&mut self) -> Result<u128, String> {
+        Ok(u128::from_le_bytes(self.array()?))
+    }
+
+    pub(crate) fn bool(&mut self) -> Result<bool, String> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub(crate) fn optional_u8(&mut self) -> Result<Option<u8>, String> {
+        Ok(if self.bool()? { Some(self.u8()?) } else { None })
+    }
+}
+
+// PackBits: a header byte `n` below 128 is followed by `n + 1` bytes to copy, and one of 128
+// or more by a byte to repeat `n - 126` times
+pub(crate) fn pack(bytes: &[u8]) -> Vec<u8> {
+    let mut packed = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let run = bytes[i..]
+            .iter()
+            .take(129)
+            .take_while(|&&b| b == bytes[i])
+            .count();
+        if run < 3 {
+            i += run;
+            continue;
+        }
+        for literal in bytes[literal_start..i].chunks(128) {
+            packed.push(literal.len() as u8 - 1);
+            packed.extend_from_slice(literal);
+        }
+        packed.push((run + 126) as u8);
+        packed.push(bytes[i]);
+        i += run;
+        literal_start = i;
+    }
+    for literal in bytes[literal_start..].chunks(128) {
+        packed.push(literal.len() as u8 - 1);
+        packed.extend_from_slice(literal);
+    }
+    packed
+}
+
+pub(crate) fn unpack(packed: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    while i < packed.len() {
+        let header = packed[i] as usize;
+        if header < 128 {
+            let end = (i + 2 + header).min(packed.len());
+            bytes.extend_from_slice(&packed[i + 1..end]);
+            i = end;
+        } else {
+            if let Some(&byte) = packed.get(i + 1) {
+                bytes.extend(std::iter::repeat_n(byte, header - 126));
+            }
+            i += 2;
+        }
+    }
+    bytes
+}
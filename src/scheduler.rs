@@ -1,24 +1,33 @@
 use crate::{
-    chip8::Chip8,
-    decoder::Decoder,
+    audio::{AudioBackend, AudioEvent, NullBackend, RodioBackend},
+    chip8::{Chip8, MissedTickPolicy},
+    crash,
+    diagnostics::Warning,
+    frontend::{FrontendEvent, PlaybackMode},
     hardware::Hardware,
     input::{
         Chip8Command, Chip8InputEvent, Chip8KeyEvent, Chip8KeyEventKind, Chip8KeyState,
-        KeyEventHandler,
+        KeyEventHandler, KeyboardLayout, MenuKey,
     },
+    keybindings::BindingTarget,
+    screen::TerminalRenderer,
+    settings::{Settings, SettingsFile},
     util,
 };
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::{
+    select,
+    sync::mpsc,
+    time::{Interval, MissedTickBehavior, interval, sleep},
+};
 
-#[derive(Clone, Debug, PartialEq)]
-pub enum PlaybackMode {
-    Running,
-    Paused,
-    Stepping,
+// Manages messages to the hardware, and shows what it reports on the terminal and speaker
+pub struct HardwareScheduler<'s> {
+    screen: &'s mut TerminalRenderer,
+    audio_sender: mpsc::Sender<AudioEvent>,
+    playback_receiver: mpsc::Receiver<PlaybackMode>, // From the clock, for the debugger
 }
-use tokio::{select, sync::mpsc, time::interval};
-
-// Manages messages to the hardware
-pub struct HardwareScheduler;
 
 pub enum HardwareMessage {
     ExecuteInstruction,
@@ -27,60 +36,182 @@ pub enum HardwareMessage {
     DecrementTimers,
     FlushScreen,
     UpdateDebugInfo,
-    CheckSoundTimer,
     RestartROM,
+    ToggleLog,
+    ToggleFrameDiff,
+    ScrollLog(isize),
+    Warn(Warning),
+    ApplySettings(Settings),
+    SetRestartKey(String),
+    RejectSettings(String),
+    OpenSettings(PathBuf, KeyboardLayout),
+    SettingsKey(MenuKey),
+    SaveState,
+    LoadState,
+    SelectSaveSlot(i8),
+    Rewind,
+    SetSpeed(f64),
+    SkipDelayWait,
+    RecordMacro,
+    PlayMacro,
 }
 
-pub enum SoundMessage {
-    TimerState(u8),
-    PlaybackMode(PlaybackMode),
-}
-
-impl HardwareScheduler {
+impl HardwareScheduler<'_> {
     pub async fn run(
+        &mut self,
         hardware: &mut Hardware<'_>,
         mut inbox: mpsc::Receiver<HardwareMessage>,
-        sound_sender: Option<mpsc::Sender<SoundMessage>>,
+        clock_sender: mpsc::Sender<ClockControlMessage>,
     ) {
         while let Some(message) = inbox.recv().await {
+            while let Ok(mode) = self.playback_receiver.try_recv() {
+                hardware.set_playback_mode(mode);
+            }
             use HardwareMessage::*;
             match message {
                 ExecuteInstruction => {
-                    // Skip execution if CPU is waiting for key input
-                    if !hardware.is_waiting_for_key() {
-                        let raw = hardware.cpu.fetch_current_instruction();
-                        hardware
-                            .execute_instruction(&Decoder::decode(&raw).unwrap())
-                            .await;
+                    hardware.replay_due_input();
+                    hardware.play_due_macro_keys();
+                    // Skip execution if CPU is waiting for key input or the display
+                    if !hardware.is_waiting_for_key() && !hardware.is_waiting_for_vblank() {
+                        let started = Instant::now();
+                        let inst = hardware.current_instruction();
+                        if let Err(e) = hardware.execute_instruction(&inst) {
+                            hardware.stop_with_fault(e);
+                        }
+                        hardware.instruction_timer.record(started.elapsed());
+                        if hardware.take_break_request() {
+                            let _ = clock_sender.send(ClockControlMessage::Pause).await;
+                        }
                     }
                 }
-                HandleKeyEvent(Chip8KeyEvent { key, kind }) => {
+                HandleKeyEvent(Chip8KeyEvent {
+                    key,
+                    kind,
+                    received,
+                }) => {
+                    hardware.latency.input_received(received);
                     // Try to handle key event if CPU is waiting
-                    hardware.handle_key_when_waiting(key, kind);
+                    hardware.live_key_event(key, kind);
                 }
                 DecrementTimers => {
-                    hardware.cpu.dec_delay();
-                    hardware.cpu.dec_sound();
+                    hardware.live_timer_tick();
                 }
                 UpdateKeyState(key_state) => {
-                    hardware.set_key_state(&key_state);
+                    if !hardware.is_replaying() && !hardware.is_playing_macro() {
+                        hardware.set_key_state(&key_state);
+                    }
                 }
                 FlushScreen => {
-                    hardware.screen.flush().unwrap();
+                    hardware.flush_screen();
+                    self.show_events(hardware);
+                    self.screen.flush(&hardware.framebuffer).unwrap();
+                    hardware.latency.frame_flushed();
+                    hardware.record_frame();
+                    hardware.record_rewind_snapshot();
+                    hardware.autosave_if_due();
+                    hardware.record_crash_snapshot();
                 }
                 UpdateDebugInfo => {
                     hardware.update_debug_info();
                 }
-                CheckSoundTimer => {
-                    // Send current sound timer state to sound scheduler
-                    if let Some(ref sender) = sound_sender {
-                        let timer_value = hardware.cpu.get_sound_timer();
-                        let _ = sender.send(SoundMessage::TimerState(timer_value)).await;
-                    }
-                }
                 RestartROM => {
                     hardware.restart_rom();
                 }
+                ToggleLog => {
+                    self.screen.log.toggle();
+                }
+                ToggleFrameDiff => {
+                    hardware.set_show_diff(!hardware.shows_diff());
+                }
+                ScrollLog(amount) => {
+                    self.screen.log.scroll(amount);
+                }
+                Warn(warning) => {
+                    hardware.warn_once(warning);
+                }
+                SkipDelayWait => {
+                    if let Err(e) = hardware.skip_delay_wait() {
+                        hardware.stop_with_fault(e);
+                    }
+                    if hardware.take_break_request() {
+                        let _ = clock_sender.send(ClockControlMessage::Pause).await;
+                    }
+                }
+                RecordMacro => {
+                    hardware.toggle_macro_recording();
+                }
+                PlayMacro => {
+                    hardware.play_macro();
+                }
+                SetSpeed(speed) => {
+                    hardware.set_speed(speed);
+                }
+                SetRestartKey(label) => {
+                    self.screen.set_restart_key(label);
+                }
+                ApplySettings(changes) => {
+                    hardware.apply_settings(&changes);
+                }
+                RejectSettings(error) => {
+                    hardware.reject_settings(error);
+                }
+                OpenSettings(path, layout) => {
+                    hardware.open_settings(path, layout);
+                }
+                SettingsKey(MenuKey::Close) => {
+                    hardware.close_settings();
+                }
+                SettingsKey(key) => {
+                    hardware.handle_settings_key(key);
+                }
+                SaveState => {
+                    hardware.save_state();
+                }
+                LoadState => {
+                    hardware.load_state();
+                }
+                SelectSaveSlot(delta) => {
+                    hardware.select_save_slot(delta);
+                }
+                Rewind => {
+                    hardware.rewind();
+                }
+            }
+            // Anything above may have started or stopped the beep
+            hardware.update_audio();
+            self.show_events(hardware);
+            // The ROM quit or hit a limit, so stop the whole emulator
+            if hardware.has_exited() {
+                break;
+            }
+        }
+    }
+
+    // Puts what the hardware reported on the screen, and passes sound on to the audio backend
+    fn show_events(&mut self, hardware: &mut Hardware<'_>) {
+        for event in hardware.take_frontend_events() {
+            match event {
+                FrontendEvent::Log(message) => self.screen.log.push(message),
+                FrontendEvent::Notice(message, duration) => {
+                    self.screen.show_message(message, duration)
+                }
+                FrontendEvent::ErrorBanner(banner) => self.screen.set_error_banner(banner),
+                FrontendEvent::Audio(event) => {
+                    match event {
+                        AudioEvent::BeepStart => self.screen.set_beeping(true),
+                        AudioEvent::BeepStop => self.screen.set_beeping(false),
+                        AudioEvent::Pattern(_) => {}
+                    }
+                    let _ = self.audio_sender.try_send(event);
+                }
+                FrontendEvent::SettingsMenu(lines) => self.screen.set_settings_menu(lines),
+                FrontendEvent::DebugInfo(debug_info) => self.screen.set_debug_info(debug_info),
+                FrontendEvent::DebugDelta(delta) => self.screen.apply_debug_delta(&delta),
+                FrontendEvent::FrameDiff(diff) => self.screen.set_frame_diff(diff),
+                FrontendEvent::ShowDiff(show_diff) => self.screen.show_diff = show_diff,
+                FrontendEvent::Color(color) => self.screen.color = color,
+                FrontendEvent::CrashSnapshot(snapshot) => crash::record_snapshot(snapshot),
             }
         }
     }
@@ -89,25 +220,39 @@ impl HardwareScheduler {
 // Manages the main clock cycle of the CPU, with pause/play controls
 pub struct ClockSheduler {
     pub hz: f64,
+    pub missed_ticks: MissedTickPolicy,
 }
 
 pub enum ClockControlMessage {
     TogglePausePlay,
+    Pause,
     Step,
     Shutdown,
+    SetHz(f64),
+    SetSpeed(f64), // Multiplies the CPU and timer rates, e.g. 2.0 to run twice as fast
+    Suspend,       // Stop running while a menu is open, without changing the playback mode
+    Resume,
+    Rewind, // Step the machine back in time, in order with the instructions being run
 }
 
 impl ClockSheduler {
+    // With the cap policy, the most ticks that will be run back to back to catch up
+    const MAX_CATCH_UP_TICKS: u32 = 10;
+
     pub async fn run(
         &self,
         mut inbox: mpsc::Receiver<ClockControlMessage>,
         hardware_sender: mpsc::Sender<HardwareMessage>,
+        timer_sender: mpsc::Sender<f64>,
         initial_is_running: bool,
         playback_state_sender: Option<mpsc::Sender<PlaybackMode>>,
-        sound_sender: Option<mpsc::Sender<SoundMessage>>,
     ) {
-        let mut exec_interval = interval(util::hertz(self.hz));
+        let mut hz = self.hz;
+        let mut speed = 1.0;
+        let mut period = util::hertz(hz);
+        let mut exec_interval = self.exec_interval(period);
         let mut is_running = initial_is_running;
+        let mut suspended = false;
         let mut single_step_pending = false;
 
         // Send initial state
@@ -118,10 +263,7 @@ impl ClockSheduler {
         };
 
         if let Some(ref sender) = playback_state_sender {
-            let _ = sender.send(initial_mode.clone()).await;
-        }
-        if let Some(ref sender) = sound_sender {
-            let _ = sender.send(SoundMessage::PlaybackMode(initial_mode)).await;
+            let _ = sender.send(initial_mode).await;
         }
         loop {
             select! {
@@ -135,28 +277,55 @@ impl ClockSheduler {
                            // Update playback state
                            let mode = if is_running { PlaybackMode::Running } else { PlaybackMode::Paused };
                            if let Some(ref sender) = playback_state_sender {
-                               let _ = sender.send(mode.clone()).await;
-                           }
-                           if let Some(ref sender) = sound_sender {
-                               let _ = sender.send(SoundMessage::PlaybackMode(mode)).await;
+                               let _ = sender.send(mode).await;
                            }
                        },
+                        Some(ClockControlMessage::Pause) => {
+                            if is_running {
+                                is_running = false;
+                                if let Some(ref sender) = playback_state_sender {
+                                    let _ = sender.send(PlaybackMode::Paused).await;
+                                }
+                            }
+                        },
                         Some(ClockControlMessage::Shutdown) => break,
+                        Some(ClockControlMessage::SetHz(new_hz)) => {
+                            hz = new_hz;
+                            period = util::hertz(hz * speed);
+                            exec_interval = self.exec_interval(period);
+                        },
+                        Some(ClockControlMessage::SetSpeed(new_speed)) => {
+                            speed = new_speed;
+                            period = util::hertz(hz * speed);
+                            exec_interval = self.exec_interval(period);
+                            let _ = timer_sender.send(speed).await;
+                            let _ = hardware_sender.send(HardwareMessage::SetSpeed(speed)).await;
+                        },
+                        Some(ClockControlMessage::Suspend) => suspended = true,
+                        Some(ClockControlMessage::Resume) => {
+                            suspended = false;
+                            exec_interval.reset();
+                        },
+                        Some(ClockControlMessage::Rewind) => {
+                            let _ = hardware_sender.send(HardwareMessage::Rewind).await;
+                        },
                         Some(ClockControlMessage::Step) => {
                             single_step_pending = true;
                             // Update playback state to show stepping
                             if let Some(ref sender) = playback_state_sender {
                                 let _ = sender.send(PlaybackMode::Stepping).await;
                             }
-                            if let Some(ref sender) = sound_sender {
-                                let _ = sender.send(SoundMessage::PlaybackMode(PlaybackMode::Stepping)).await;
-                            }
                         },
                         None => break,
                     }
                 },
-                _ = exec_interval.tick(), if is_running => {
+                tick = exec_interval.tick(), if is_running && !suspended => {
                     let _ = hardware_sender.send(HardwareMessage::ExecuteInstruction).await;
+                    if self.missed_ticks == MissedTickPolicy::Cap
+                        && tick.elapsed() > period * Self::MAX_CATCH_UP_TICKS
+                    {
+                        exec_interval.reset();
+                    }
                 },
                 _ = async {}, if single_step_pending => {
                     let _ = hardware_sender.send(HardwareMessage::ExecuteInstruction).await;
@@ -165,6 +334,15 @@ impl ClockSheduler {
             }
         }
     }
+
+    fn exec_interval(&self, period: Duration) -> Interval {
+        let mut exec_interval = interval(period);
+        exec_interval.set_missed_tick_behavior(match self.missed_ticks {
+            MissedTickPolicy::Skip => MissedTickBehavior::Skip,
+            MissedTickPolicy::Burst | MissedTickPolicy::Cap => MissedTickBehavior::Burst,
+        });
+        exec_interval
+    }
 }
 
 // Manages the decrementing of the CPUs timers
@@ -173,10 +351,21 @@ struct TimerScheduler {
 }
 
 impl TimerScheduler {
-    pub async fn run(&self, hardware_sender: mpsc::Sender<HardwareMessage>) {
+    pub async fn run(
+        &self,
+        hardware_sender: mpsc::Sender<HardwareMessage>,
+        mut speed_inbox: mpsc::Receiver<f64>,
+    ) {
         let mut exec_interval = interval(util::hertz(self.hz));
         loop {
-            exec_interval.tick().await;
+            select! {
+                _ = exec_interval.tick() => {},
+                // Timers speed up and slow down with the CPU, as games time themselves by them
+                Some(speed) = speed_inbox.recv() => {
+                    exec_interval = interval(util::hertz(self.hz * speed));
+                    continue;
+                },
+            }
             if hardware_sender
                 .send(HardwareMessage::DecrementTimers)
                 .await
@@ -193,9 +382,9 @@ struct ScreenScheduler {
     pub hz: f64,
 }
 
-// Manages sound playback using rodio
+// Passes audio events from the hardware to the audio backend
 pub struct SoundScheduler {
-    pub hz: f64, // How often to check sound timer state
+    pub min_beep: Duration, // Shorter beeps are stretched to this length
 }
 
 impl ScreenScheduler {
@@ -228,93 +417,89 @@ impl ScreenScheduler {
 impl SoundScheduler {
     pub async fn run(
         &self,
-        mut inbox: mpsc::Receiver<SoundMessage>,
-        hardware_sender: mpsc::Sender<HardwareMessage>,
+        mut backend: Box<dyn AudioBackend>,
+        mut inbox: mpsc::Receiver<AudioEvent>,
     ) {
-        use rodio::source::SineWave;
-        use rodio::{OutputStreamBuilder, Sink, Source};
-        use std::time::Duration;
-
-        // Initialize rodio audio system
-        let stream_handle = match OutputStreamBuilder::open_default_stream() {
-            Ok(handle) => handle,
-            Err(_) => {
-                // Audio system not available, run silently
-                return;
-            }
-        };
-
-        let sink = Sink::connect_new(stream_handle.mixer());
-
-        let mut timer_check_interval = interval(util::hertz(self.hz));
-        let mut current_timer_value = 0u8;
-        let mut is_playing = false;
-        let mut playback_mode = PlaybackMode::Running;
-
-        // Create a simple beep tone (sine wave at ~440Hz)
-        let create_beep = || {
-            SineWave::new(440.0)
-                .take_duration(Duration::from_millis(100))
-                .repeat_infinite()
-                .amplify(0.1)
-        };
-
-        loop {
-            select! {
-                message = inbox.recv() => {
-                    match message {
-                                                Some(SoundMessage::TimerState(timer_value)) => {
-                            current_timer_value = timer_value;
-
-                            // Start playing if timer > 0 and not currently playing
-                            if timer_value > 0 && !is_playing && playback_mode == PlaybackMode::Running {
-                                sink.append(create_beep());
-                                sink.play();
-                                is_playing = true;
-                            }
-                            // Stop playing if timer == 0 and currently playing
-                            else if timer_value == 0 && is_playing {
-                                sink.stop();
-                                is_playing = false;
-                            }
-                        },
-                        Some(SoundMessage::PlaybackMode(mode)) => {
-                            playback_mode = mode.clone();
-                            match mode {
-                                PlaybackMode::Running => {
-                                    if current_timer_value > 0 && !is_playing {
-                                        sink.append(create_beep());
-                                        sink.play();
-                                        is_playing = true;
-                                    }
-                                },
-                                PlaybackMode::Paused | PlaybackMode::Stepping => {
-                                    if is_playing {
-                                        sink.pause();
-                                    }
-                                }
-                            }
-                        },
-                        None => break,
+        let mut beep_started = Instant::now();
+        while let Some(event) = inbox.recv().await {
+            match event {
+                AudioEvent::BeepStart => beep_started = Instant::now(),
+                AudioEvent::BeepStop => {
+                    let remaining = self.min_beep.saturating_sub(beep_started.elapsed());
+                    if !remaining.is_zero() {
+                        sleep(remaining).await;
                     }
-                },
-                _ = timer_check_interval.tick() => {
-                    // Periodically request sound timer state from hardware
-                    let _ = hardware_sender.send(HardwareMessage::CheckSoundTimer).await;
                 }
+                AudioEvent::Pattern(_) => {}
             }
+            backend.handle(event);
         }
     }
 }
 
 pub struct InputScheduler {
     key_state: Chip8KeyState,
+    settings_path: Option<PathBuf>, // Where the settings page saves to, if it's available
+    speed: usize,                   // Index into `SPEEDS`
+    fast_forward: bool,
+    slow_motion: bool,
+}
+
+impl Default for InputScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InputScheduler {
+    // The speeds the speed up and slow down keys step through
+    pub const SPEEDS: [f64; 7] = [0.125, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+    const NORMAL_SPEED: usize = 3;
+    const FAST_FORWARD_SPEED: f64 = 8.0;
+    const SLOW_MOTION_SPEED: f64 = 0.25;
+
     pub fn new() -> Self {
         Self {
             key_state: Chip8KeyState::default(),
+            settings_path: None,
+            speed: Self::NORMAL_SPEED,
+            fast_forward: false,
+            slow_motion: false,
+        }
+    }
+
+    // The speed to run at, after a speed command. None when it didn't change
+    pub fn change_speed(&mut self, command: Chip8Command, kind: Chip8KeyEventKind) -> Option<f64> {
+        let before = self.current_speed();
+        match (command, kind) {
+            (Chip8Command::SpeedUp, Chip8KeyEventKind::Press) => {
+                self.speed = (self.speed + 1).min(Self::SPEEDS.len() - 1);
+            }
+            (Chip8Command::SlowDown, Chip8KeyEventKind::Press) => {
+                self.speed = self.speed.saturating_sub(1);
+            }
+            // Whichever was pressed last wins, as only one held key's release is watched for
+            (Chip8Command::FastForward, kind) => {
+                self.fast_forward = kind == Chip8KeyEventKind::Press;
+                self.slow_motion = false;
+            }
+            (Chip8Command::SlowMotion, kind) => {
+                self.slow_motion = kind == Chip8KeyEventKind::Press;
+                self.fast_forward = false;
+            }
+            _ => {}
+        }
+        let after = self.current_speed();
+        (after != before).then_some(after)
+    }
+
+    fn current_speed(&self) -> f64 {
+        if self.fast_forward {
+            Self::FAST_FORWARD_SPEED
+        } else if self.slow_motion {
+            Self::SLOW_MOTION_SPEED
+        } else {
+            Self::SPEEDS[self.speed]
         }
     }
 
@@ -325,10 +510,19 @@ impl InputScheduler {
         clock_sender: mpsc::Sender<ClockControlMessage>,
         debug: bool,
     ) {
+        if !input.reports_key_releases() {
+            let _ = hardware_sender
+                .send(HardwareMessage::Warn(Warning::NoKeyReleaseEvents))
+                .await;
+        }
         loop {
             let input_event = input.next_input_event().await;
             match input_event {
-                Chip8InputEvent::Chip8KeyEvent(Chip8KeyEvent { key, kind }) => {
+                Chip8InputEvent::Chip8KeyEvent(Chip8KeyEvent {
+                    key,
+                    kind,
+                    received,
+                }) => {
                     // Update local key state
                     if kind == Chip8KeyEventKind::Press {
                         self.key_state.press(key);
@@ -338,7 +532,11 @@ impl InputScheduler {
 
                     // Send key event to hardware (for GetKey instruction handling)
                     let _ = hardware_sender
-                        .send(HardwareMessage::HandleKeyEvent(Chip8KeyEvent { key, kind }))
+                        .send(HardwareMessage::HandleKeyEvent(Chip8KeyEvent {
+                            key,
+                            kind,
+                            received,
+                        }))
                         .await;
 
                     // Update hardware key state (for SkipKeyPress instructions)
@@ -346,6 +544,20 @@ impl InputScheduler {
                         .send(HardwareMessage::UpdateKeyState(self.key_state))
                         .await;
                 }
+                Chip8InputEvent::CommandEvent {
+                    command:
+                        command @ (Chip8Command::SpeedUp
+                        | Chip8Command::SlowDown
+                        | Chip8Command::FastForward
+                        | Chip8Command::SlowMotion),
+                    kind,
+                } => {
+                    if let Some(speed) = self.change_speed(command, kind) {
+                        let _ = clock_sender
+                            .send(ClockControlMessage::SetSpeed(speed))
+                            .await;
+                    }
+                }
                 Chip8InputEvent::CommandEvent {
                     command,
                     kind: Chip8KeyEventKind::Press,
@@ -362,58 +574,214 @@ impl InputScheduler {
                         Chip8Command::DebugStep if debug => {
                             let _ = clock_sender.send(ClockControlMessage::Step).await;
                         }
+                        Chip8Command::SkipDelayWait if debug => {
+                            let _ = hardware_sender.send(HardwareMessage::SkipDelayWait).await;
+                        }
                         Chip8Command::Restart => {
                             let _ = hardware_sender.send(HardwareMessage::RestartROM).await;
                         }
+                        Chip8Command::ToggleFrameDiff if debug => {
+                            let _ = hardware_sender.send(HardwareMessage::ToggleFrameDiff).await;
+                        }
+                        Chip8Command::ToggleLog => {
+                            let _ = hardware_sender.send(HardwareMessage::ToggleLog).await;
+                        }
+                        Chip8Command::ScrollLogUp => {
+                            let _ = hardware_sender.send(HardwareMessage::ScrollLog(1)).await;
+                        }
+                        Chip8Command::ScrollLogDown => {
+                            let _ = hardware_sender.send(HardwareMessage::ScrollLog(-1)).await;
+                        }
+                        Chip8Command::SaveState => {
+                            let _ = hardware_sender.send(HardwareMessage::SaveState).await;
+                        }
+                        Chip8Command::LoadState => {
+                            let _ = hardware_sender.send(HardwareMessage::LoadState).await;
+                        }
+                        Chip8Command::Rewind => {
+                            let _ = clock_sender.send(ClockControlMessage::Rewind).await;
+                        }
+                        Chip8Command::RecordMacro => {
+                            let _ = hardware_sender.send(HardwareMessage::RecordMacro).await;
+                        }
+                        Chip8Command::PlayMacro => {
+                            let _ = hardware_sender.send(HardwareMessage::PlayMacro).await;
+                        }
+                        Chip8Command::PreviousSaveSlot => {
+                            let _ = hardware_sender
+                                .send(HardwareMessage::SelectSaveSlot(-1))
+                                .await;
+                        }
+                        Chip8Command::NextSaveSlot => {
+                            let _ = hardware_sender
+                                .send(HardwareMessage::SelectSaveSlot(1))
+                                .await;
+                        }
+                        Chip8Command::OpenSettings => {
+                            if let Some(ref path) = self.settings_path {
+                                input.set_menu_open(true);
+                                let _ = clock_sender.send(ClockControlMessage::Suspend).await;
+                                let _ = hardware_sender
+                                    .send(HardwareMessage::OpenSettings(
+                                        path.clone(),
+                                        input.layout(),
+                                    ))
+                                    .await;
+                            }
+                        }
                         _ => {}
                     };
                 }
+                Chip8InputEvent::MenuEvent(key) => {
+                    if key == MenuKey::Close {
+                        input.set_menu_open(false);
+                        let _ = clock_sender.send(ClockControlMessage::Resume).await;
+                    }
+                    let _ = hardware_sender
+                        .send(HardwareMessage::SettingsKey(key))
+                        .await;
+                }
                 _ => {}
             };
         }
     }
 }
 
+// Applies edits to the settings file while the emulator runs
+struct SettingsScheduler {
+    file: SettingsFile,
+}
+
+impl SettingsScheduler {
+    // How often the file is checked for changes
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub async fn run(
+        &mut self,
+        input: &KeyEventHandler,
+        hardware_sender: mpsc::Sender<HardwareMessage>,
+        clock_sender: mpsc::Sender<ClockControlMessage>,
+    ) {
+        let mut poll_interval = interval(Self::POLL_INTERVAL);
+        loop {
+            poll_interval.tick().await;
+            let message = match self.file.poll() {
+                Ok(Some(changes)) => {
+                    if let Some(layout) = changes.layout {
+                        input.set_layout(layout);
+                    }
+                    if let Some(ref keys) = changes.keys {
+                        input.set_bindings(keys.clone());
+                    }
+                    if changes.layout.is_some() || changes.keys.is_some() {
+                        let restart = BindingTarget::Command(Chip8Command::Restart);
+                        let _ = hardware_sender
+                            .send(HardwareMessage::SetRestartKey(input.label(restart)))
+                            .await;
+                    }
+                    if let Some(hz) = changes.cpu_hz {
+                        let _ = clock_sender.send(ClockControlMessage::SetHz(hz)).await;
+                    }
+                    HardwareMessage::ApplySettings(changes)
+                }
+                Ok(None) => continue,
+                Err(e) => HardwareMessage::RejectSettings(e.to_string()),
+            };
+            if hardware_sender.send(message).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
 pub struct Chip8Orchaestrator;
 
 impl Chip8Orchaestrator {
-    pub async fn run(chip8: &mut Chip8<'_>) {
+    pub async fn run(chip8: &mut Chip8<'_>, screen: &mut TerminalRenderer) {
         // Comm channels
         let (hard_send, hard_recv) = mpsc::channel::<HardwareMessage>(100);
         let (clock_send, clock_recv) = mpsc::channel::<ClockControlMessage>(100);
         let (playback_send, playback_recv) = mpsc::channel::<PlaybackMode>(100);
-        let (sound_send, sound_recv) = mpsc::channel::<SoundMessage>(100);
+        let (audio_send, audio_recv) = mpsc::channel::<AudioEvent>(100);
+        let (speed_send, speed_recv) = mpsc::channel::<f64>(100);
 
         let timer_scheduler = TimerScheduler {
             hz: Chip8::TIMER_HZ,
         };
         let clock_scheulder = ClockSheduler {
-            hz: Chip8::CPU_FREQ_HZ,
+            hz: chip8.config.cpu_hz,
+            missed_ticks: chip8.config.missed_ticks,
         };
         let screen_scheulder = ScreenScheduler {
             hz: Chip8::SCREEN_HZ,
         };
         let sound_scheduler = SoundScheduler {
-            hz: Chip8::TIMER_HZ,
+            min_beep: chip8.config.min_beep,
         };
-        let mut input_scheduler = InputScheduler::new();
-
-        // Set up hardware to receive playback state updates
-        chip8.hardware.set_playback_receiver(playback_recv);
+        let mut input_scheduler = InputScheduler {
+            settings_path: chip8.config.settings_path.clone(),
+            ..InputScheduler::new()
+        };
+        let mut settings_scheduler =
+            chip8
+                .config
+                .settings_path
+                .clone()
+                .map(|path| SettingsScheduler {
+                    file: SettingsFile::new(path),
+                });
+
+        // Fall back to silence, rather than not running at all, without an audio device. Unless
+        // asked for something else, the beep is shown on screen instead
+        let audible = chip8.config.bell.is_none_or(|bell| bell.is_audible());
+        let device = if audible { RodioBackend::open() } else { None };
+        let visible = match chip8.config.bell {
+            Some(bell) => bell.is_visible(),
+            None => device.is_none(),
+        };
+        let audio_backend: Box<dyn AudioBackend> = match device {
+            Some(backend) => Box::new(backend),
+            None => {
+                if audible {
+                    let fallback = if visible {
+                        "showing the beep on screen instead"
+                    } else {
+                        "running without sound"
+                    };
+                    screen
+                        .log
+                        .push(format!("No audio device found, {fallback}"));
+                }
+                Box::new(NullBackend)
+            }
+        };
+        screen.visual_bell = visible;
+        let mut hardware_scheduler = HardwareScheduler {
+            screen,
+            audio_sender: audio_send,
+            playback_receiver: playback_recv,
+        };
+        let input = &chip8.input;
 
         select! {
-            _ = timer_scheduler.run(hard_send.clone()) => {},
+            _ = timer_scheduler.run(hard_send.clone(), speed_recv) => {},
             _ = clock_scheulder.run(
                 clock_recv,
                 hard_send.clone(),
+                speed_send,
                 !chip8.config.debug,
                 if chip8.config.debug { Some(playback_send) } else { None },
-                Some(sound_send.clone())
             ) => {},
             _ = screen_scheulder.run(hard_send.clone(), chip8.config.debug) => {},
-            _ = sound_scheduler.run(sound_recv, hard_send.clone()) => {},
-            _ = HardwareScheduler::run(&mut chip8.hardware, hard_recv, Some(sound_send.clone())) => {},
-            _ = input_scheduler.run(&chip8.input, hard_send, clock_send, chip8.config.debug) => {},
+            _ = sound_scheduler.run(audio_backend, audio_recv) => {},
+            _ = hardware_scheduler.run(&mut chip8.hardware, hard_recv, clock_send.clone()) => {},
+            _ = input_scheduler.run(input, hard_send.clone(), clock_send.clone(), chip8.config.debug) => {},
+            _ = async {
+                match settings_scheduler {
+                    Some(ref mut scheduler) => scheduler.run(input, hard_send, clock_send).await,
+                    None => std::future::pending().await,
+                }
+            } => {},
         }
     }
 }
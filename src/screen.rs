@@ -1,139 +1,264 @@
+use std::collections::{HashSet, VecDeque};
 use std::io::{Write, stdout};
+use std::time::{Duration, Instant};
 
 use crossterm::{
     self,
     cursor::{Hide, Show},
-    execute, queue,
+    event::{DisableBracketedPaste, EnableBracketedPaste},
+    execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 
 use crate::{
-    input::Chip8KeyState,
-    primitive::{Instruction, RawInstruction},
-    scheduler::PlaybackMode,
+    annotations::MemoryAnnotations,
+    framebuffer::FrameBuffer,
+    frontend::{DebugDelta, DebugInfo, PlaybackMode, ScreenColor},
+    machinestate::StateDiff,
+    termwriter::TerminalWriter,
+    timing::FrameSkips,
 };
 
-#[derive(Debug, Clone)]
-pub struct DebugInfo {
-    pub current_pc: u16,
-    pub raw_instruction: RawInstruction,
-    pub decoded_instruction: Instruction,
-    pub index_register: u16,
-    pub delay_timer: u8,
-    pub sound_timer: u8,
-    pub registers: [u8; 16],
-    pub key_state: Chip8KeyState,
-    pub playback_mode: PlaybackMode,
+// Recent messages (warnings, faults) shown in a toggleable, scrollable panel, since stderr
+// isn't visible while the emulator is running
+#[derive(Default)]
+pub struct LogBuffer {
+    lines: VecDeque<String>,
+    scroll: usize, // Number of lines scrolled back from the newest message
+    visible: bool,
+    unread: usize, // Messages pushed while the panel was hidden
 }
 
-macro_rules! screen_color {
-    (
-        pub enum $name:ident {
-            $($variant:ident),* $(,)?
+impl LogBuffer {
+    const CAPACITY: usize = 200;
+    const PANEL_LINES: usize = 8;
+
+    pub fn push(&mut self, message: String) {
+        if self.lines.len() == Self::CAPACITY {
+            self.lines.pop_front();
         }
-    ) => {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
-        pub enum $name {
-            $($variant,)*
+        self.lines.push_back(message);
+        if !self.visible {
+            self.unread += 1;
         }
+    }
 
-        impl From<$name> for crossterm::style::Color {
-            fn from(screen_color: $name) -> Self {
-                match screen_color {
-                    $($name::$variant => crossterm::style::Color::$variant,)*
-                }
-            }
-        }
-    };
-}
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.scroll = 0;
+        self.unread = 0;
+    }
 
-screen_color!(
-    pub enum ScreenColor {
-        Red,
-        DarkRed,
-        Green,
-        DarkGreen,
-        Yellow,
-        DarkYellow,
-        Blue,
-        DarkBlue,
-        Magenta,
-        DarkMagenta,
-        Cyan,
-        DarkCyan,
-        White,
-        Grey,
-    }
-);
-
-impl ToString for ScreenColor {
-    fn to_string(&self) -> String {
-        format!("{:#?}", self).to_lowercase()
+    pub fn last(&self) -> Option<&str> {
+        self.lines.back().map(String::as_str)
+    }
+
+    // Positive amounts scroll back towards older messages
+    pub fn scroll(&mut self, amount: isize) {
+        let max_scroll = self.lines.len().saturating_sub(Self::PANEL_LINES);
+        self.scroll = self.scroll.saturating_add_signed(amount).min(max_scroll);
+    }
+
+    // The lines currently scrolled into view, oldest first
+    fn visible_lines(&self) -> Vec<String> {
+        if self.lines.is_empty() {
+            return vec!["(no messages)".to_string()];
+        }
+        let end = self.lines.len() - self.scroll;
+        let start = end.saturating_sub(Self::PANEL_LINES);
+        self.lines.range(start..end).cloned().collect()
     }
 }
 
-pub struct Screen {
+// Draws a frame buffer to the terminal, along with the title, debug panels, and log
+pub struct TerminalRenderer {
     pub color: ScreenColor,
-    pixels: [bool; Self::N_PIXELS as usize],
+    pub log: LogBuffer,
+    pub annotations: MemoryAnnotations, // Labels shown next to memory in the debug overlay
+    // What the last frame that drew changed, and its pixels, for highlighting
+    frame_diff: StateDiff,
+    changed_pixels: HashSet<(u8, u8)>,
+    pub show_diff: bool,
+    pub visual_bell: bool, // Show an indicator while the beep sounds
+    beeping: bool,
     debug_info: Option<DebugInfo>,
+    subtitle: Option<String>,
+    key_hints: Option<String>, // What the ROM's keys do, shown while paused
+    message: Option<(String, Instant)>, // Shown over the display until the instant
+    error_banner: Option<String>, // Shown over the display until it's cleared
+    settings_menu: Option<Vec<String>>, // The settings page's lines, while it's open
+    restart_key: String,
+    in_terminal: bool, // Whether the renderer has taken over the terminal
+    writer: Option<TerminalWriter>, // Writes frames while in the terminal
+    frame_skips: FrameSkips,
 }
 
-impl Screen {
-    pub const N_ROWS: u8 = 32;
-    pub const N_COLS: u8 = 64;
-    pub const N_PIXELS: u16 = Self::N_ROWS as u16 * Self::N_COLS as u16;
-
+impl TerminalRenderer {
     pub fn new(color: ScreenColor) -> Self {
-        execute!(std::io::stdout(), EnterAlternateScreen, Hide).expect("Could not create terminal");
         Self {
-            pixels: [false; Self::N_PIXELS as usize],
+            frame_diff: StateDiff::default(),
+            changed_pixels: HashSet::new(),
+            show_diff: false,
             debug_info: None,
+            subtitle: None,
+            key_hints: None,
+            message: None,
+            error_banner: None,
+            visual_bell: false,
+            beeping: false,
+            settings_menu: None,
+            restart_key: "P".to_string(),
+            in_terminal: false,
+            writer: None,
+            frame_skips: FrameSkips::default(),
+            log: LogBuffer::default(),
+            annotations: MemoryAnnotations::default(),
             color,
         }
     }
 
-    pub fn get_pixel(&self, x: u8, y: u8) -> Option<bool> {
-        if x >= Self::N_COLS || y >= Self::N_ROWS {
-            None
+    // Switches to the alternate screen, which is left again when the renderer is dropped
+    pub fn enter_terminal(&mut self) {
+        // Pastes arrive as one event, so they can be typed into the keypad
+        execute!(
+            std::io::stdout(),
+            EnterAlternateScreen,
+            EnableBracketedPaste,
+            Hide
+        )
+        .expect("Could not create terminal");
+        self.in_terminal = true;
+        self.writer = Some(TerminalWriter::start());
+    }
+
+    fn pixel_color(&self, frame: &FrameBuffer, x: u8, y: u8) -> crossterm::style::Color {
+        use crossterm::style::Color;
+        if self.show_diff && self.changed_pixels.contains(&(x, y)) {
+            Color::Red // Drawn or erased by the last frame
+        } else if !frame.is_lit(x, y) {
+            Color::Black
         } else {
-            Some(self.pixels[Self::get_idx(x, y)])
+            // Pixels on XO-CHIP's second plane, or on both
+            match frame.color_index(x, y) {
+                2 => Color::DarkGrey,
+                3 => Color::White,
+                _ => self.color.into(),
+            }
         }
     }
 
-    pub fn set_pixel(&mut self, x: u8, y: u8, value: bool) {
-        if x >= Self::N_COLS || y >= Self::N_ROWS {
-            return;
+    pub fn set_beeping(&mut self, beeping: bool) {
+        self.beeping = beeping;
+    }
+
+    pub fn set_settings_menu(&mut self, lines: Option<Vec<String>>) {
+        self.settings_menu = lines;
+    }
+
+    // Extra text shown next to the title, outside of debug mode
+    pub fn set_subtitle(&mut self, subtitle: String) {
+        self.subtitle = Some(subtitle);
+    }
+
+    // `key_hints` describes the ROM's controls in terms of the keyboard layout
+    pub fn set_key_hints(&mut self, key_hints: String) {
+        self.key_hints = Some(key_hints);
+    }
+
+    // Shows the key hints over the display for a while, if there are any
+    pub fn show_key_hints(&mut self, duration: Duration) {
+        if let Some(ref key_hints) = self.key_hints {
+            self.show_message(format!("Controls: {key_hints}"), duration);
         }
-        self.pixels[Self::get_idx(x, y)] = value;
     }
 
-    pub fn clear(&mut self) {
-        self.pixels.fill(false);
+    // Shows a short message over the bottom of the display, replacing any that's showing
+    pub fn show_message(&mut self, message: String, duration: Duration) {
+        self.message = Some((message, Instant::now() + duration));
+    }
+
+    pub fn set_error_banner(&mut self, banner: Option<String>) {
+        self.error_banner = banner;
+    }
+
+    // Key shown in the controls hint, which depends on the keyboard layout and bindings
+    pub fn set_restart_key(&mut self, key: String) {
+        self.restart_key = key;
+    }
+
+    // What the debug overlay shows, once debugging
+    pub fn debug_info(&self) -> Option<&DebugInfo> {
+        self.debug_info.as_ref()
     }
 
     pub fn set_debug_info(&mut self, debug_info: DebugInfo) {
         self.debug_info = Some(debug_info);
     }
 
+    pub fn set_frame_diff(&mut self, diff: StateDiff) {
+        self.changed_pixels = diff.pixels.iter().copied().collect();
+        self.frame_diff = diff;
+    }
+
+    pub fn apply_debug_delta(&mut self, delta: &DebugDelta) {
+        if let Some(ref mut debug_info) = self.debug_info {
+            debug_info.apply(delta);
+        }
+    }
+
     // Draws to the console
-    pub fn flush(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn flush(&mut self, frame: &FrameBuffer) -> Result<(), Box<dyn std::error::Error>> {
         use crossterm::{cursor::*, queue, style::*};
-        use std::io::stdout;
+        // Headless cores never take over the terminal, so many can run at once
+        if !self.in_terminal {
+            return Ok(());
+        }
+        // Rendered into a buffer, which is written to the terminal all at once
+        let out = &mut Vec::new();
         let (term_width, term_height) = crossterm::terminal::size()?;
 
+        // Skip drawing while the terminal is still busy with an earlier frame, so a terminal
+        // that can't keep up shows fewer frames rather than slowing down emulation
+        let busy = self.writer.as_ref().is_some_and(TerminalWriter::is_busy);
+        self.frame_skips.record(busy);
+        if busy {
+            return Ok(());
+        }
+        // Terminals that support synchronized updates show the whole frame at once, rather than
+        // as it arrives
+        queue!(out, crossterm::terminal::BeginSynchronizedUpdate)?;
+
         // Calculate centering offset
-        let display_width = (Screen::N_COLS * 2) as u16;
-        let display_height = Screen::N_ROWS as u16;
+        // The display takes the same cells in either resolution
+        let display_width = (FrameBuffer::LORES_COLS as u16) * 2;
+        let display_height = FrameBuffer::LORES_ROWS as u16;
         let offset_x = (term_width.saturating_sub(display_width)) / 2;
 
-        // Check if we have any debug info to display
+        // Panels shown below the display: debug info if debugging, and the log if toggled on
+        let mut panels = match self.debug_info {
+            Some(ref debug) => self.debug_panels(debug),
+            None => Vec::new(),
+        };
+        if self.log.visible {
+            panels.push(self.log_panel());
+        }
+        if let Some(ref lines) = self.settings_menu {
+            panels.push(Panel::new(
+                "SETTINGS",
+                Color::Yellow,
+                u8::MAX,
+                lines.clone(),
+            ));
+        }
         let has_debug_info = self.debug_info.is_some();
+        let panels_height = panels.iter().map(Panel::height).sum::<u16>();
 
         // Reserve space at bottom
         let bottom_reserve = if has_debug_info {
-            6 // Up to 4 debug lines + some padding (no title/escape when debugging)
+            // All the panels + some padding (no title/escape when debugging)
+            panels_height + 2
         } else {
-            4 // Just title + escape + padding
+            4 + panels_height // Title + escape + padding, and any panels
         };
 
         let available_height = term_height.saturating_sub(bottom_reserve);
@@ -143,111 +268,249 @@ impl Screen {
             available_height.saturating_sub(display_height) / 2
         };
 
-        // Draw display centered
-        for y in 0..Screen::N_ROWS {
-            queue!(stdout(), MoveTo(offset_x, offset_y + y as u16))?;
-            for x in 0..Screen::N_COLS {
-                let pixel = self.get_pixel(x, y).unwrap();
-                if pixel {
-                    queue!(stdout(), SetBackgroundColor(self.color.into()), Print("  "))?;
-                } else {
-                    queue!(stdout(), SetBackgroundColor(Color::Black), Print("  "))?;
-                }
+        // Draw display centered. Low resolution pixels are two cells wide, and high resolution
+        // ones are one cell wide and half a cell tall, as the halves of a `▀`
+        let cell = if frame.is_hires() { "▀" } else { "  " };
+        for row in 0..display_height {
+            queue!(out, MoveTo(offset_x, offset_y + row))?;
+            let cells: Vec<Colors> = (0..frame.width())
+                .map(|x| {
+                    if frame.is_hires() {
+                        let (top, bottom) = (row as u8 * 2, row as u8 * 2 + 1);
+                        Colors::new(
+                            self.pixel_color(frame, x, top),
+                            self.pixel_color(frame, x, bottom),
+                        )
+                    } else {
+                        Colors {
+                            foreground: None,
+                            background: Some(self.pixel_color(frame, x, row as u8)),
+                        }
+                    }
+                })
+                .collect();
+            // Runs of cells with the same colors are printed together, with one color change
+            for run in cells.chunk_by(|a, b| a == b) {
+                queue!(out, SetColors(run[0]), Print(cell.repeat(run.len())))?;
             }
-            queue!(stdout(), ResetColor)?;
+            queue!(out, ResetColor)?;
+        }
+
+        // The visual bell, over the top right corner of the display
+        if self.visual_bell && self.beeping {
+            let text = " BEEP ";
+            queue!(
+                out,
+                MoveTo(
+                    (offset_x + display_width)
+                        .min(term_width)
+                        .saturating_sub(text.len() as u16),
+                    offset_y
+                ),
+                SetColors(Colors::new(Color::Black, Color::White)),
+                Print(text),
+                ResetColor
+            )?;
+        }
+
+        // Message over the bottom of the display, for a few seconds
+        if let Some((ref message, until)) = self.message {
+            if Instant::now() < until {
+                let text = format!(" {message} ");
+                let text: String = text.chars().take(display_width as usize).collect();
+                let x = offset_x + (display_width - text.chars().count() as u16) / 2;
+                queue!(
+                    out,
+                    MoveTo(x, offset_y + display_height - 2),
+                    SetColors(Colors::new(Color::Black, Color::White)),
+                    Print(text),
+                    ResetColor
+                )?;
+            } else {
+                self.message = None;
+            }
+        }
+
+        // Error over the middle of the display, e.g. when halted on an invalid instruction
+        if let Some(ref banner) = self.error_banner {
+            let text = format!(" {banner} ");
+            let text: String = text.chars().take(display_width as usize).collect();
+            let x = offset_x + (display_width - text.chars().count() as u16) / 2;
+            queue!(
+                out,
+                MoveTo(x, offset_y + display_height / 2),
+                SetColors(Colors::new(Color::White, Color::Red)),
+                Print(text),
+                ResetColor
+            )?;
         }
 
         // Add title (only when not in debug or step mode to save space)
         if !has_debug_info {
+            let title = match self.subtitle {
+                Some(ref subtitle) => format!("CHIP-8 Emulator | {subtitle}"),
+                None => "CHIP-8 Emulator".to_string(),
+            };
+            let unread = match self.log.unread {
+                0 => String::new(),
+                count => format!(" ({count} new)"),
+            };
+            let restart_key = &self.restart_key;
             queue!(
-                stdout(),
+                out,
                 MoveTo(offset_x, offset_y.saturating_sub(2)),
-                Print("CHIP-8 Emulator"),
+                Print(title),
                 MoveTo(offset_x, offset_y + display_height + 1),
-                Print("Press 'Escape' to quit, Press 'P' to restart")
+                Print(format!(
+                    "Press 'Escape' to quit, Press '{restart_key}' to restart, Press 'Tab' for the log, Press 'F2' for settings, Press 'F5'/'F9' to save/load{unread}"
+                )),
+                crossterm::terminal::Clear(crossterm::terminal::ClearType::UntilNewLine)
             )?;
         }
 
-        // Add debug info right after the display (no title when debugging)
-        if let Some(ref debug) = self.debug_info {
-            self.render_debug_info(debug, offset_x, offset_y + display_height + 1)?;
+        // Add panels right after the display (no title when debugging), or after the controls.
+        // Drawn even without any panels, to clear the ones that were just closed
+        let start_y = if has_debug_info {
+            offset_y + display_height + 1
+        } else {
+            offset_y + display_height + 2
+        };
+        let area = Rect {
+            x: offset_x,
+            y: start_y,
+            width: term_width.saturating_sub(offset_x),
+            height: term_height.saturating_sub(start_y),
+        };
+        self.render_panels(out, &panels, area)?;
+        queue!(out, crossterm::terminal::EndSynchronizedUpdate)?;
+
+        // One write for the whole frame
+        match self.writer {
+            Some(ref writer) => writer.submit(std::mem::take(out)),
+            None => {
+                stdout().write_all(out)?;
+                stdout().flush()?;
+            }
         }
-
-        stdout().flush()?;
         Ok(())
     }
 
-    fn render_debug_info(
-        &self,
-        debug: &DebugInfo,
-        offset_x: u16,
-        start_y: u16,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        use crossterm::style::*;
-
-        let mut debug_line = start_y;
-
-        // Render key state
-        self.render_debug_line(
-            &self.format_key_state(debug),
-            Color::Yellow,
-            "INPUT",
-            offset_x,
-            debug_line,
-        )?;
-        debug_line += 1;
-
-        // Render CPU state
-        self.render_debug_line(
-            &self.format_cpu_state(debug),
-            Color::Cyan,
-            "CPU",
-            offset_x,
-            debug_line,
-        )?;
-        debug_line += 1;
-
-        // Render current instruction
-        self.render_debug_line(
-            &self.format_instruction(debug),
-            Color::Magenta,
-            "INST",
-            offset_x,
-            debug_line,
-        )?;
-        debug_line += 1;
-
-        // Render playback mode
-        self.render_debug_line(
-            &self.format_playback_mode(debug),
-            Color::Green,
-            "Mode",
-            offset_x,
-            debug_line,
-        )?;
+    // The debug overlay, in display order
+    fn debug_panels(&self, debug: &DebugInfo) -> Vec<Panel> {
+        use crossterm::style::Color;
+        let mut panels = vec![
+            Panel::new(
+                "INPUT",
+                Color::Yellow,
+                2,
+                vec![self.format_key_state(debug)],
+            ),
+            Panel::new(
+                "KEYS",
+                Color::DarkYellow,
+                1,
+                vec![debug.key_history.to_string()],
+            ),
+            Panel::new("CPU", Color::Cyan, 4, vec![self.format_cpu_state(debug)]),
+            Panel::new(
+                "INST",
+                Color::Magenta,
+                5,
+                vec![self.format_instruction(debug)],
+            ),
+            Panel::new(
+                "Mode",
+                Color::Green,
+                3,
+                vec![self.format_playback_mode(debug)],
+            ),
+            Panel::new(
+                "TIME",
+                Color::DarkYellow,
+                1,
+                vec![self.format_timing(debug)],
+            ),
+            Panel::new("SND", Color::DarkMagenta, 1, vec![self.format_sound(debug)]),
+            Panel::new("TMR", Color::DarkGreen, 0, self.format_timer_history(debug)),
+            Panel::new("LAT", Color::DarkCyan, 0, vec![debug.latency.to_string()]),
+            Panel::new("NEXT", Color::Blue, 1, self.format_disassembly(debug)),
+            Panel::new("MEM", Color::Grey, 0, vec![self.format_memory(debug)]),
+        ];
+        if self.show_diff {
+            panels.push(Panel::new(
+                "DIFF",
+                Color::Red,
+                0,
+                vec![self.frame_diff.to_string()],
+            ));
+        }
+        if debug.playback_mode == PlaybackMode::Paused
+            && let Some(ref key_hints) = self.key_hints
+        {
+            panels.push(Panel::new("HINT", Color::White, 3, vec![key_hints.clone()]));
+        }
+        panels
+    }
 
-        Ok(())
+    fn log_panel(&self) -> Panel {
+        use crossterm::style::Color;
+        // Shown over everything else, as the user asked for it
+        Panel::new("LOG", Color::White, u8::MAX, self.log.visible_lines())
     }
 
-    fn render_debug_line(
+    // Draws as many panels as fit in the area, dropping the lowest priority panels first.
+    // Lines wider than the area are cut off rather than wrapped
+    fn render_panels(
         &self,
-        content: &str,
-        color: crossterm::style::Color,
-        prefix: &str,
-        offset_x: u16,
-        y: u16,
+        out: &mut Vec<u8>,
+        panels: &[Panel],
+        area: Rect,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        use crossterm::{cursor::*, queue, style::*};
-        use std::io::stdout;
-
-        queue!(
-            stdout(),
-            MoveTo(offset_x, y),
-            SetForegroundColor(color),
-            Print(format!("{}: {}", prefix, content)),
-            crossterm::terminal::Clear(crossterm::terminal::ClearType::UntilNewLine),
-            ResetColor
-        )?;
+        use crossterm::{cursor::*, queue, style::*, terminal::*};
+
+        let mut by_priority: Vec<usize> = (0..panels.len()).collect();
+        by_priority.sort_by_key(|&i| std::cmp::Reverse(panels[i].priority));
+        let mut shown = vec![false; panels.len()];
+        let mut rows_left = area.height;
+        for i in by_priority {
+            if panels[i].height() <= rows_left {
+                rows_left -= panels[i].height();
+                shown[i] = true;
+            }
+        }
+
+        let mut y = area.y;
+        for panel in panels
+            .iter()
+            .zip(shown)
+            .filter(|(_, shown)| *shown)
+            .map(|(p, _)| p)
+        {
+            for (i, line) in panel.lines.iter().enumerate() {
+                let prefix = if i == 0 {
+                    format!("{}: ", panel.title)
+                } else {
+                    " ".repeat(panel.title.len() + 2)
+                };
+                let text: String = format!("{prefix}{line}")
+                    .chars()
+                    .take(area.width as usize)
+                    .collect();
+                queue!(
+                    out,
+                    MoveTo(area.x, y),
+                    SetForegroundColor(panel.color),
+                    Print(text),
+                    Clear(ClearType::UntilNewLine),
+                    ResetColor
+                )?;
+                y += 1;
+            }
+        }
+        // Clear whatever was left over from panels that no longer fit
+        if y < area.y + area.height {
+            queue!(out, MoveTo(area.x, y), Clear(ClearType::FromCursorDown))?;
+        }
         Ok(())
     }
 
@@ -287,32 +550,157 @@ impl Screen {
         )
     }
 
+    fn format_timing(&self, debug: &DebugInfo) -> String {
+        let timing = &debug.timing;
+        format!(
+            "{} cycles/frame | Last {} inst took {:.2}ms of {:.2}ms budget | Speed: {:.0}% | Frames skipped: {:.0}%",
+            (timing.cycles_per_frame * 10.0).round() / 10.0,
+            timing.batch_instructions,
+            timing.batch_time.as_secs_f64() * 1000.0,
+            timing.batch_budget.as_secs_f64() * 1000.0,
+            timing.speed_percent,
+            self.frame_skips.rate()
+        )
+    }
+
+    fn format_disassembly(&self, debug: &DebugInfo) -> Vec<String> {
+        debug
+            .upcoming
+            .iter()
+            .map(|(addr, raw, inst)| {
+                let label = match self.annotations.label_at(addr.get()) {
+                    Some(label) => format!(" [{label}]"),
+                    None => String::new(),
+                };
+                match inst {
+                    Some(inst) => format!("{addr}{label}: {raw} {inst}"),
+                    None => format!("{addr}{label}: {raw} .dw {raw}"),
+                }
+            })
+            .collect()
+    }
+
+    // The sound timer as a bar, so it can be followed while muted
+    fn format_sound(&self, debug: &DebugInfo) -> String {
+        const BAR_WIDTH: usize = 16;
+        let filled = (debug.sound_timer as usize).div_ceil(u8::MAX as usize / BAR_WIDTH + 1);
+        format!(
+            "[{}{}] {:3} | {}",
+            "█".repeat(filled),
+            "░".repeat(BAR_WIDTH - filled),
+            debug.sound_timer,
+            if debug.beeping { "Beeping" } else { "Silent" }
+        )
+    }
+
+    // Sparklines of both timers over the last few seconds, each scaled to its own peak
+    fn format_timer_history(&self, debug: &DebugInfo) -> Vec<String> {
+        const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+        let sparkline = |values: Vec<u8>| -> String {
+            let peak = values.iter().copied().max().unwrap_or(0).max(1) as usize;
+            values
+                .iter()
+                .map(|&value| match value {
+                    0 => ' ',
+                    _ => LEVELS[(value as usize * (LEVELS.len() - 1)).div_ceil(peak)],
+                })
+                .collect()
+        };
+        let history = &debug.timer_history;
+        vec![
+            format!(
+                "DT {}",
+                sparkline(history.iter().map(|&(d, _)| d).collect())
+            ),
+            format!(
+                "ST {}",
+                sparkline(history.iter().map(|&(_, s)| s).collect())
+            ),
+        ]
+    }
+
+    fn format_memory(&self, debug: &DebugInfo) -> String {
+        let bytes: Vec<String> = debug
+            .memory_at_index
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect();
+        match self.annotations.label_at(debug.index_register) {
+            Some(label) => format!("[I] {} ({label})", bytes.join(" ")),
+            None => format!("[I] {}", bytes.join(" ")),
+        }
+    }
+
     fn format_playback_mode(&self, debug: &DebugInfo) -> String {
-        match debug.playback_mode {
+        let mode = match debug.playback_mode {
             PlaybackMode::Running => "Running",
             PlaybackMode::Paused => "Paused",
             PlaybackMode::Stepping => "Stepping",
+        };
+        if debug.speed == 1.0 {
+            mode.to_string()
+        } else {
+            format!("{mode} {}x", debug.speed)
         }
-        .to_string()
     }
 }
 
-impl Screen {
-    fn get_idx(x: u8, y: u8) -> usize {
-        assert!(x < Self::N_COLS, "X screen index is out of bounds");
-        assert!(y < Self::N_ROWS, "Y screen index is out of bounds");
-        y as usize * Self::N_COLS as usize + x as usize
+// Area of the terminal, in cells
+#[derive(Clone, Copy, Debug)]
+struct Rect {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+}
+
+// A titled block of lines in the debug overlay
+struct Panel {
+    title: &'static str,
+    color: crossterm::style::Color,
+    priority: u8, // Higher priority panels are kept when space is short
+    lines: Vec<String>,
+}
+
+impl Panel {
+    fn new(
+        title: &'static str,
+        color: crossterm::style::Color,
+        priority: u8,
+        lines: Vec<String>,
+    ) -> Self {
+        Self {
+            title,
+            color,
+            priority,
+            lines,
+        }
+    }
+
+    fn height(&self) -> u16 {
+        self.lines.len() as u16
     }
 }
 
-impl Drop for Screen {
+impl Drop for TerminalRenderer {
     fn drop(&mut self) {
+        // The panic hook has already restored the terminal, and printed over it
+        if !self.in_terminal || std::thread::panicking() {
+            return;
+        }
+        // Let the last frame be written first, so it can't land after leaving the screen
+        drop(self.writer.take());
         crossterm::queue!(
             std::io::stdout(),
             crossterm::terminal::Clear(crossterm::terminal::ClearType::All)
         )
         .unwrap();
         stdout().flush().unwrap();
-        let _ = execute!(std::io::stdout(), LeaveAlternateScreen, Show);
+        let _ = execute!(
+            std::io::stdout(),
+            LeaveAlternateScreen,
+            DisableBracketedPaste,
+            Show
+        );
     }
 }
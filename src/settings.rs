@@ -0,0 +1,323 @@
+// The user's settings file, `config.toml` in the config directory. Command line flags take
+// precedence when the emulator starts, and edits to the file are applied while it runs:
+//
+//     color = "yellow"
+//     layout = "colemak"
+//     cpu_hz = 700
+//     show_diff = true
+//
+//     [keys]
+//     5 = "up"
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use clap::ValueEnum;
+
+use crate::frontend::ScreenColor;
+use crate::input::KeyboardLayout;
+use crate::keybindings::KeyBindings;
+use crate::minitoml;
+use crate::paths;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub color: Option<ScreenColor>,
+    pub layout: Option<KeyboardLayout>,
+    pub cpu_hz: Option<f64>,
+    pub show_diff: Option<bool>, // Highlight what the last frame changed in debug mode
+    pub keys: Option<KeyBindings>, // The `[keys]` table, written by `chip-8-emulator remap`
+}
+
+impl Settings {
+    pub fn path() -> PathBuf {
+        paths::config_dir().join("config.toml")
+    }
+
+    // The settings in the file, or none if there is no file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        Self::parse(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{path:?}: {e}")))
+    }
+
+    // Writes the settings that are set into the file, keeping its other lines and comments
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.update_toml(&text))
+    }
+
+    // `text` with the lines of the settings that are set replaced, or added before the first
+    // table. The key bindings are written separately, by `save_key_bindings`
+    pub fn update_toml(&self, text: &str) -> String {
+        let mut assignments = self.assignments();
+        let mut in_table = false;
+        let mut lines: Vec<String> = text
+            .lines()
+            .map(|line| {
+                in_table |= line.trim_start().starts_with('[');
+                let name = line.split_once('=').map(|(name, _)| name.trim());
+                match assignments.iter().position(|(n, _)| Some(*n) == name) {
+                    Some(i) if !in_table => {
+                        let (name, value) = assignments.remove(i);
+                        format!("{name} = {value}")
+                    }
+                    _ => line.to_string(),
+                }
+            })
+            .collect();
+        let mut end = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with('['))
+            .unwrap_or(lines.len());
+        if end < lines.len() {
+            while end > 0 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+        }
+        lines.splice(
+            end..end,
+            assignments
+                .into_iter()
+                .map(|(name, value)| format!("{name} = {value}")),
+        );
+        lines.join("\n") + "\n"
+    }
+
+    // Replaces the `[keys]` table of the file, keeping the rest of it
+    pub fn save_key_bindings(path: &Path, bindings: &KeyBindings) -> io::Result<()> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            path,
+            minitoml::replace_table(&text, "keys", &bindings.toml_lines()),
+        )
+    }
+
+    fn assignments(&self) -> Vec<(&'static str, String)> {
+        let mut assignments = Vec::new();
+        if let Some(color) = self.color {
+            assignments.push(("color", format!("\"{}\"", minitoml::enum_name(&color))));
+        }
+        if let Some(layout) = self.layout {
+            assignments.push(("layout", format!("\"{}\"", minitoml::enum_name(&layout))));
+        }
+        if let Some(hz) = self.cpu_hz {
+            assignments.push(("cpu_hz", hz.to_string()));
+        }
+        if let Some(show_diff) = self.show_diff {
+            assignments.push(("show_diff", show_diff.to_string()));
+        }
+        assignments
+    }
+
+    // Sets the settings that are set in `changes`
+    pub fn update(&mut self, changes: &Settings) {
+        self.color = changes.color.or(self.color);
+        self.layout = changes.layout.or(self.layout);
+        self.cpu_hz = changes.cpu_hz.or(self.cpu_hz);
+        self.show_diff = changes.show_diff.or(self.show_diff);
+        self.keys = changes.keys.clone().or(self.keys.take());
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut settings = Self::default();
+        minitoml::for_each_setting(text, |table, name, value| {
+            if table == "keys" {
+                let keys = settings.keys.get_or_insert_default();
+                return keys.parse_setting(name, &minitoml::parse_string(value)?);
+            }
+            match name {
+                "color" => settings.color = Some(minitoml::parse_enum(value)?),
+                "layout" => settings.layout = Some(minitoml::parse_enum(value)?),
+                "cpu_hz" => settings.cpu_hz = Some(minitoml::parse_hz(value)?),
+                "cycles_per_frame" => {
+                    settings.cpu_hz = Some(minitoml::parse_cycles_per_frame(value)?)
+                }
+                "show_diff" => settings.show_diff = Some(minitoml::parse_bool(value)?),
+                _ => return Err(format!("unknown setting '{name}'")),
+            }
+            Ok(())
+        })?;
+        Ok(settings)
+    }
+
+    // Only the settings that are different in `self`, or None if nothing changed
+    pub fn changes_from(&self, old: &Settings) -> Option<Settings> {
+        fn changed<T: PartialEq + Clone>(new: &Option<T>, old: &Option<T>) -> Option<T> {
+            new.clone().filter(|_| new != old)
+        }
+        let changes = Settings {
+            color: changed(&self.color, &old.color),
+            layout: changed(&self.layout, &old.layout),
+            cpu_hz: changed(&self.cpu_hz, &old.cpu_hz),
+            show_diff: changed(&self.show_diff, &old.show_diff),
+            keys: changed(&self.keys, &old.keys),
+        };
+        (changes != Settings::default()).then_some(changes)
+    }
+
+    // e.g. "color yellow, speed 700 Hz"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(color) = self.color {
+            parts.push(format!("color {}", minitoml::enum_name(&color)));
+        }
+        if let Some(layout) = self.layout {
+            parts.push(format!("layout {}", minitoml::enum_name(&layout)));
+        }
+        if let Some(hz) = self.cpu_hz {
+            parts.push(format!("speed {hz} Hz"));
+        }
+        if let Some(show_diff) = self.show_diff {
+            parts.push(format!(
+                "frame diff {}",
+                if show_diff { "on" } else { "off" }
+            ));
+        }
+        if self.keys.is_some() {
+            parts.push("key bindings".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+// The settings page, opened with F2. Each change is saved to the settings file, and then
+// applied by reloading it like any other edit
+pub struct SettingsMenu {
+    path: PathBuf,
+    current: Settings, // Every setting, as it is in effect
+    selected: usize,
+}
+
+impl SettingsMenu {
+    const ROWS: [&str; 4] = ["Color", "Layout", "Speed", "Frame diff"];
+    const SPEED_STEP_HZ: f64 = 50.0;
+
+    pub fn new(path: PathBuf, current: Settings) -> Self {
+        Self {
+            path,
+            current,
+            selected: 0,
+        }
+    }
+
+    // Moves the selection up or down, wrapping around
+    pub fn select(&mut self, delta: isize) {
+        let rows = Self::ROWS.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(rows) as usize;
+    }
+
+    // Steps the selected setting forwards or backwards, and saves it
+    pub fn change(&mut self, delta: isize) -> io::Result<()> {
+        let current = &self.current;
+        let change = match self.selected {
+            0 => Settings {
+                color: current.color.map(|color| cycle(color, delta)),
+                ..Default::default()
+            },
+            1 => Settings {
+                layout: current.layout.map(|layout| cycle(layout, delta)),
+                ..Default::default()
+            },
+            2 => Settings {
+                cpu_hz: current
+                    .cpu_hz
+                    .map(|hz| (hz + delta as f64 * Self::SPEED_STEP_HZ).max(Self::SPEED_STEP_HZ)),
+                ..Default::default()
+            },
+            _ => Settings {
+                show_diff: current.show_diff.map(|on| !on),
+                ..Default::default()
+            },
+        };
+        self.current.update(&change);
+        change.save(&self.path)
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let current = &self.current;
+        let values = [
+            current.color.as_ref().map(minitoml::enum_name),
+            current.layout.as_ref().map(minitoml::enum_name),
+            current.cpu_hz.map(|hz| format!("{hz} Hz")),
+            current
+                .show_diff
+                .map(|on| (if on { "on" } else { "off" }).to_string()),
+        ];
+        let mut lines: Vec<String> = Self::ROWS
+            .iter()
+            .zip(values)
+            .enumerate()
+            .map(|(i, (name, value))| {
+                let cursor = if i == self.selected { '>' } else { ' ' };
+                format!("{cursor} {name:<10} < {} >", value.unwrap_or_default())
+            })
+            .collect();
+        lines.push(format!(
+            "Up/Down to choose, Left/Right to change, Escape to close. Saved to {}",
+            self.path.display()
+        ));
+        lines
+    }
+}
+
+// The next or previous value of a command line enum, wrapping around
+fn cycle<T: ValueEnum + PartialEq>(value: T, delta: isize) -> T {
+    let variants = T::value_variants();
+    let i = variants.iter().position(|v| *v == value).unwrap_or(0) as isize;
+    variants[(i + delta).rem_euclid(variants.len() as isize) as usize].clone()
+}
+
+// Notices edits to the settings file by its modification time
+pub struct SettingsFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    current: Settings,
+}
+
+impl SettingsFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            modified: Self::modified(&path),
+            current: Settings::load(&path).unwrap_or_default(),
+            path,
+        }
+    }
+
+    // The settings that changed since the last call, if the file was edited
+    pub fn poll(&mut self) -> io::Result<Option<Settings>> {
+        let modified = Self::modified(&self.path);
+        if modified == self.modified {
+            return Ok(None);
+        }
+        self.modified = modified;
+        let settings = Settings::load(&self.path)?;
+        let changes = settings.changes_from(&self.current);
+        self.current = settings;
+        Ok(changes)
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
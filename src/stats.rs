@@ -0,0 +1,65 @@
+// Aggregate per-ROM play statistics, persisted between runs in the data directory
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::paths;
+
+#[derive(Debug, Default, Clone)]
+pub struct RomStats {
+    pub launches: u64,
+    pub playtime: Duration,
+}
+
+impl RomStats {
+    // Loads the stats for the ROM with the given hash, or empty stats if none were saved
+    pub fn load(rom_hash: u64) -> Self {
+        let mut stats = Self::default();
+        let Ok(contents) = fs::read_to_string(Self::path(rom_hash)) else {
+            return stats;
+        };
+        for line in contents.lines() {
+            match line.split_once('=') {
+                Some(("launches", value)) => stats.launches = value.trim().parse().unwrap_or(0),
+                Some(("playtime_secs", value)) => {
+                    stats.playtime = Duration::from_secs(value.trim().parse().unwrap_or(0))
+                }
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    pub fn save(&self, rom_hash: u64) -> io::Result<()> {
+        let path = Self::path(rom_hash);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(
+            path,
+            format!(
+                "launches={}\nplaytime_secs={}\n",
+                self.launches,
+                self.playtime.as_secs()
+            ),
+        )
+    }
+
+    // Short human readable summary, e.g. "Played 3 times, 1h 04m total"
+    pub fn summary(&self) -> String {
+        let secs = self.playtime.as_secs();
+        let time = if secs >= 3600 {
+            format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+        } else {
+            format!("{}m {:02}s", secs / 60, secs % 60)
+        };
+        let times = if self.launches == 1 { "time" } else { "times" };
+        format!("Played {} {times}, {time} total", self.launches)
+    }
+
+    fn path(rom_hash: u64) -> PathBuf {
+        paths::stats_dir().join(format!("{rom_hash:016x}.txt"))
+    }
+}
@@ -0,0 +1,87 @@
+// Writes rendered frames to the terminal on a thread of its own, so a slow terminal only holds
+// up drawing, and never the emulation. Only the latest frame is kept: any rendered while the
+// terminal is still busy with an earlier one are skipped
+
+use std::io::{Write, stdout};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+#[derive(Default)]
+struct Slot {
+    frame: Option<Vec<u8>>, // The latest frame that hasn't been written yet
+    writing: bool,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    slot: Mutex<Slot>,
+    ready: Condvar,
+}
+
+pub struct TerminalWriter {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TerminalWriter {
+    pub fn start() -> Self {
+        let shared = Arc::new(Shared::default());
+        let thread = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("terminal-writer".to_string())
+                .spawn(move || Self::run(&shared))
+                .expect("Could not start the terminal writer")
+        };
+        Self {
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    // Replaces any frame that hasn't been written yet
+    pub fn submit(&self, frame: Vec<u8>) {
+        self.shared.slot.lock().unwrap().frame = Some(frame);
+        self.shared.ready.notify_one();
+    }
+
+    // Whether a frame is still being written, or waiting to be
+    pub fn is_busy(&self) -> bool {
+        let slot = self.shared.slot.lock().unwrap();
+        slot.writing || slot.frame.is_some()
+    }
+
+    fn run(shared: &Shared) {
+        loop {
+            let frame = {
+                let mut slot = shared.slot.lock().unwrap();
+                loop {
+                    if let Some(frame) = slot.frame.take() {
+                        slot.writing = true;
+                        break frame;
+                    }
+                    if slot.closed {
+                        return;
+                    }
+                    slot = shared.ready.wait(slot).unwrap();
+                }
+            };
+            let mut out = stdout().lock();
+            // Nothing can be shown about a terminal that can't be written to
+            let _ = out.write_all(&frame).and_then(|_| out.flush());
+            shared.slot.lock().unwrap().writing = false;
+        }
+    }
+}
+
+impl Drop for TerminalWriter {
+    // Writes the last frame, and waits for the thread to finish
+    fn drop(&mut self) {
+        self.shared.slot.lock().unwrap().closed = true;
+        self.shared.ready.notify_one();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
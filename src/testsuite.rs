@@ -0,0 +1,145 @@
+// `chip-8-emulator test`: runs test ROMs, such as Timendus' suite and corax+, headless on each
+// version and compares where they leave the screen with the pass screen kept next to the ROM,
+// as `{rom}.{version}.pass` in the format of `FrameBuffer::to_text`. The suites draw one quirk
+// or opcode group per line of text, so a failure is reported by the lines that differ.
+// `--bless` saves the screens a run leaves as the pass screens, to be checked by eye once
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::chip8::Chip8Version;
+use crate::error::Chip8Error;
+use crate::hardware::{HaltAction, HardwareExecutionConfig};
+use crate::headless::{Chip8Core, InputSchedule};
+
+// Timendus' quirks test skips its platform menu when this is set before it starts
+pub const PLATFORM_ADDR: u16 = 0x1FF;
+
+// The menu entry in Timendus' quirks test for each version
+pub fn platform(version: &Chip8Version) -> u8 {
+    match version {
+        Chip8Version::Cosmac => 1,
+        Chip8Version::Superchip => 2,
+        Chip8Version::XoChip => 3,
+        Chip8Version::Chip48 => 4,
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TestOutcome {
+    Pass,
+    Fail(Vec<usize>), // The lines of text that differ, from 1
+    NoPassScreen,
+    Blessed,
+    Fault(String), // The ROM stopped on an error, before it could draw its results
+}
+
+impl fmt::Display for TestOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use TestOutcome::*;
+        match self {
+            Pass => write!(f, "pass"),
+            Fail(lines) if lines.is_empty() => write!(f, "FAIL: the screen changed size"),
+            Fail(lines) => write!(
+                f,
+                "FAIL: line {} differs",
+                lines
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            NoPassScreen => write!(f, "FAIL: no pass screen, run with --bless to save one"),
+            Blessed => write!(f, "saved as the pass screen"),
+            Fault(reason) => write!(f, "FAIL: {reason}"),
+        }
+    }
+}
+
+impl TestOutcome {
+    pub fn is_pass(&self) -> bool {
+        matches!(self, TestOutcome::Pass | TestOutcome::Blessed)
+    }
+}
+
+pub fn pass_screen_path(rom: &Path, version: &Chip8Version) -> PathBuf {
+    let mut path = rom.as_os_str().to_owned();
+    path.push(format!(".{version}.pass"));
+    PathBuf::from(path)
+}
+
+// The screen the ROM leaves after `frames` frames, or once it exits
+pub fn run(bytes: &[u8], version: &Chip8Version, frames: usize) -> Result<String, Chip8Error> {
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        version: version.clone(),
+        on_halt: HaltAction::Exit,
+        ..Default::default()
+    });
+    core.load_rom(bytes)?;
+    core.emulator
+        .hardware
+        .cpu
+        .store_in_addr(PLATFORM_ADDR, platform(version))?;
+    core.run_frames(frames, &InputSchedule::new());
+    match core.emulator.hardware.take_fault() {
+        Some(e) => Err(e),
+        None => Ok(core.emulator.hardware.framebuffer.to_text()),
+    }
+}
+
+// The lines of text that differ between two screens, where a line is a run of rows between
+// blank ones in the pass screen. None if they match, and no lines if they aren't the same size
+pub fn differing_lines(expected: &str, actual: &str) -> Option<Vec<usize>> {
+    let expected: Vec<&str> = expected.trim_end().lines().collect();
+    let actual: Vec<&str> = actual.trim_end().lines().collect();
+    if expected.len() != actual.len() {
+        return Some(Vec::new());
+    }
+    let mut lines = Vec::new();
+    let mut line = 0;
+    let mut in_line = false;
+    for (want, got) in expected.iter().zip(&actual) {
+        let blank = !want.contains(|c| c != '.');
+        if !blank && !in_line {
+            line += 1;
+        }
+        in_line = !blank;
+        // Stray pixels between lines count against the line above, or the first one
+        if want != got && lines.last() != Some(&line.max(1)) {
+            lines.push(line.max(1));
+        }
+    }
+    (!lines.is_empty()).then_some(lines)
+}
+
+// Runs one ROM on one version and checks it against, or saves, its pass screen. Errors are
+// the ROM or its pass screen not loading
+pub fn check(
+    rom: &Path,
+    version: &Chip8Version,
+    frames: usize,
+    bless: bool,
+) -> Result<TestOutcome, Chip8Error> {
+    let bytes = fs::read(rom)?;
+    let screen = match run(&bytes, version, frames) {
+        Ok(screen) => screen,
+        Err(e @ Chip8Error::RomTooLarge { .. }) => return Err(e),
+        Err(e) => return Ok(TestOutcome::Fault(e.to_string())),
+    };
+    let path = pass_screen_path(rom, version);
+    if bless {
+        fs::write(&path, screen + "\n")?;
+        return Ok(TestOutcome::Blessed);
+    }
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(TestOutcome::NoPassScreen),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(match differing_lines(&expected, &screen) {
+        Some(lines) => TestOutcome::Fail(lines),
+        None => TestOutcome::Pass,
+    })
+}
@@ -0,0 +1,235 @@
+// Measures how long instructions take to emulate, compared to the real time they represent
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::chip8::Chip8;
+
+// Summary of the last batch of instructions (those executed since the previous frame)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimingStats {
+    pub batch_instructions: u32,
+    pub batch_time: Duration,   // Time spent emulating the batch
+    pub batch_budget: Duration, // Real time the batch should take at the configured clock
+    pub speed_percent: f64,     // Instructions executed per second, relative to the clock
+    pub cycles_per_frame: f64,  // Of the configured clock
+}
+
+pub struct InstructionTimer {
+    batch_instructions: u32,
+    batch_time: Duration,
+    batch_started: Instant,
+    speed_percent: f64,
+}
+
+impl Default for InstructionTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstructionTimer {
+    // How much each batch contributes to the smoothed speed figure
+    const SPEED_SMOOTHING: f64 = 0.1;
+
+    pub fn new() -> Self {
+        Self {
+            batch_instructions: 0,
+            batch_time: Duration::ZERO,
+            batch_started: Instant::now(),
+            speed_percent: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, elapsed: Duration) {
+        self.batch_instructions += 1;
+        self.batch_time += elapsed;
+    }
+
+    // Closes the current batch and starts a new one
+    pub fn end_batch(&mut self, cpu_hz: f64) -> TimingStats {
+        let wall_time = self.batch_started.elapsed().as_secs_f64();
+        let instant_speed = if wall_time > 0.0 {
+            self.batch_instructions as f64 / (wall_time * cpu_hz) * 100.0
+        } else {
+            self.speed_percent
+        };
+        self.speed_percent += (instant_speed - self.speed_percent) * Self::SPEED_SMOOTHING;
+
+        let stats = TimingStats {
+            batch_instructions: self.batch_instructions,
+            batch_time: self.batch_time,
+            batch_budget: Duration::from_secs_f64(self.batch_instructions as f64 / cpu_hz),
+            speed_percent: self.speed_percent,
+            cycles_per_frame: cpu_hz / Chip8::SCREEN_HZ,
+        };
+        self.batch_instructions = 0;
+        self.batch_time = Duration::ZERO;
+        self.batch_started = Instant::now();
+        stats
+    }
+}
+
+// Running average and worst case of a measured delay
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DelayStat {
+    pub count: u32,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+impl DelayStat {
+    pub fn record(&mut self, delay: Duration) {
+        self.count += 1;
+        self.total += delay;
+        self.max = self.max.max(delay);
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::ZERO
+        } else {
+            self.total / self.count
+        }
+    }
+}
+
+impl std::fmt::Display for DelayStat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "avg {:.1}ms (max {:.1}ms)",
+            self.mean().as_secs_f64() * 1000.0,
+            self.max.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+// How long key events take from being read from the terminal until they are visible to
+// instructions, and until the next frame is drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    pub to_hardware: DelayStat,
+    pub to_frame: DelayStat,
+}
+
+impl std::fmt::Display for LatencyStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} key events | to CPU {} | to screen {}",
+            self.to_hardware.count, self.to_hardware, self.to_frame
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct LatencyTracker {
+    stats: LatencyStats,
+    unflushed_input: Option<Instant>, // Oldest key event that hasn't been drawn yet
+}
+
+impl LatencyTracker {
+    pub fn input_received(&mut self, received: Instant) {
+        self.stats.to_hardware.record(received.elapsed());
+        self.unflushed_input.get_or_insert(received);
+    }
+
+    pub fn frame_flushed(&mut self) {
+        if let Some(received) = self.unflushed_input.take() {
+            self.stats.to_frame.record(received.elapsed());
+        }
+    }
+
+    pub fn stats(&self) -> LatencyStats {
+        self.stats
+    }
+}
+
+// How many frames weren't drawn because the terminal was still busy with an earlier one,
+// counted over one second windows
+pub struct FrameSkips {
+    window_start: Instant,
+    drawn: u32,
+    skipped: u32,
+    rate: f64, // Percentage skipped in the last full window
+}
+
+impl Default for FrameSkips {
+    fn default() -> Self {
+        Self {
+            window_start: Instant::now(),
+            drawn: 0,
+            skipped: 0,
+            rate: 0.0,
+        }
+    }
+}
+
+impl FrameSkips {
+    const WINDOW: Duration = Duration::from_secs(1);
+
+    pub fn record(&mut self, skipped: bool) {
+        if self.window_start.elapsed() >= Self::WINDOW {
+            let total = self.drawn + self.skipped;
+            self.rate = if total == 0 {
+                0.0
+            } else {
+                self.skipped as f64 * 100.0 / total as f64
+            };
+            *self = Self {
+                rate: self.rate,
+                ..Self::default()
+            };
+        }
+        if skipped {
+            self.skipped += 1;
+        } else {
+            self.drawn += 1;
+        }
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+}
+
+// The delay and sound timer values of the last few seconds, one sample per timer tick
+pub struct TimerHistory {
+    samples: VecDeque<(u8, u8)>, // (Delay, Sound), oldest first
+}
+
+impl Default for TimerHistory {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(Self::CAPACITY),
+        }
+    }
+}
+
+impl TimerHistory {
+    const CAPACITY: usize = 180; // 3 seconds at 60Hz
+
+    pub fn record(&mut self, delay: u8, sound: u8) {
+        if self.samples.len() == Self::CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((delay, sound));
+    }
+
+    // Shrinks the history to at most `width` samples, keeping the largest values in each
+    // bucket so short beeps aren't lost
+    pub fn downsample(&self, width: usize) -> Vec<(u8, u8)> {
+        let bucket = self.samples.len().div_ceil(width.max(1)).max(1);
+        self.samples
+            .iter()
+            .collect::<Vec<_>>()
+            .chunks(bucket)
+            .map(|chunk| {
+                chunk.iter().fold((0, 0), |(delay, sound), &&(d, s)| {
+                    (delay.max(d), sound.max(s))
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,29 @@
+// Recognises the short COSMAC VIP machine code routines that hybrid ROMs commonly call with
+// 0NNN, so they can be emulated without an 1802 CPU
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VipRoutine {
+    DisplayOn,  // INP 1 enables the 1861 display chip
+    DisplayOff, // OUT 1 disables it
+    ToneOn,     // SEQ sets Q, which drives the speaker
+    ToneOff,    // REQ clears Q
+}
+
+impl VipRoutine {
+    // `SEP R4` hands control back to the CHIP-8 interpreter, ending the routine
+    const RETURN: u8 = 0xD4;
+    // Longest routine that is looked for
+    pub const MAX_LEN: usize = 2;
+
+    // Matches the machine code at the routine's address, up to and including its return
+    pub fn recognize(code: &[u8]) -> Option<Self> {
+        let end = code.iter().position(|&byte| byte == Self::RETURN)?;
+        match &code[..end] {
+            [0x69] => Some(VipRoutine::DisplayOn),
+            [0x61] => Some(VipRoutine::DisplayOff),
+            [0x7B] => Some(VipRoutine::ToneOn),
+            [0x7A] => Some(VipRoutine::ToneOff),
+            _ => None,
+        }
+    }
+}
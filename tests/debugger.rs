@@ -0,0 +1,149 @@
+use chip_8_emulator::Chip8Error;
+use chip_8_emulator::chip8::Chip8Version;
+use chip_8_emulator::frontend::FrontendEvent;
+use chip_8_emulator::hardware::{Hardware, HardwareExecutionConfig, InvalidAction};
+use chip_8_emulator::input::Chip8KeyEventKind;
+use chip_8_emulator::primitive::{Instruction, Register};
+
+// The last message the hardware sent to the frontend's log
+fn last_logged(hardware: &mut Hardware) -> Option<String> {
+    hardware
+        .take_frontend_events()
+        .into_iter()
+        .rev()
+        .find_map(|event| match event {
+            FrontendEvent::Log(message) => Some(message),
+            _ => None,
+        })
+}
+
+#[test]
+fn delay_waits_are_skipped_in_one_go() {
+    // Sets the delay timer to 10 and spins on FX07 until it runs out, then sets V1 to 5
+    let rom = [
+        0x60, 0x0A, 0xF0, 0x15, 0xF0, 0x07, 0x30, 0x00, 0x12, 0x04, 0x61, 0x05, 0x12, 0x0C,
+    ];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    let step = |hardware: &mut Hardware, n| {
+        for _ in 0..n {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst).unwrap();
+        }
+    };
+    step(&mut hardware, 2);
+
+    assert_eq!(hardware.skip_delay_wait().unwrap(), 10);
+    assert_eq!(hardware.cpu.get_delay_timer(), 0);
+    step(&mut hardware, 4);
+    assert_eq!(hardware.cpu.register_val(&Register::new(1).unwrap()), 5);
+
+    // Nothing to skip once it's out
+    assert_eq!(hardware.skip_delay_wait().unwrap(), 0);
+}
+
+#[test]
+fn invalid_instructions_halt_skip_or_fault() {
+    // 5121 isn't an instruction, then sets V1 to 5
+    let rom = [0x51, 0x21, 0x61, 0x05];
+    let run = |on_invalid| {
+        let mut hardware = Hardware::new(HardwareExecutionConfig {
+            on_invalid,
+            ..Default::default()
+        });
+        hardware.load_rom(&rom).unwrap();
+        for _ in 0..3 {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst)?;
+        }
+        Ok::<_, Chip8Error>(hardware)
+    };
+
+    let mut halted = run(InvalidAction::Halt).unwrap();
+    assert_eq!(halted.cpu.get_pc(), 0x200);
+    assert_eq!(
+        last_logged(&mut halted).as_deref(),
+        Some("Halted: Invalid instruction 5121 at 0x200")
+    );
+
+    let skipped = run(InvalidAction::Skip).unwrap();
+    assert_eq!(skipped.cpu.register_val(&Register::new(1).unwrap()), 5);
+
+    assert!(matches!(
+        run(InvalidAction::Panic),
+        Err(Chip8Error::InvalidInstruction {
+            pc: 0x200,
+            opcode: 0x5121
+        })
+    ));
+}
+
+#[test]
+fn snapshots_list_the_last_instructions_run() {
+    // V0 += 1, then loops back to it
+    let rom = [0x70, 0x01, 0x12, 0x00];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    for _ in 0..40 {
+        let inst = hardware.current_instruction();
+        hardware.execute_instruction(&inst).unwrap();
+    }
+    let snapshot = hardware.machine_snapshot();
+    let recent: Vec<&str> = snapshot
+        .lines()
+        .skip_while(|line| *line != "Recent instructions:")
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .collect();
+    assert_eq!(recent.len(), 16);
+    assert!(recent[0].starts_with("  0x200: 0x7001"));
+    assert!(recent[15].starts_with("  0x202: 0x1200"));
+}
+
+#[test]
+fn the_debugger_shows_instructions_the_version_cant_run_as_unsupported() {
+    // 00FF switches to hires on SUPER-CHIP, then 6001
+    let rom = [0x00, 0xFF, 0x60, 0x01];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    let info = hardware.get_debug_info();
+    assert!(matches!(
+        info.decoded_instruction,
+        Instruction::Unsupported(Chip8Version::Cosmac)
+    ));
+    assert!(matches!(
+        info.upcoming[0].2,
+        Some(Instruction::Unsupported(Chip8Version::Cosmac))
+    ));
+}
+
+#[test]
+fn the_overlay_follows_memory_writes_and_key_presses() {
+    // I = 0x300, V0 = 255, then FX33 writes 2, 5, 5 at I without moving it
+    let rom = [0xA3, 0x00, 0x60, 0xFF, 0xF0, 0x33, 0x12, 0x06];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    for _ in 0..3 {
+        let inst = hardware.current_instruction();
+        hardware.execute_instruction(&inst).unwrap();
+        hardware.update_debug_info();
+    }
+    hardware.record_key_event(0xA, Chip8KeyEventKind::Press);
+    hardware.update_debug_info();
+
+    // What the frontend shows, from the whole overlay and the changes sent after it
+    let mut shown = None;
+    for event in hardware.take_frontend_events() {
+        match event {
+            FrontendEvent::DebugInfo(debug_info) => shown = Some(debug_info),
+            FrontendEvent::DebugDelta(delta) => shown.as_mut().unwrap().apply(&delta),
+            _ => {}
+        }
+    }
+    let shown = shown.unwrap();
+    let actual = hardware.get_debug_info();
+    assert_eq!(shown.memory_at_index[..3], [2, 5, 5]);
+    assert_eq!(shown.memory_at_index, actual.memory_at_index);
+    assert_eq!(shown.key_history, actual.key_history);
+    assert_eq!(shown.current_pc, 0x206);
+}
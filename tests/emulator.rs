@@ -0,0 +1,62 @@
+use chip_8_emulator::Emulator;
+use chip_8_emulator::audio::AudioEvent;
+use chip_8_emulator::frontend::FrontendEvent;
+use chip_8_emulator::hardware::HardwareExecutionConfig;
+
+#[test]
+fn stepping_draws_the_same_screen_as_whole_frames() {
+    let mut emulator = Emulator::new(HardwareExecutionConfig::default());
+    emulator
+        .load_rom(include_bytes!("../roms/IBM Logo.ch8"))
+        .unwrap();
+    for _ in 0..500 {
+        assert!(emulator.step().unwrap());
+    }
+    assert_eq!(
+        emulator.framebuffer().to_text().trim_end(),
+        include_str!("golden/ibm_logo.txt").trim_end()
+    );
+}
+
+#[test]
+fn timers_tick_every_sixtieth_of_a_second_of_steps() {
+    // Sets the delay timer to 2, then loops
+    let rom = [0x60, 0x02, 0xF0, 0x15, 0x12, 0x04];
+    let mut emulator = Emulator::new(HardwareExecutionConfig {
+        cpu_hz: 600.0,
+        ..Default::default()
+    });
+    emulator.load_rom(&rom).unwrap();
+    for _ in 0..10 {
+        emulator.step().unwrap();
+    }
+    assert_eq!(emulator.hardware.cpu.get_delay_timer(), 1);
+    for _ in 0..10 {
+        emulator.step().unwrap();
+    }
+    assert_eq!(emulator.hardware.cpu.get_delay_timer(), 0);
+}
+
+#[test]
+fn the_beep_is_reported_to_the_frontend() {
+    // Sets the sound timer to 2, then loops
+    let rom = [0x60, 0x02, 0xF0, 0x18, 0x12, 0x04];
+    let mut emulator = Emulator::new(HardwareExecutionConfig {
+        cpu_hz: 600.0,
+        ..Default::default()
+    });
+    emulator.load_rom(&rom).unwrap();
+    for _ in 0..30 {
+        emulator.step().unwrap();
+    }
+    let beeps: Vec<_> = emulator
+        .hardware
+        .take_frontend_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            FrontendEvent::Audio(event) => Some(event),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(beeps, [AudioEvent::BeepStart, AudioEvent::BeepStop]);
+}
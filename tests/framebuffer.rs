@@ -0,0 +1,132 @@
+use chip_8_emulator::framebuffer::FrameBuffer;
+
+#[test]
+fn pixels_outside_the_display_are_ignored() {
+    let mut frame = FrameBuffer::new();
+    frame.set_pixel(FrameBuffer::LORES_COLS, 0, true);
+    assert_eq!(frame.get_pixel(FrameBuffer::LORES_COLS, 0), None);
+    assert!(frame.pixels().iter().all(|&on| !on));
+}
+
+#[test]
+fn hash_follows_the_pixels() {
+    let mut frame = FrameBuffer::new();
+    let blank = frame.hash();
+    frame.set_pixel(3, 4, true);
+    assert_ne!(frame.hash(), blank);
+    assert_eq!(frame.get_pixel(3, 4), Some(true));
+    frame.clear();
+    assert_eq!(frame.hash(), blank);
+}
+
+#[test]
+fn switched_off_display_is_dark() {
+    let mut frame = FrameBuffer::new();
+    frame.set_pixel(0, 0, true);
+    frame.display_on = false;
+    assert!(!frame.is_lit(0, 0));
+    assert_eq!(frame.get_pixel(0, 0), Some(true));
+}
+
+#[test]
+fn superchip_roms_can_switch_to_high_resolution() {
+    use chip_8_emulator::chip8::Chip8Version;
+    use chip_8_emulator::hardware::HardwareExecutionConfig;
+    use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+
+    // HIGH, then a 16x16 sprite at (100, 40), which is off a 64x32 display
+    let mut rom = vec![
+        0x00, 0xFF, 0x60, 100, 0x61, 40, 0xA2, 0x0C, 0xD0, 0x10, 0x12, 0x0A,
+    ];
+    rom.extend([0xFF; 32]);
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        version: Chip8Version::Superchip,
+        ..Default::default()
+    });
+    core.load_rom(&rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+
+    let frame = &core.emulator.hardware.framebuffer;
+    assert!(frame.is_hires());
+    assert_eq!(frame.pixels().len(), 128 * 64);
+    assert_eq!(frame.get_pixel(100, 40), Some(true));
+    assert_eq!(frame.get_pixel(115, 55), Some(true));
+    assert_eq!(frame.get_pixel(116, 56), Some(false));
+}
+
+#[test]
+fn superchip_draws_8x16_sprites_in_low_resolution() {
+    use chip_8_emulator::chip8::Chip8Version;
+    use chip_8_emulator::hardware::HardwareExecutionConfig;
+    use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+    use chip_8_emulator::primitive::Register;
+    use chip_8_emulator::quirks::Quirks;
+
+    // DXY0 at (0, 0), then again 8 rows down, so the last 8 rows of the first collide
+    let mut rom = vec![
+        0x60, 0x00, 0x61, 0x08, 0xA2, 0x0C, 0xD0, 0x00, 0xD0, 0x10, 0x12, 0x0A,
+    ];
+    rom.extend([0xFF; 32]);
+    let mut quirks = Quirks::for_version(&Chip8Version::Superchip);
+    quirks.collision_rows = true;
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        version: Chip8Version::Superchip,
+        quirks: Some(quirks),
+        ..Default::default()
+    });
+    core.load_rom(&rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+
+    let frame = &core.emulator.hardware.framebuffer;
+    assert!(!frame.is_hires());
+    assert_eq!(frame.get_pixel(7, 0), Some(true));
+    assert_eq!(frame.get_pixel(8, 0), Some(false));
+    assert_eq!(frame.get_pixel(7, 8), Some(false));
+    assert_eq!(frame.get_pixel(7, 23), Some(true));
+    assert_eq!(frame.get_pixel(7, 24), Some(false));
+    // Collision rows are only counted in high resolution
+    let vf = core
+        .emulator
+        .hardware
+        .cpu
+        .register_val(&Register::new(0xF).unwrap());
+    assert_eq!(vf, 1);
+}
+
+#[test]
+fn superchip_roms_can_draw_the_big_font() {
+    use chip_8_emulator::chip8::Chip8Version;
+    use chip_8_emulator::hardware::HardwareExecutionConfig;
+    use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+
+    // The big "1" at (1, 0), which is 8 pixels wide and 10 tall
+    let rom = [0x60, 0x01, 0x61, 0x00, 0xF0, 0x30, 0xD0, 0x1A, 0x12, 0x08];
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        version: Chip8Version::Superchip,
+        ..Default::default()
+    });
+    core.load_rom(&rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+
+    let frame = &core.emulator.hardware.framebuffer;
+    assert_eq!(frame.get_pixel(3, 0), Some(false));
+    assert_eq!(frame.get_pixel(4, 0), Some(true));
+    assert!((1..9).all(|x| frame.get_pixel(x, 9) == Some(true)));
+    assert_eq!(frame.get_pixel(4, 10), Some(false));
+}
+
+#[test]
+fn sprite_rows_are_clipped_or_wrap_at_the_right_edge() {
+    let mut frame = FrameBuffer::new();
+    // The right half of 0xFF00 is blank, so the left half ends at x = 65
+    assert!(!frame.xor_sprite_row(0, 58, 0, 0xFF00, false));
+    assert_eq!(frame.get_pixel(63, 0), Some(true));
+    assert!(!frame.xor_sprite_row(0, 58, 1, 0xFF00, true));
+    assert_eq!(frame.get_pixel(1, 1), Some(true));
+    assert_eq!(frame.get_pixel(2, 1), Some(false));
+    assert_eq!(frame.get_pixel(0, 0), Some(false));
+
+    // Drawing over lit pixels turns them off, and reports the collision
+    assert!(frame.xor_sprite_row(0, 58, 0, 0x8000, false));
+    assert_eq!(frame.get_pixel(58, 0), Some(false));
+}
@@ -0,0 +1,18 @@
+// Draw path regression tests: each ROM must leave exactly the golden bitmap on screen
+
+use chip_8_emulator::golden_rom;
+
+golden_rom!(
+    ibm_logo,
+    include_bytes!("../roms/IBM Logo.ch8"),
+    frames = 60,
+    expected = include_str!("golden/ibm_logo.txt"),
+);
+
+// Draws every font character in a row
+golden_rom!(
+    font,
+    include_bytes!("roms/font.ch8"),
+    frames = 20,
+    expected = include_str!("golden/font.txt"),
+);
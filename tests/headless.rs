@@ -0,0 +1,67 @@
+use chip_8_emulator::hardware::HardwareExecutionConfig;
+use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+
+fn run(rom: &[u8]) -> Vec<chip_8_emulator::headless::FrameHash> {
+    let mut core = Chip8Core::new(HardwareExecutionConfig::default());
+    core.load_rom(rom).unwrap();
+    core.run_frames(30, &InputSchedule::new())
+}
+
+#[test]
+fn cores_run_the_same_on_other_threads() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Chip8Core<'static>>();
+
+    let roms: [&[u8]; 2] = [
+        include_bytes!("../roms/IBM Logo.ch8"),
+        include_bytes!("roms/font.ch8"),
+    ];
+    let parallel: Vec<_> = std::thread::scope(|scope| {
+        let runs: Vec<_> = roms.iter().map(|rom| scope.spawn(|| run(rom))).collect();
+        runs.into_iter().map(|run| run.join().unwrap()).collect()
+    });
+    let sequential: Vec<_> = roms.iter().map(|rom| run(rom)).collect();
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn runs_stop_after_exactly_the_cycle_budget() {
+    // 0x200 LD V0, 1; 0x202 JP 0x200
+    let rom = [0x60, 0x01, 0x12, 0x00];
+    let mut core = Chip8Core::new(HardwareExecutionConfig::default());
+    core.load_rom(&rom).unwrap();
+    let frames = core.run_cycles(100, &InputSchedule::new());
+    assert_eq!(core.emulator.hardware.cycles(), 100);
+    // 8 instructions a frame at 500Hz, and a shorter last one
+    assert_eq!(frames.len(), 13);
+    assert!(core.run_cycles(100, &InputSchedule::new()).is_empty());
+}
+
+#[test]
+fn runs_stop_at_a_key_wait_with_no_input_left() {
+    // 0x200 LD V0, K; 0x202 LD V0, 1; 0x204 JP 0x200
+    let rom = [0xF0, 0x0A, 0x60, 0x01, 0x12, 0x00];
+    let mut core = Chip8Core::new(HardwareExecutionConfig::default());
+    core.load_rom(&rom).unwrap();
+    let input = InputSchedule::new();
+    assert_eq!(core.run_cycles(1000, &input).len(), 1);
+    assert!(core.is_stuck_on_key(&input));
+
+    // A press still to come keeps it going, until it loops back to the wait
+    let mut core = Chip8Core::new(HardwareExecutionConfig::default());
+    core.load_rom(&rom).unwrap();
+    let input = InputSchedule::new().press(3, 5).release(4, 5);
+    assert_eq!(core.run_cycles(1000, &input).len(), 5);
+    assert_eq!(core.emulator.hardware.cycles(), 4);
+}
+
+#[test]
+fn slow_speeds_still_run_an_instruction_a_frame() {
+    let rom = [0x60, 0x01, 0x12, 0x00];
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        cpu_hz: 30.0,
+        ..Default::default()
+    });
+    core.load_rom(&rom).unwrap();
+    assert_eq!(core.run_cycles(10, &InputSchedule::new()).len(), 10);
+}
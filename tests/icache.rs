@@ -0,0 +1,43 @@
+use chip_8_emulator::hardware::HardwareExecutionConfig;
+use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+use chip_8_emulator::primitive::Register;
+
+#[test]
+fn self_modifying_code_runs_the_new_instructions() {
+    // Stores V0-V1 (6105, i.e. V1 = 5) over the CLS at 0x20A, which was decoded when the ROM
+    // was loaded
+    let rom = [
+        0xA2, 0x0A, 0x60, 0x61, 0x61, 0x05, 0xF1, 0x55, 0x61, 0x00, 0x00, 0xE0, 0x12, 0x0C,
+    ];
+    let mut core = Chip8Core::new(HardwareExecutionConfig::default());
+    core.load_rom(&rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+
+    let v1 = core
+        .emulator
+        .hardware
+        .cpu
+        .register_val(&Register::new(1).unwrap());
+    assert_eq!(v1, 5);
+}
+
+#[test]
+fn writing_over_a_block_that_ran_drops_it() {
+    // Adds 1 to V3, then stores 7310 (V3 += 0x10) over that instruction, and loops back to run
+    // the new one before halting
+    let rom = [
+        0x73, 0x01, 0xA2, 0x00, 0x60, 0x73, 0x61, 0x10, 0xF1, 0x55, 0x43, 0x01, 0x12, 0x00, 0x12,
+        0x0E,
+    ];
+    let mut core = Chip8Core::new(HardwareExecutionConfig::default());
+    core.load_rom(&rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+
+    let v3 = core
+        .emulator
+        .hardware
+        .cpu
+        .register_val(&Register::new(3).unwrap());
+    assert_eq!(v3, 0x11);
+    assert!(core.emulator.hardware.cpu.is_self_modifying());
+}
@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use chip_8_emulator::input::{Chip8KeyEventKind, HeldKeys, PastedKeys};
+#[cfg(feature = "tui")]
+use chip_8_emulator::scheduler::InputScheduler;
+
+#[test]
+fn keys_that_stop_repeating_are_released() {
+    let timeout = Duration::from_millis(600);
+    let start = Instant::now();
+    let mut held = HeldKeys::default();
+    held.press(5, start);
+    held.press(0xA, start);
+    held.release(0xA);
+
+    assert_eq!(
+        held.take_stuck(start + Duration::from_millis(500), timeout),
+        None
+    );
+    // A repeat keeps it held
+    held.press(5, start + Duration::from_millis(500));
+    assert_eq!(
+        held.take_stuck(start + Duration::from_millis(1000), timeout),
+        None
+    );
+    assert_eq!(
+        held.take_stuck(start + Duration::from_millis(1200), timeout),
+        Some(5)
+    );
+    assert_eq!(
+        held.take_stuck(start + Duration::from_millis(5000), timeout),
+        None
+    );
+}
+
+#[test]
+fn pasted_hex_digits_are_typed_in_turn() {
+    let start = Instant::now();
+    let mut pasted = PastedKeys::default();
+    pasted.paste("4 4-a!", start);
+
+    let mut typed = Vec::new();
+    for ms in (0..1000).step_by(10) {
+        while let Some(event) = pasted.next_due(start + Duration::from_millis(ms)) {
+            typed.push((ms, event));
+        }
+    }
+    use Chip8KeyEventKind::*;
+    assert_eq!(
+        typed,
+        [
+            (0, (4, Press)),
+            (100, (4, Release)),
+            (200, (4, Press)),
+            (300, (4, Release)),
+            (400, (0xA, Press)),
+            (500, (0xA, Release)),
+        ]
+    );
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn speed_steps_and_fast_forward_is_held() {
+    use Chip8KeyEventKind::*;
+    use chip_8_emulator::input::Chip8Command::*;
+    let mut input = InputScheduler::new();
+    assert_eq!(input.change_speed(SpeedUp, Press), Some(2.0));
+    assert_eq!(input.change_speed(SpeedUp, Release), None);
+    assert_eq!(input.change_speed(FastForward, Press), Some(8.0));
+    // Key repeats don't change anything
+    assert_eq!(input.change_speed(FastForward, Press), None);
+    assert_eq!(input.change_speed(FastForward, Release), Some(2.0));
+    assert_eq!(input.change_speed(SlowMotion, Press), Some(0.25));
+    assert_eq!(input.change_speed(SlowMotion, Release), Some(2.0));
+
+    for _ in 0..10 {
+        input.change_speed(SlowDown, Press);
+    }
+    assert_eq!(input.change_speed(SlowDown, Press), None);
+    assert_eq!(input.change_speed(SpeedUp, Press), Some(0.25));
+}
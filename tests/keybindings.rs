@@ -0,0 +1,69 @@
+use chip_8_emulator::input::{Chip8Command, KeyMapping, KeyboardLayout};
+use chip_8_emulator::keybindings::BindingTarget;
+#[cfg(feature = "tui")]
+use chip_8_emulator::remap::RemapWizard;
+use chip_8_emulator::settings::Settings;
+use crossterm::event::KeyCode;
+
+#[test]
+fn bindings_move_keys_and_commands_from_the_layout() {
+    let settings =
+        Settings::parse("color = \"red\"\n\n[keys]\n5 = \"up\"\nquit = \"Q\"\n").unwrap();
+    let mapping = KeyMapping::new(KeyboardLayout::Qwerty, settings.keys.unwrap());
+
+    assert_eq!(mapping.target_of(KeyCode::Up), Some(BindingTarget::Key(5)));
+    assert_eq!(mapping.target_of(KeyCode::Char('w')), None);
+    let quit = BindingTarget::Command(Chip8Command::Quit);
+    assert_eq!(mapping.target_of(KeyCode::Char('q')), Some(quit));
+    assert_eq!(mapping.target_of(KeyCode::Esc), None);
+    // Q was the layout's 4, which is left without a key
+    assert_eq!(mapping.label(BindingTarget::Key(4)), "4");
+    assert_eq!(mapping.label(quit), "Q");
+
+    assert!(Settings::parse("[keys]\nG = \"x\"").is_err());
+    assert!(Settings::parse("[keys]\n1 = \"nope\"").is_err());
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn the_wizard_asks_for_every_key_once() {
+    let mut wizard = RemapWizard::new(KeyboardLayout::Qwerty, Default::default());
+    assert_eq!(wizard.current(), Some(BindingTarget::Key(1)));
+    assert_eq!(wizard.prompt().unwrap(), "[1/30] CHIP-8 key 1 (now 1): ");
+    wizard.press(KeyCode::Char('x')).unwrap();
+    assert!(wizard.press(KeyCode::Char('X')).is_err());
+    assert!(wizard.press(KeyCode::Null).is_err());
+    wizard.keep();
+    while wizard.current().is_some() {
+        wizard.keep();
+    }
+
+    let bindings = wizard.into_bindings();
+    assert_eq!(bindings.toml_lines(), ["1 = \"x\""]);
+}
+
+#[test]
+fn bindings_are_saved_into_their_own_table() {
+    let path = std::env::temp_dir().join(format!("chip8-keys-{}.toml", std::process::id()));
+    std::fs::write(&path, "color = \"red\"\n\n[keys]\n1 = \"y\"\n").unwrap();
+
+    let mut bindings = Settings::load(&path).unwrap().keys.unwrap();
+    bindings.bind(BindingTarget::Command(Chip8Command::Rewind), KeyCode::F(3));
+    Settings::save_key_bindings(&path, &bindings).unwrap();
+    let text = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        text,
+        "color = \"red\"\n\n[keys]\n1 = \"y\"\nrewind = \"f3\"\n"
+    );
+
+    // Settings saved later stay out of the table
+    let changes = Settings {
+        cpu_hz: Some(600.0),
+        ..Default::default()
+    };
+    assert_eq!(
+        changes.update_toml(&text),
+        "color = \"red\"\ncpu_hz = 600\n\n[keys]\n1 = \"y\"\nrewind = \"f3\"\n"
+    );
+    std::fs::remove_file(&path).unwrap();
+}
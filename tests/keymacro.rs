@@ -0,0 +1,52 @@
+use chip_8_emulator::input::Chip8KeyEventKind::{Press, Release};
+use chip_8_emulator::keymacro::{self, KeyMacro, MacroPlayback, MacroRecording, MacroStore};
+
+#[test]
+fn macros_play_back_relative_to_where_they_start() {
+    let mut recording = MacroRecording::start(1000);
+    recording.record(1010, 5, Press);
+    recording.record(1050, 5, Release);
+    recording.record(1060, 0xA, Press);
+    // A is still held, so it's released at the end
+    let key_macro = recording.finish(1100);
+    assert_eq!(key_macro.presses(), 2);
+    assert_eq!(
+        key_macro.to_text(),
+        "# chip-8-emulator key macro\nkey 10 5 press\nkey 50 5 release\nkey 60 A press\nkey 100 A release\n"
+    );
+    assert_eq!(KeyMacro::parse(&key_macro.to_text()), Ok(key_macro.clone()));
+
+    let mut playback = MacroPlayback::start(key_macro, 20);
+    assert_eq!(playback.next_due(29), None);
+    assert_eq!(playback.next_due(30), Some((5, Press)));
+    assert_eq!(playback.next_due(200), Some((5, Release)));
+    assert_eq!(playback.next_due(200), Some((0xA, Press)));
+    assert_eq!(playback.next_due(200), Some((0xA, Release)));
+    assert!(playback.is_finished());
+
+    assert!(KeyMacro::parse("tick 5").is_err());
+}
+
+#[test]
+fn macros_are_kept_per_rom_and_name() {
+    let dir = std::env::temp_dir().join(format!("chip8-macros-{}", std::process::id()));
+    let store = MacroStore::new(dir.clone(), 0xABC, "boss".to_string());
+    assert_eq!(store.load().unwrap(), None);
+
+    let mut recording = MacroRecording::start(0);
+    recording.record(3, 1, Press);
+    let key_macro = recording.finish(9);
+    store.save(&key_macro).unwrap();
+    assert_eq!(store.load().unwrap(), Some(key_macro));
+    assert_eq!(
+        MacroStore::new(dir.clone(), 0xABC, "other".to_string())
+            .load()
+            .unwrap(),
+        None
+    );
+
+    assert!(keymacro::parse_name("level-2_start").is_ok());
+    assert!(keymacro::parse_name("../escape").is_err());
+    assert!(keymacro::parse_name("").is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use chip_8_emulator::hardware::HardwareExecutionConfig;
+use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+use chip_8_emulator::limits::{ExecutionLimits, LimitExceeded};
+
+#[test]
+fn parses_limits_with_units() {
+    let limits = ExecutionLimits::parse("cycles=1000, time=1.5m,disk=2M").unwrap();
+    assert_eq!(limits.cycles, Some(1000));
+    assert_eq!(limits.time, Some(Duration::from_secs(90)));
+    assert_eq!(limits.disk_bytes, Some(2 << 20));
+    assert!(ExecutionLimits::parse("memory=1").is_err());
+}
+
+#[test]
+fn cycle_limit_stops_the_rom() {
+    // An endless loop: 0x200 JP 0x200
+    let rom = [0x12, 0x00];
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        limits: ExecutionLimits::parse("cycles=20").unwrap(),
+        ..Default::default()
+    });
+    core.load_rom(&rom).unwrap();
+    let frames = core.run_frames(10, &InputSchedule::new());
+    assert_eq!(frames.len(), 3);
+    assert_eq!(
+        core.emulator.hardware.limit_exceeded(),
+        Some(LimitExceeded::Cycles(20))
+    );
+}
+
+#[test]
+fn savestates_count_towards_the_disk_limit() {
+    use chip_8_emulator::hardware::Hardware;
+    use chip_8_emulator::quirks::Quirks;
+    use chip_8_emulator::savestate::SaveSlots;
+
+    let dir = std::env::temp_dir().join(format!("chip8-disk-limit-{}", std::process::id()));
+    let config = HardwareExecutionConfig {
+        limits: ExecutionLimits::parse("disk=1K").unwrap(),
+        ..Default::default()
+    };
+    let quirks = Quirks::for_version(&config.version);
+    let mut hardware = Hardware::new(config);
+    hardware.load_rom(&[0x12, 0x00]).unwrap();
+    hardware.set_save_slots(SaveSlots::new(dir.clone(), 1, quirks));
+
+    // Each save counts, although they all overwrite the same slot
+    hardware.save_state();
+    let size = std::fs::metadata(SaveSlots::new(dir.clone(), 1, quirks).path())
+        .unwrap()
+        .len();
+    let mut saves = 1;
+    while hardware.limit_exceeded().is_none() && saves < 100 {
+        hardware.save_state();
+        saves += 1;
+    }
+    assert_eq!(saves, 1024_u64.div_ceil(size));
+    assert_eq!(hardware.limit_exceeded(), Some(LimitExceeded::Disk(1024)));
+    assert!(hardware.has_exited());
+    let _ = std::fs::remove_dir_all(dir);
+}
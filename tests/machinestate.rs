@@ -0,0 +1,34 @@
+use chip_8_emulator::cpu::CPU;
+use chip_8_emulator::framebuffer::FrameBuffer;
+use chip_8_emulator::machinestate::MachineState;
+use chip_8_emulator::primitive::Register;
+
+#[test]
+fn identical_states_have_no_changes() {
+    let state = MachineState::new(&CPU::new(), &FrameBuffer::new());
+    let diff = state.diff(&state.clone());
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "no changes");
+}
+
+#[test]
+fn diffs_list_registers_memory_runs_and_pixels() {
+    let before = MachineState::new(&CPU::new(), &FrameBuffer::new());
+    let mut after = before.clone();
+    after.cpu.set_pc(0x204);
+    after.cpu.register_set(&Register::new(3).unwrap(), 1);
+    for addr in [0x300, 0x301, 0x302, 0x310] {
+        after.cpu.store_in_addr(addr, 0xFF).unwrap();
+    }
+    after.framebuffer.set_pixel(1, 2, true);
+    after.framebuffer.set_pixel(3, 2, true);
+
+    let diff = before.diff(&after);
+    assert_eq!(diff.registers, vec![(3, 0, 1)]);
+    assert_eq!(diff.memory, vec![0x300..0x303, 0x310..0x311]);
+    assert_eq!(diff.pixels, vec![(1, 2), (3, 2)]);
+    assert_eq!(
+        diff.to_string(),
+        "PC 0x000 -> 0x204, V3 00 -> 01, memory 0x300-0x302, memory 0x310, 2 pixels"
+    );
+}
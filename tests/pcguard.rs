@@ -0,0 +1,48 @@
+use chip_8_emulator::Chip8Error;
+use chip_8_emulator::cpu::CPU;
+use chip_8_emulator::hardware::{Hardware, HardwareExecutionConfig};
+
+fn run(rom: &[u8], steps: usize, pc_guard: bool) -> Result<u16, Chip8Error> {
+    let mut hardware = Hardware::new(HardwareExecutionConfig {
+        pc_guard,
+        ..Default::default()
+    });
+    hardware.load_rom(rom).unwrap();
+    for _ in 0..steps {
+        let inst = hardware.current_instruction();
+        hardware.execute_instruction(&inst)?;
+    }
+    Ok(hardware.cpu.get_pc())
+}
+
+#[test]
+fn the_pc_wraps_around_memory() {
+    let mut cpu = CPU::new();
+    cpu.set_pc(0xFFE);
+    cpu.increment_pc();
+    assert_eq!(cpu.get_pc(), 0);
+    assert!(cpu.take_pc_wrapped());
+    assert!(!cpu.take_pc_wrapped());
+
+    // V0 = 0xFF, then BFFF jumps to 0x10FE, which wraps to 0x0FE
+    assert_eq!(run(&[0x60, 0xFF, 0xBF, 0xFF], 2, false).unwrap(), 0x0FE);
+    // Odd addresses run without the guard
+    assert_eq!(run(&[0x12, 0x05], 1, false).unwrap(), 0x205);
+}
+
+#[test]
+fn the_guard_faults_on_an_odd_pc() {
+    let e = run(&[0x12, 0x05], 2, true).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "PC is odd, after a jump to a misaligned address at 0x205"
+    );
+}
+
+#[test]
+fn the_guard_faults_past_the_end_of_memory() {
+    // Jumps to the last instruction, 0xFFE, which is 0000 and runs on
+    let e = run(&[0x1F, 0xFE], 3, true).unwrap_err();
+    assert!(matches!(e, Chip8Error::Guard { pc: 0x000, .. }));
+    assert!(e.to_string().starts_with("PC ran past the end of memory"));
+}
@@ -0,0 +1,73 @@
+use chip_8_emulator::Chip8Error;
+use chip_8_emulator::cpu::CPU;
+use chip_8_emulator::frontend::FrontendEvent;
+use chip_8_emulator::hardware::{Hardware, HardwareExecutionConfig, MemoryProtection};
+
+fn run(rom: &[u8], protection: Option<MemoryProtection>) -> Result<Hardware<'_>, Chip8Error> {
+    let mut hardware = Hardware::new(HardwareExecutionConfig {
+        memory_protection: protection,
+        ..Default::default()
+    });
+    hardware.load_rom(rom).unwrap();
+    for _ in 0..rom.len() / 2 {
+        let inst = hardware.current_instruction();
+        hardware.execute_instruction(&inst)?;
+    }
+    Ok(hardware)
+}
+
+// The last message the hardware sent to the frontend's log
+fn last_logged(hardware: &mut Hardware) -> Option<String> {
+    hardware
+        .take_frontend_events()
+        .into_iter()
+        .rev()
+        .find_map(|event| match event {
+            FrontendEvent::Log(message) => Some(message),
+            _ => None,
+        })
+}
+
+// I = 0x050, V0 = 0xFF, then FX55 writes V0 over the top of the 0 glyph
+const FONT_WRITE: [u8; 6] = [0xA0, 0x50, 0x60, 0xFF, 0xF0, 0x55];
+
+#[test]
+fn writes_below_0x200_are_logged() {
+    let mut hardware = run(&FONT_WRITE, None).unwrap();
+    assert_eq!(hardware.cpu.load_from_addr(0x050).unwrap(), 0xFF);
+    assert_eq!(last_logged(&mut hardware), None);
+
+    let mut hardware = run(&FONT_WRITE, Some(MemoryProtection::Log)).unwrap();
+    assert_eq!(hardware.cpu.load_from_addr(0x050).unwrap(), 0xFF);
+    assert_eq!(
+        last_logged(&mut hardware).as_deref(),
+        Some("Wrote to the font (0x050) at 0x204")
+    );
+
+    // BCD of V0 at 0x1F0
+    let mut hardware = run(
+        &[0xA1, 0xF0, 0x60, 0x7B, 0xF0, 0x33],
+        Some(MemoryProtection::Log),
+    )
+    .unwrap();
+    assert_eq!(
+        last_logged(&mut hardware).as_deref(),
+        Some("Wrote to the interpreter's memory below 0x200 (0x1F0) at 0x204")
+    );
+}
+
+#[test]
+fn writes_to_the_font_can_fault() {
+    let Err(e) = run(&FONT_WRITE, Some(MemoryProtection::Fault)) else {
+        panic!("the write should have faulted");
+    };
+    assert_eq!(e.to_string(), "Wrote to the font (0x050) at 0x204");
+}
+
+#[test]
+fn memory_access_past_the_end_is_an_error() {
+    let mut cpu = CPU::new();
+    assert!(cpu.load_from_addr(0x1000).is_err());
+    assert!(cpu.store_in_addr(0x1000, 1).is_err());
+    assert_eq!(cpu.resolve_addr(0x1002, true).unwrap(), 0x002);
+}
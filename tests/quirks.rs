@@ -0,0 +1,224 @@
+use chip_8_emulator::Chip8Error;
+use chip_8_emulator::chip8::Chip8Version;
+use chip_8_emulator::hardware::{Hardware, HardwareExecutionConfig};
+use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+use chip_8_emulator::primitive::Register;
+use chip_8_emulator::quirks::{self, Quirks};
+
+// Sets VF, ORs V0 with V1, then loops
+const LOGIC_ROM: [u8; 8] = [0x6F, 0x05, 0x61, 0x03, 0x80, 0x11, 0x12, 0x06];
+
+fn vf_after_logic(config: HardwareExecutionConfig) -> u8 {
+    let mut core = Chip8Core::new(config);
+    core.load_rom(&LOGIC_ROM).unwrap();
+    core.run_frames(1, &InputSchedule::new());
+    core.emulator
+        .hardware
+        .cpu
+        .register_val(&Register::new(0xF).unwrap())
+}
+
+#[test]
+fn versions_are_presets_that_can_be_overridden() {
+    assert_eq!(vf_after_logic(HardwareExecutionConfig::default()), 0);
+    assert_eq!(
+        vf_after_logic(HardwareExecutionConfig {
+            version: Chip8Version::Superchip,
+            ..Default::default()
+        }),
+        5
+    );
+
+    let mut quirks = Quirks::for_version(&Chip8Version::Cosmac);
+    quirks.set("vf_reset", false).unwrap();
+    assert_eq!(
+        vf_after_logic(HardwareExecutionConfig {
+            quirks: Some(quirks),
+            ..Default::default()
+        }),
+        5
+    );
+}
+
+#[test]
+fn overrides_are_parsed() {
+    assert_eq!(
+        quirks::parse_override("jump_vx=on"),
+        Ok(("jump_vx".to_string(), true))
+    );
+    assert_eq!(
+        quirks::parse_override("wrap=off"),
+        Ok(("wrap".to_string(), false))
+    );
+    assert!(quirks::parse_override("wrap").is_err());
+    assert!(quirks::parse_override("wrap=maybe").is_err());
+    assert!(quirks::parse_override("sprite_clip=on").is_err());
+    assert_eq!(quirks::parse_on_off("off"), Ok(false));
+    assert!(quirks::parse_on_off("maybe").is_err());
+}
+
+#[test]
+fn add_index_can_set_vf_on_overflow() {
+    // I = 0xFFF, V0 = 1, VF = 7, I += V0
+    let rom = [0xAF, 0xFF, 0x60, 0x01, 0x6F, 0x07, 0xF0, 0x1E];
+    let run = |index_overflow| {
+        let mut quirks = Quirks::for_version(&Chip8Version::Cosmac);
+        quirks.index_overflow = index_overflow;
+        let mut hardware = Hardware::new(HardwareExecutionConfig {
+            quirks: Some(quirks),
+            ..Default::default()
+        });
+        hardware.load_rom(&rom).unwrap();
+        for _ in 0..4 {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst).unwrap();
+        }
+        assert_eq!(hardware.cpu.get_index(), 0x1000);
+        hardware.cpu.register_val(&Register::new(0xF).unwrap())
+    };
+    assert_eq!(run(false), 7);
+    assert_eq!(run(true), 1);
+}
+
+#[test]
+fn set_font_points_at_the_moved_font() {
+    // V0 = 1, then FX29
+    let rom = [0x60, 0x01, 0xF0, 0x29];
+    let mut hardware = Hardware::new(HardwareExecutionConfig {
+        font_addr: 0,
+        ..Default::default()
+    });
+    hardware.load_rom(&rom).unwrap();
+    for _ in 0..2 {
+        let inst = hardware.current_instruction();
+        hardware.execute_instruction(&inst).unwrap();
+    }
+    assert_eq!(hardware.cpu.get_index(), 5);
+    assert_eq!(hardware.cpu.load_from_addr(5).unwrap(), 0x20);
+}
+
+#[test]
+fn stores_past_the_end_of_memory_fault_or_wrap() {
+    // I = 0xFFE, V0 = 1, V1 = 2, V2 = 3, then FX55 and FX33 over the end of memory
+    let rom = [
+        0xAF, 0xFE, 0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0xF2, 0x55, 0xF0, 0x33,
+    ];
+    let run = |memory_wrap| {
+        let mut quirks = Quirks::for_version(&Chip8Version::Superchip);
+        quirks.memory_wrap = memory_wrap;
+        let mut hardware = Hardware::new(HardwareExecutionConfig {
+            quirks: Some(quirks),
+            ..Default::default()
+        });
+        hardware.load_rom(&rom).unwrap();
+        for _ in 0..4 {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst).unwrap();
+        }
+        let inst = hardware.current_instruction();
+        hardware.execute_instruction(&inst).map(|_| hardware)
+    };
+
+    let Err(e) = run(false) else {
+        panic!("FX55 should have faulted");
+    };
+    assert!(matches!(
+        e,
+        Chip8Error::MemoryOutOfBounds {
+            pc: 0x208,
+            addr: 0x1000
+        }
+    ));
+
+    let mut hardware = run(true).unwrap();
+    let read = |hardware: &Hardware, addr| hardware.cpu.load_from_addr(addr).unwrap();
+    assert_eq!(
+        [
+            read(&hardware, 0xFFE),
+            read(&hardware, 0xFFF),
+            read(&hardware, 0)
+        ],
+        [1, 2, 3]
+    );
+    // BCD of 1 is 0, 0, 1
+    let inst = hardware.current_instruction();
+    hardware.execute_instruction(&inst).unwrap();
+    assert_eq!(read(&hardware, 0), 1);
+}
+
+#[test]
+fn sprites_past_the_end_of_memory_fault_or_wrap() {
+    // Draws a two row sprite from I = 0xFFF
+    let rom = [0xAF, 0xFF, 0xD0, 0x02];
+    let run = |memory_wrap| {
+        let mut quirks = Quirks::for_version(&Chip8Version::Cosmac);
+        quirks.memory_wrap = memory_wrap;
+        let mut hardware = Hardware::new(HardwareExecutionConfig {
+            quirks: Some(quirks),
+            ..Default::default()
+        });
+        hardware.load_rom(&rom).unwrap();
+        hardware.cpu.store_in_addr(0xFFF, 0x80).unwrap();
+        hardware.cpu.store_in_addr(0x000, 0x40).unwrap();
+        for _ in 0..2 {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst)?;
+        }
+        Ok::<_, Chip8Error>(hardware)
+    };
+
+    assert!(matches!(
+        run(false).err(),
+        Some(Chip8Error::MemoryOutOfBounds {
+            pc: 0x202,
+            addr: 0x1001
+        })
+    ));
+    let hardware = run(true).unwrap();
+    assert!(hardware.framebuffer.is_lit(0, 0));
+    assert!(hardware.framebuffer.is_lit(1, 1));
+}
+
+#[test]
+fn fx18_with_zero_stops_the_sound() {
+    // ST = 10, then ST = 0
+    let rom = [0x60, 0x0A, 0xF0, 0x18, 0x60, 0x00, 0xF0, 0x18];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    let step = |hardware: &mut Hardware| {
+        let inst = hardware.current_instruction();
+        hardware.execute_instruction(&inst).unwrap();
+        hardware.update_audio();
+    };
+    step(&mut hardware);
+    step(&mut hardware);
+    assert!(hardware.is_beeping());
+    step(&mut hardware);
+    step(&mut hardware);
+    assert_eq!(hardware.cpu.get_sound_timer(), 0);
+    assert!(!hardware.is_beeping());
+}
+
+#[test]
+fn short_sounds_are_silent_with_sound_threshold() {
+    // ST = 1
+    let rom = [0x60, 0x01, 0xF0, 0x18];
+    let beeps = |sound_threshold| {
+        let mut quirks = Quirks::for_version(&Chip8Version::Superchip);
+        quirks.sound_threshold = sound_threshold;
+        let mut hardware = Hardware::new(HardwareExecutionConfig {
+            quirks: Some(quirks),
+            ..Default::default()
+        });
+        hardware.load_rom(&rom).unwrap();
+        for _ in 0..2 {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst).unwrap();
+        }
+        hardware.update_audio();
+        assert_eq!(hardware.cpu.get_sound_timer(), 1);
+        hardware.is_beeping()
+    };
+    assert!(!beeps(true));
+    assert!(beeps(false));
+}
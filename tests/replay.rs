@@ -0,0 +1,197 @@
+use chip_8_emulator::frontend::FrontendEvent;
+use chip_8_emulator::hardware::{Hardware, HardwareExecutionConfig};
+use chip_8_emulator::input::Chip8KeyEventKind;
+use chip_8_emulator::primitive::Register;
+use chip_8_emulator::quirks::Quirks;
+use chip_8_emulator::replay::{InputReplay, ReplayEvent};
+use chip_8_emulator::util;
+
+// The header of a recording of `rom` with the default quirks
+fn header(rom: &[u8]) -> String {
+    let quirks = Quirks::for_version(&HardwareExecutionConfig::default().version);
+    format!(
+        "version 1\nrom {:016x}\nquirks {:x}\n",
+        util::stable_hash(rom),
+        quirks.bits()
+    )
+}
+
+// The last message the hardware sent to the frontend's log
+fn last_logged(hardware: &mut Hardware) -> Option<String> {
+    hardware
+        .take_frontend_events()
+        .into_iter()
+        .rev()
+        .find_map(|event| match event {
+            FrontendEvent::Log(message) => Some(message),
+            _ => None,
+        })
+}
+
+#[test]
+fn recordings_are_parsed_in_order() {
+    let mut replay = InputReplay::parse(&format!(
+        "# chip-8-emulator input recording\n{}seed 42\nkey 3 A press\ntick 3\nkey 9 a release\n",
+        header(&[])
+    ))
+    .unwrap();
+    assert_eq!(replay.seed, 42);
+    assert_eq!(replay.next_due(2), None);
+    assert_eq!(
+        replay.next_due(3),
+        Some(ReplayEvent::Key(0xA, Chip8KeyEventKind::Press))
+    );
+    assert_eq!(replay.next_due(3), Some(ReplayEvent::TimerTick));
+    assert_eq!(replay.next_due(8), None);
+    assert!(replay.next_due(9).is_some());
+    assert!(replay.is_finished());
+
+    assert!(InputReplay::parse(&format!("{}key 1 A press\n", header(&[]))).is_err());
+    assert!(InputReplay::parse(&format!("{}seed 1\nkey 1 G press\n", header(&[]))).is_err());
+    assert!(InputReplay::parse("seed 1\nkey 1 A press\n").is_err());
+}
+
+#[test]
+fn replayed_keys_reach_the_program_at_the_recorded_cycle() {
+    // Waits for a key into V1 after one instruction, then halts
+    let rom = [0x60, 0x01, 0xF1, 0x0A, 0x12, 0x04];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    hardware
+        .set_input_replay(
+            InputReplay::parse(&format!(
+                "{}seed 1\nkey 2 5 press\nkey 2 5 release\n",
+                header(&rom)
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+
+    for _ in 0..5 {
+        hardware.replay_due_input();
+        if !hardware.is_waiting_for_key() {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst).unwrap();
+        }
+    }
+    assert_eq!(hardware.cpu.register_val(&Register::new(1).unwrap()), 5);
+    assert!(!hardware.is_replaying());
+}
+
+#[test]
+fn the_same_seed_gives_the_same_random_numbers() {
+    // V0-V3 = random bytes
+    let rom = [0xC0, 0xFF, 0xC1, 0xFF, 0xC2, 0xFF, 0xC3, 0xFF];
+    let run = |seed| {
+        let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+        hardware.load_rom(&rom).unwrap();
+        hardware.set_seed(seed);
+        for _ in 0..4 {
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst).unwrap();
+        }
+        hardware.cpu.all_register_val()
+    };
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+}
+
+#[test]
+fn replays_report_the_first_frame_that_diverged() {
+    // V0 = 1, then loops
+    let rom = [0x60, 0x01, 0x12, 0x02];
+    let mut live = Hardware::new(HardwareExecutionConfig::default());
+    live.load_rom(&rom).unwrap();
+    let inst = live.current_instruction();
+    live.execute_instruction(&inst).unwrap();
+    let checksum = live.state_checksum();
+
+    let replay = |recording: &str| {
+        let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+        hardware.load_rom(&rom).unwrap();
+        let recording = format!("{}{recording}", header(&rom));
+        hardware
+            .set_input_replay(InputReplay::parse(&recording).unwrap())
+            .unwrap();
+        for _ in 0..3 {
+            hardware.replay_due_input();
+            let inst = hardware.current_instruction();
+            hardware.execute_instruction(&inst).unwrap();
+        }
+        hardware.replay_divergence()
+    };
+    assert_eq!(
+        replay(&format!("seed 1\ntick 1\ncrc 1 {checksum:08x}\n")),
+        None
+    );
+    let divergence = replay("seed 1\ntick 1\ncrc 1 00000000\ntick 2\ncrc 2 00000000\n").unwrap();
+    assert_eq!((divergence.frame, divergence.cycle), (1, 1));
+    assert_eq!(divergence.actual, checksum);
+    assert_eq!(divergence.changes, None);
+
+    // The jump to itself changes nothing after the frame that matched
+    let divergence = replay(&format!(
+        "seed 1\ntick 1\ncrc 1 {checksum:08x}\ntick 2\ncrc 2 00000000\n"
+    ))
+    .unwrap();
+    assert!(divergence.changes.unwrap().is_empty());
+}
+
+#[test]
+fn checksums_are_crc32() {
+    assert_eq!(util::crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn recordings_with_cycles_out_of_order_are_rejected() {
+    let e = InputReplay::parse(&format!("{}seed 1\ntick 5\ntick 3\n", header(&[])))
+        .err()
+        .unwrap();
+    assert_eq!(e, "line 6: cycle 3 comes before the previous event's, 5");
+}
+
+#[test]
+fn rewinding_stops_a_replay() {
+    let rom = [0x60, 0x01, 0x12, 0x00];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    let recording = format!("{}seed 1\ntick 100\n", header(&rom));
+    hardware
+        .set_input_replay(InputReplay::parse(&recording).unwrap())
+        .unwrap();
+    hardware.record_rewind_snapshot();
+    hardware.rewind();
+    assert!(!hardware.is_replaying());
+    assert_eq!(
+        last_logged(&mut hardware).as_deref(),
+        Some("Replay stopped, as the machine jumped to a saved state, the keyboard is live again")
+    );
+}
+
+#[test]
+fn recordings_of_other_roms_or_quirks_are_refused() {
+    let rom = [0x60, 0x01, 0x12, 0x00];
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+
+    let other_rom = InputReplay::parse(&format!("{}seed 1\n", header(&[0x12, 0x00]))).unwrap();
+    assert_eq!(
+        hardware.set_input_replay(other_rom),
+        Err("the recording is of a different ROM".to_string())
+    );
+    let mut quirks = Quirks::for_version(&HardwareExecutionConfig::default().version);
+    quirks.wrap = !quirks.wrap;
+    let other_quirks = InputReplay::parse(&format!(
+        "version 1\nrom {:016x}\nquirks {:x}\nseed 1\n",
+        util::stable_hash(&rom),
+        quirks.bits()
+    ))
+    .unwrap();
+    let e = hardware.set_input_replay(other_quirks).unwrap_err();
+    assert!(e.contains("other quirks (wrap="), "{e}");
+    assert!(!hardware.is_replaying());
+
+    let newer = format!("{}seed 1\n", header(&rom)).replace("version 1", "version 2");
+    let e = InputReplay::parse(&newer).err().unwrap();
+    assert!(e.contains("format version 2"), "{e}");
+}
@@ -0,0 +1,34 @@
+use chip_8_emulator::chip8::Chip8Version;
+use chip_8_emulator::cpu::CPU;
+use chip_8_emulator::framebuffer::FrameBuffer;
+use chip_8_emulator::primitive::Register;
+use chip_8_emulator::rewind::RewindBuffer;
+
+#[test]
+fn rewinding_goes_back_through_recorded_frames() {
+    let mut rewind = RewindBuffer::new(4);
+    let mut cpu = CPU::new();
+    let v0 = Register::new(0).unwrap();
+    for frame in 0..6 {
+        cpu.register_set(&v0, frame);
+        rewind.record(&cpu, &FrameBuffer::new());
+    }
+    // Only the last 4 frames are kept
+    assert_eq!(rewind.len(), 4);
+    assert_eq!(rewind.rewind(1).unwrap().cpu.register_val(&v0), 5);
+    assert_eq!(rewind.rewind(2).unwrap().cpu.register_val(&v0), 3);
+    assert_eq!(rewind.rewind(5).unwrap().cpu.register_val(&v0), 2);
+    assert!(rewind.rewind(1).is_none());
+}
+
+#[test]
+fn snapshots_are_packed() {
+    let mut rewind = RewindBuffer::default();
+    let cpu = CPU::with_memory_size(Chip8Version::XoChip.memory_size());
+    rewind.record(&cpu, &FrameBuffer::new());
+    assert!(rewind.packed_size() < 2048);
+    assert_eq!(
+        rewind.rewind(1).unwrap().cpu.memory_size(),
+        cpu.memory_size()
+    );
+}
@@ -0,0 +1,65 @@
+use chip_8_emulator::chip8::Chip8Version;
+use chip_8_emulator::romconfig::RomConfig;
+
+#[test]
+fn parses_settings_and_key_hints() {
+    let config = RomConfig::parse(
+        r#"
+        # Shipped with the ROM
+        title = "Pong # 2"  # Comments can follow values
+        version = "chip48"
+        cpu_hz = 700
+        collision_rows = true
+        homepage = "https://example.com"
+
+        [keys]
+        1 = "Up"
+        "C" = "Serve"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.title.as_deref(), Some("Pong # 2"));
+    assert_eq!(config.version, Some(Chip8Version::Chip48));
+    assert_eq!(config.cpu_hz, Some(700.0));
+    assert!(config.collision_rows);
+    assert!(!config.display_wait);
+    assert_eq!(
+        config.key_hints,
+        vec![(0x1, "Up".to_string()), (0xC, "Serve".to_string())]
+    );
+}
+
+#[test]
+fn speed_can_be_given_in_cycles_per_frame() {
+    let config = RomConfig::parse("cycles_per_frame = 15").unwrap();
+    assert_eq!(config.cpu_hz, Some(900.0));
+    assert!(RomConfig::parse("cycles_per_frame = 0").is_err());
+    assert!(RomConfig::parse("cpu_hz = nan").is_err());
+    assert!(RomConfig::parse("cpu_hz = inf").is_err());
+}
+
+#[test]
+fn the_font_can_be_moved() {
+    let config = RomConfig::parse("font_addr = 0x000").unwrap();
+    assert_eq!(config.font_addr, Some(0));
+    // Both fonts take 240 bytes, which don't fit below 0x200 from 0x190
+    assert!(RomConfig::parse("font_addr = 0x190").is_err());
+}
+
+#[test]
+fn quirks_are_kept_in_file_order() {
+    let config = RomConfig::parse("[quirks]\nvf_reset = false\nwrap = true").unwrap();
+    assert_eq!(
+        config.quirks,
+        vec![("vf_reset".to_string(), false), ("wrap".to_string(), true)]
+    );
+    let error = RomConfig::parse("[quirks]\nwarp = true").unwrap_err();
+    assert!(error.starts_with("line 2:"), "{error}");
+    assert!(RomConfig::parse("[quirks]\nwrap = on").is_err());
+}
+
+#[test]
+fn reports_the_line_of_bad_settings() {
+    let error = RomConfig::parse("title = \"Pong\"\n\n[keys]\nG = \"Jump\"").unwrap_err();
+    assert!(error.starts_with("line 4:"), "{error}");
+}
@@ -0,0 +1,27 @@
+use chip_8_emulator::chip8::Chip8Version;
+use chip_8_emulator::hardware::HardwareExecutionConfig;
+use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+use chip_8_emulator::primitive::Register;
+
+#[test]
+fn flags_are_restored_into_the_registers() {
+    // Save V0-V1, clear them, then restore them along with the unused flags after them
+    let rom = [
+        0x60, 0x07, 0x61, 0x09, 0xF1, 0x75, 0x60, 0x00, 0x61, 0x00, 0x62, 0xFF, 0xF2, 0x85, 0x12,
+        0x0E,
+    ];
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        version: Chip8Version::Superchip,
+        ..Default::default()
+    });
+    core.load_rom(&rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+
+    let register = |i| {
+        core.emulator
+            .hardware
+            .cpu
+            .register_val(&Register::new(i).unwrap())
+    };
+    assert_eq!((register(0), register(1), register(2)), (7, 9, 0));
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use chip_8_emulator::cpu::CPU;
+use chip_8_emulator::hardware::{Hardware, HardwareExecutionConfig};
+use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+use chip_8_emulator::primitive::Register;
+use chip_8_emulator::quirks::Quirks;
+use chip_8_emulator::savestate::{SaveSlots, SaveState};
+
+const QUIRKS: Quirks = Quirks {
+    shift_vy: true,
+    index_increment: true,
+    jump_vx: false,
+    vf_reset: true,
+    wrap: false,
+    display_wait: false,
+    collision_rows: false,
+    key_release: true,
+    index_overflow: false,
+    memory_wrap: false,
+    sound_threshold: true,
+};
+
+#[test]
+fn save_states_round_trip() {
+    // Sets V0, calls a subroutine that draws the 0 glyph and waits for a key
+    let rom = [
+        0x60, 0x2A, 0x22, 0x06, 0x12, 0x04, 0xF0, 0x29, 0xD1, 0x15, 0xF1, 0x0A, 0x00, 0xEE,
+    ];
+    let mut core = Chip8Core::new(HardwareExecutionConfig::default());
+    core.load_rom(&rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+
+    let hardware = &core.emulator.hardware;
+    let state = SaveState::new(7, QUIRKS, &hardware.cpu, &hardware.framebuffer);
+    let loaded = SaveState::decode(&state.encode()).unwrap();
+    assert_eq!((loaded.rom_hash, loaded.saved_at), (7, state.saved_at));
+    assert_eq!(loaded.quirks, QUIRKS);
+    assert_eq!(loaded.framebuffer, hardware.framebuffer);
+    assert_eq!(loaded.cpu.get_pc(), hardware.cpu.get_pc());
+    assert_eq!(loaded.cpu.call_stack(), hardware.cpu.call_stack());
+    assert_eq!(
+        loaded.cpu.all_register_val(),
+        hardware.cpu.all_register_val()
+    );
+    assert!(loaded.cpu.is_waiting_for_key());
+}
+
+#[test]
+fn deep_call_stacks_round_trip() {
+    let mut cpu = CPU::new();
+    for depth in 0..300 {
+        cpu.push_stack(0x200 + depth * 2);
+    }
+    let state = SaveState::new(7, QUIRKS, &cpu, &Default::default());
+    let loaded = SaveState::decode(&state.encode()).unwrap();
+    assert_eq!(loaded.cpu.call_stack(), cpu.call_stack());
+}
+
+#[test]
+fn other_files_are_not_loaded_as_save_states() {
+    assert!(SaveState::decode(b"not a state").is_err());
+    let state = SaveState::new(7, QUIRKS, &Default::default(), &Default::default());
+    let mut bytes = state.encode();
+    bytes.pop();
+    assert!(SaveState::decode(&bytes).is_err());
+
+    // A byte flipped on disk
+    let mut bytes = state.encode();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    assert_eq!(
+        SaveState::decode(&bytes).err().as_deref(),
+        Some("the save state is damaged")
+    );
+}
+
+#[test]
+fn slots_only_load_states_of_their_rom() {
+    let dir = std::env::temp_dir().join(format!("chip8-savestates-{}", std::process::id()));
+    let mut slots = SaveSlots::new(dir.clone(), 1, QUIRKS);
+    slots.select(-1);
+    assert_eq!(slots.slot(), SaveSlots::COUNT - 1);
+    assert!(slots.load().unwrap().is_none());
+
+    // A state copied over from another ROM's slot
+    let other = SaveState::new(2, QUIRKS, &Default::default(), &Default::default());
+    other.save(&slots.path()).unwrap();
+    assert!(slots.load().is_err());
+
+    // One saved while running with a quirk changed
+    let mut quirks = QUIRKS;
+    quirks.set("wrap", true).unwrap();
+    let other = SaveState::new(1, quirks, &Default::default(), &Default::default());
+    other.save(&slots.path()).unwrap();
+    let error = slots.load().err().unwrap().to_string();
+    assert!(
+        error.ends_with("was saved with other quirks (wrap=on)"),
+        "{error}"
+    );
+
+    slots
+        .save(&Default::default(), &Default::default())
+        .unwrap();
+    assert_eq!(slots.load().unwrap().unwrap().rom_hash, 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn autosaves_are_resumed() {
+    let dir = std::env::temp_dir().join(format!("chip8-autosave-{}", std::process::id()));
+    let rom = [0x60, 0x2A, 0x12, 0x02]; // V0 = 42, then loops
+    let hardware = |autosave| {
+        let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+        hardware.load_rom(&rom).unwrap();
+        hardware.set_save_slots(SaveSlots::new(dir.clone(), 3, QUIRKS));
+        if autosave {
+            hardware.set_autosave_interval(Duration::from_secs(60));
+        }
+        hardware
+    };
+
+    // Nothing is written unless autosaving is on
+    let mut first = hardware(false);
+    first.autosave();
+    assert!(!hardware(false).resume_autosave());
+
+    let mut first = hardware(true);
+    let inst = first.current_instruction();
+    first.execute_instruction(&inst).unwrap();
+    first.autosave();
+
+    let mut second = hardware(false);
+    assert!(second.resume_autosave());
+    assert_eq!(second.cpu.register_val(&Register::new(0).unwrap()), 42);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn faulted_machines_are_not_autosaved() {
+    let dir = std::env::temp_dir().join(format!("chip8-autosave-fault-{}", std::process::id()));
+    let rom = [0x00, 0xEE]; // Returns with nothing to return to
+    let mut hardware = Hardware::new(HardwareExecutionConfig::default());
+    hardware.load_rom(&rom).unwrap();
+    hardware.set_save_slots(SaveSlots::new(dir.clone(), 3, QUIRKS));
+    hardware.set_autosave_interval(Duration::from_secs(60));
+    let inst = hardware.current_instruction();
+    let e = hardware.execute_instruction(&inst).unwrap_err();
+    hardware.stop_with_fault(e);
+    hardware.autosave();
+    assert!(!hardware.resume_autosave());
+    let _ = std::fs::remove_dir_all(dir);
+}
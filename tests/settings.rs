@@ -0,0 +1,62 @@
+use chip_8_emulator::frontend::ScreenColor;
+use chip_8_emulator::input::KeyboardLayout;
+use chip_8_emulator::settings::{Settings, SettingsFile};
+
+#[test]
+fn only_changed_settings_are_reloaded() {
+    let old = Settings::parse("color = \"yellow\"\ncpu_hz = 500").unwrap();
+    let new = Settings::parse("color = \"yellow\"\ncpu_hz = 700\nlayout = \"colemak\"").unwrap();
+    let changes = new.changes_from(&old).unwrap();
+    assert_eq!(changes.color, None);
+    assert_eq!(changes.cpu_hz, Some(700.0));
+    assert_eq!(changes.layout, Some(KeyboardLayout::Colemak));
+    assert_eq!(old.changes_from(&old), None);
+    assert_eq!(old.color, Some(ScreenColor::Yellow));
+}
+
+#[test]
+fn edits_to_the_file_are_noticed() {
+    let path = std::env::temp_dir().join(format!("chip8-settings-{}.toml", std::process::id()));
+    std::fs::write(&path, "show_diff = false").unwrap();
+    let mut file = SettingsFile::new(path.clone());
+    assert!(file.poll().unwrap().is_none());
+
+    // Typos are reported rather than silently ignored
+    std::fs::write(&path, "colour = \"white\"").unwrap();
+    filetime_bump(&path);
+    assert!(file.poll().is_err());
+
+    std::fs::write(&path, "show_diff = true").unwrap();
+    filetime_bump(&path);
+    let changes = file.poll().unwrap().unwrap();
+    assert_eq!(changes.show_diff, Some(true));
+    std::fs::remove_file(&path).unwrap();
+}
+
+// Moves the modification time forward, as writes in quick succession can share one
+fn filetime_bump(path: &std::path::Path) {
+    use std::time::{Duration, SystemTime};
+    static BUMPS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+    let bumps = BUMPS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let file = std::fs::File::options().write(true).open(path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(bumps))
+        .unwrap();
+}
+
+#[test]
+fn saving_keeps_the_rest_of_the_file() {
+    let changes = Settings {
+        color: Some(ScreenColor::DarkGreen),
+        cpu_hz: Some(600.0),
+        ..Default::default()
+    };
+    let text = changes.update_toml("# Mine\ncolor = \"red\"\nlayout = \"dvorak\"");
+    assert_eq!(
+        text,
+        "# Mine\ncolor = \"dark-green\"\nlayout = \"dvorak\"\ncpu_hz = 600\n"
+    );
+    assert_eq!(
+        Settings::parse(&text).unwrap().color,
+        Some(ScreenColor::DarkGreen)
+    );
+}
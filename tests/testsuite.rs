@@ -0,0 +1,34 @@
+use chip_8_emulator::chip8::Chip8Version;
+use chip_8_emulator::testsuite::{self, TestOutcome};
+
+#[test]
+fn failures_are_reported_by_line_of_text() {
+    let expected = "##..\n....\n#...\n#...\n....";
+    assert_eq!(testsuite::differing_lines(expected, expected), None);
+    assert_eq!(
+        testsuite::differing_lines(expected, "##..\n....\n#...\n.#..\n...."),
+        Some(vec![2])
+    );
+    // A stray pixel between lines counts against the line above
+    assert_eq!(
+        testsuite::differing_lines(expected, "##..\n..#.\n#...\n#...\n...."),
+        Some(vec![1])
+    );
+    assert_eq!(testsuite::differing_lines(expected, "##.."), Some(vec![]));
+}
+
+#[test]
+fn blessed_screens_are_the_pass_screens() {
+    let dir = std::env::temp_dir().join(format!("chip8-testsuite-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rom = dir.join("ibm.ch8");
+    std::fs::write(&rom, include_bytes!("../roms/IBM Logo.ch8")).unwrap();
+    let version = Chip8Version::Cosmac;
+
+    let check = |bless| testsuite::check(&rom, &version, 60, bless).unwrap();
+    assert_eq!(check(false), TestOutcome::NoPassScreen);
+    assert_eq!(check(true), TestOutcome::Blessed);
+    assert!(dir.join("ibm.ch8.cosmac.pass").exists());
+    assert_eq!(check(false), TestOutcome::Pass);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,113 @@
+use chip_8_emulator::audio::AudioPattern;
+use chip_8_emulator::chip8::Chip8Version;
+use chip_8_emulator::hardware::HardwareExecutionConfig;
+use chip_8_emulator::headless::{Chip8Core, InputSchedule};
+use chip_8_emulator::primitive::Register;
+
+fn run_xochip(rom: &[u8]) -> Chip8Core<'_> {
+    run_version(rom, Chip8Version::XoChip)
+}
+
+fn run_version(rom: &[u8], version: Chip8Version) -> Chip8Core<'_> {
+    let mut core = Chip8Core::new(HardwareExecutionConfig {
+        version,
+        ..Default::default()
+    });
+    core.load_rom(rom).unwrap();
+    core.run_frames(2, &InputSchedule::new());
+    core
+}
+
+#[test]
+fn sprites_are_drawn_to_the_selected_plane() {
+    // Select the second plane, then draw one pixel at (0, 0)
+    let rom = [
+        0xF2, 0x01, 0x60, 0x00, 0xA2, 0x0A, 0xD0, 0x01, 0x12, 0x08, 0x80,
+    ];
+    let core = run_xochip(&rom);
+
+    let frame = &core.emulator.hardware.framebuffer;
+    assert_eq!(frame.get_pixel(0, 0), Some(false));
+    assert_eq!(frame.color_index(0, 0), 2);
+    assert!(frame.to_text().starts_with("+."));
+}
+
+#[test]
+fn registers_are_saved_above_4k_and_skips_cover_long_loads() {
+    let rom = [
+        0x60, 0x11, 0x61, 0x22, // V0 = 0x11, V1 = 0x22
+        0xF0, 0x00, 0x30, 0x00, // I = 0x3000
+        0x50, 0x12, // Save V0-V1
+        0x60, 0x00, 0x61, 0x00, // Clear them
+        0x50, 0x13, // Load V0-V1
+        0x30, 0x11, // Skip the whole long load, as V0 = 0x11
+        0xF0, 0x00, 0x00, 0x00, // I = 0
+        0x12, 0x16,
+    ];
+    let core = run_xochip(&rom);
+
+    let cpu = &core.emulator.hardware.cpu;
+    assert_eq!(cpu.get_index(), 0x3000);
+    assert_eq!(cpu.load_from_addr(0x3001).unwrap(), 0x22);
+    assert_eq!(cpu.register_val(&Register::new(0).unwrap()), 0x11);
+    assert_eq!(cpu.register_val(&Register::new(1).unwrap()), 0x22);
+}
+
+#[test]
+fn audio_patterns_are_loaded_from_i_with_the_pitch() {
+    let mut rom = vec![
+        0xA2, 0x0A, // I = 0x20A
+        0x60, 0x70, 0xF0, 0x3A, // Pitch 112
+        0xF0, 0x02, // Load the pattern
+        0x12, 0x08,
+    ];
+    rom.extend([0xF0; 16]);
+    let core = run_xochip(&rom);
+
+    let pattern = core.emulator.hardware.audio_pattern().unwrap();
+    assert_eq!(pattern.samples, [0xF0; 16]);
+    assert_eq!(pattern.pitch, 112);
+    assert_eq!(pattern.sample_rate(), 8000.0);
+    assert_eq!(
+        pattern.bits().take(5).collect::<Vec<_>>(),
+        [true, true, true, true, false]
+    );
+
+    let default = AudioPattern {
+        samples: [0; 16],
+        pitch: 64,
+    };
+    assert_eq!(default.sample_rate(), 4000.0);
+}
+
+#[test]
+fn the_pitch_applies_to_the_pattern_already_loaded() {
+    let rom = [0xF0, 0x02, 0x60, 0x10, 0xF0, 0x3A, 0x12, 0x06];
+    let core = run_xochip(&rom);
+    assert_eq!(core.emulator.hardware.audio_pattern().unwrap().pitch, 0x10);
+}
+
+#[test]
+fn superchip_scrolls_work_on_xochip_too() {
+    // A pixel at (0, 0), scrolled down 2, right 8 and left 4
+    let rom = [
+        0x60, 0x00, 0xA2, 0x10, 0xD0, 0x01, 0x00, 0xC2, 0x00, 0xFB, 0x00, 0xFB, 0x00, 0xFC, 0x12,
+        0x0E, 0x80,
+    ];
+    for version in [Chip8Version::Superchip, Chip8Version::XoChip] {
+        let core = run_version(&rom, version.clone());
+        let frame = &core.emulator.hardware.framebuffer;
+        assert_eq!(frame.get_pixel(4, 2), Some(true), "{version}");
+        assert_eq!(
+            frame.pixels().iter().filter(|&&on| on).count(),
+            1,
+            "{version}"
+        );
+    }
+    // Not on the original
+    let core = run_version(&rom, Chip8Version::Cosmac);
+    assert_eq!(
+        core.emulator.hardware.framebuffer.get_pixel(0, 0),
+        Some(true)
+    );
+}